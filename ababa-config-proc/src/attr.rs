@@ -0,0 +1,163 @@
+//! parsing of the `#[ababa(...)]` attributes
+
+use syn::{
+    Attribute, ExprPath, ExprRange, Field, GenericArgument, Ident, LitStr, PathArguments, Type,
+};
+
+/// attributes of the struct or enum itself
+#[derive(Default)]
+pub struct ContainerAttrs {
+    pub deny_unknown_fields: bool,
+}
+
+impl ContainerAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut container = ContainerAttrs::default();
+
+        for attr in ababa_attrs(attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("deny_unknown_fields") {
+                    container.deny_unknown_fields = true;
+                } else {
+                    return Err(meta.error("unknown ababa attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(container)
+    }
+}
+
+/// attributes of a single field
+#[derive(Default)]
+pub struct FieldAttrs {
+    /// `#[ababa(default)]` is `Some(None)`, `#[ababa(default = "path")]` is `Some(Some(path))`
+    pub default: Option<Option<ExprPath>>,
+    pub rename: Option<String>,
+    pub aliases: Vec<String>,
+    pub skip: bool,
+    pub flatten: bool,
+    /// `#[ababa(range = 0.0..)]`
+    pub range: Option<ExprRange>,
+    pub nonzero: bool,
+    /// `#[ababa(unit = "angle")]`, the name of the variant of `AbabaUnit`
+    pub unit: Option<Ident>,
+    /// `#[ababa(validate = "path")]`, called with a reference to the converted value
+    pub validate: Option<ExprPath>,
+}
+
+impl FieldAttrs {
+    pub fn parse(field: &Field) -> syn::Result<Self> {
+        let mut attrs = FieldAttrs::default();
+
+        for attr in ababa_attrs(&field.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("default") {
+                    attrs.default = match meta.value() {
+                        Ok(value) => Some(Some(value.parse::<LitStr>()?.parse()?)),
+                        Err(_) => Some(None),
+                    };
+                } else if meta.path.is_ident("rename") {
+                    attrs.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("alias") {
+                    attrs.aliases.push(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("skip") {
+                    attrs.skip = true;
+                } else if meta.path.is_ident("flatten") {
+                    attrs.flatten = true;
+                } else if meta.path.is_ident("range") {
+                    attrs.range = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("nonzero") {
+                    attrs.nonzero = true;
+                } else if meta.path.is_ident("unit") {
+                    let unit = meta.value()?.parse::<LitStr>()?;
+                    let variant = match unit.value().as_str() {
+                        "angle" => "Angle",
+                        "length" => "Length",
+                        _ => return Err(meta.error("expected `\"angle\"` or `\"length\"`")),
+                    };
+                    attrs.unit = Some(Ident::new(variant, unit.span()));
+                } else if meta.path.is_ident("validate") {
+                    attrs.validate = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                } else {
+                    return Err(meta.error("unknown ababa attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        if attrs.flatten && (attrs.rename.is_some() || !attrs.aliases.is_empty()) {
+            return Err(syn::Error::new_spanned(
+                field,
+                "flattened fields can't be renamed",
+            ));
+        }
+
+        let constrained = attrs.range.is_some()
+            || attrs.nonzero
+            || attrs.unit.is_some()
+            || attrs.validate.is_some();
+        if constrained && (attrs.flatten || attrs.skip) {
+            return Err(syn::Error::new_spanned(
+                field,
+                "flattened and skipped fields can't have constraints",
+            ));
+        }
+
+        Ok(attrs)
+    }
+}
+
+fn ababa_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|a| a.path().is_ident("ababa"))
+}
+
+/// whether the type is an `Option<..>`. only looks at the name, so this also matches other types
+/// called `Option`, but there's no way to know better inside of a macro.
+pub fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// `T` of an `Option<T>`, see [is_option]
+pub fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+
+    match args.args.first() {
+        Some(GenericArgument::Type(inner)) if segment.ident == "Option" => Some(inner),
+        _ => None,
+    }
+}
+
+/// `T` of a `Box<T>`, unless it's a trait object. only looks at the name, like [is_option].
+///
+/// `TryFrom<AbabaValue>` can't be implemented for `Box<T>` in general, so the derive reads these
+/// fields as `T` and boxes them. trait objects need their own impl for the box.
+pub fn box_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+
+    match args.args.first() {
+        Some(GenericArgument::Type(Type::TraitObject(_))) => None,
+        Some(GenericArgument::Type(inner)) if segment.ident == "Box" => Some(inner),
+        _ => None,
+    }
+}
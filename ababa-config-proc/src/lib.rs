@@ -1,71 +1,578 @@
 use proc_macro::TokenStream;
 
+use crate::attr::{box_inner, is_option, option_inner, ContainerAttrs, FieldAttrs};
+use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use syn::spanned::Spanned;
-use syn::{parse_macro_input, Data, DeriveInput, Fields};
+use syn::{
+    parse_macro_input, Data, DataEnum, DeriveInput, Fields, FieldsNamed, FieldsUnnamed, Generics,
+    Type,
+};
 
-#[proc_macro_derive(AbabaDeserialize)]
+mod attr;
+mod schema;
+mod ser;
+
+/// derives [TryFrom]<AbabaValue>.
+///
+/// - structs with named fields are read from objects (`Name { field: value }`), the type of the
+///   object has to match the name of the struct if it is specified.
+/// - tuple structs are read from tuples (`Name(a, b)`), the type is optional as well.
+/// - newtypes (`struct Name(T)`) are transparent, they accept everything `T` accepts.
+/// - unit structs are read from their name: `Name`, or `Name {}` and `Name()`.
+/// - enums pick the variant with the type of the value:
+///   - struct variants are read from objects: `Variant { field: value }`
+///   - tuple variants are read from typed tuples: `Variant(a, b)`
+///   - variants with a single field also accept everything the field accepts, as long as it
+///     is tagged with the variant name: `Variant { .. }` is passed on to the field
+///   - unit variants are read from lone idents: `Variant`, or `Variant {}` and `Variant()`
+///
+/// named fields can be customized with `#[ababa(...)]` attributes:
+///
+/// - `default`: use [Default::default] if the field is missing
+/// - `default = "path"`: call the function `path` if the field is missing
+/// - `rename = "name"`: read the field from `name` instead of the name of the rust field
+/// - `alias = "name"`: also accept `name`, can be used multiple times
+/// - `skip`: never read the field, always use the default
+/// - `flatten`: read the fields of the field's type from the surrounding object, the type has to
+///   implement `AbabaFromFields` (which is derived for structs with named fields)
+/// - `range = 0.0..`: the number has to be in the range, any rust range of numbers works
+/// - `nonzero`: the number, or at least one number of a tuple (like a vector), must not be zero
+/// - `unit = "angle"` or `unit = "length"`: numbers written with a unit (like `45deg` or `10cm`)
+///   must have this one, also the numbers of tuples and lists. numbers without a unit are fine.
+/// - `validate = "path"`: call `path(&value) -> Result<(), String>` with the converted value, the
+///   `Err` message is reported. for `Option<T>` fields it's called with the `T`, if there is one
+///
+/// violated constraints are an `AbabaParseError::ConstraintViolated` (`UnitMismatch` for units)
+/// at the path of the field.
+///
+/// fields of type `Option<T>` are implicitly optional and `None` if missing or written as `None`.
+/// fields of type `Box<T>` are read as `T`, since the blanket impl isn't possible for a box.
+/// `Box<dyn Trait>` needs its own impl.
+///
+/// tuple variants need exactly as many items as they have fields.
+///
+/// fields that aren't read by any rust field are ignored, unless the struct or enum has the
+/// `#[ababa(deny_unknown_fields)]` attribute. then they're an error, which suggests the closest
+/// field name in case of a typo.
+///
+/// generic type parameters need to implement [TryFrom]<AbabaValue>, the bound is added to the
+/// generated impl together with the where clause of the type.
+///
+/// `AbabaDescribe` is derived as well, its schema describes all of the above and can validate a
+/// value without stopping at the first error. it requires all field types to implement
+/// `AbabaDescribe` too. the schema of recursive types can't be built, it would never end.
+#[proc_macro_derive(AbabaDeserialize, attributes(ababa))]
 pub fn derive_deser_attr(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
+    match derive(&input) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// derives `AbabaSerialize`, the other direction of [AbabaDeserialize].
+///
+/// structs and struct variants become typed objects, tuple variants typed tuples and unit
+/// variants idents, so that the derived [TryFrom]<AbabaValue> reads them back into the same value.
+/// variants with a single field are wrapped in a tuple: `Variant(value,)`. tuple structs become
+/// typed tuples and unit structs idents, newtypes are written as their field.
+///
+/// the field attributes of [AbabaDeserialize] are respected: `rename` is the name that is written,
+/// `skip` fields aren't written, `flatten` fields write their fields into the surrounding object.
+/// fields of type `Option<T>` are left out if they're `None`, unless they have a `default = ".."`
+/// function, which would turn the missing field into its result.
+#[proc_macro_derive(AbabaSerialize, attributes(ababa))]
+pub fn derive_ser(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match ser::derive(&input) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn derive(input: &DeriveInput) -> syn::Result<TokenStream2> {
     let struct_name = &input.ident;
-    let Data::Struct(struc) = input.data else {
-        return syn::Error::new(input.span(), "Can only implement resource on struct")
-            .to_compile_error()
-            .into();
-    };
+    let container = ContainerAttrs::parse(&input.attrs)?;
+    let generics = bounded(
+        input,
+        quote!(TryFrom<ababa_config::AbabaValue, Error = ababa_config::AbabaParseError>),
+    );
+
+    let (body, extra_impls) = match &input.data {
+        Data::Struct(struc) => match &struc.fields {
+            Fields::Named(fields) => {
+                let constructor = named_constructor(quote!(Self), fields)?;
+                let field_names = field_names(fields)?;
+                let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+                let from_fields = quote! {
+                    impl #impl_generics ababa_config::AbabaFromFields
+                        for #struct_name #ty_generics #where_clause
+                    {
+                        fn from_fields(
+                            fields: &mut std::collections::HashMap<String, ababa_config::AbabaValue>,
+                            span: ababa_config::Span,
+                        ) -> Result<Self, ababa_config::AbabaParseError> {
+                            Ok(#constructor)
+                        }
+
+                        fn field_names() -> Option<Vec<&'static str>> {
+                            #field_names
+                        }
+                    }
+                };
 
-    let Fields::Named(fields) = struc.fields else {
-        return syn::Error::new(
-            struc.fields.span(),
-            "Can only implement resource on struct with named fields",
-        )
-        .to_compile_error()
-        .into();
+                let check = match container.deny_unknown_fields {
+                    true => unknown_fields_check(quote! {
+                        <Self as ababa_config::AbabaFromFields>::field_names()
+                    }),
+                    false => quote!(),
+                };
+
+                let describe = schema::describe_struct(input, fields, &container)?;
+                (
+                    struct_body(struct_name, check),
+                    quote!(#from_fields #describe),
+                )
+            }
+            // transparent, the struct accepts everything the field accepts
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                let value = boxed(&fields.unnamed[0].ty, quote!(value.try_into()?));
+                (
+                    quote!(Ok(Self(#value))),
+                    schema::describe_newtype(input, &fields.unnamed[0].ty),
+                )
+            }
+            Fields::Unnamed(fields) => (
+                tuple_struct_body(struct_name, fields),
+                schema::describe_tuple_struct(input, fields),
+            ),
+            Fields::Unit => {
+                let name = struct_name.to_string();
+                let body = unit_body(quote!(Self));
+                (
+                    quote! {
+                        match value.struct_type() {
+                            Some(#name) => #body,
+                            struct_type => Err(ababa_config::AbabaParseError::UnknownVariant {
+                                expected: vec![#name],
+                                got: struct_type.map(str::to_string),
+                                span,
+                            }),
+                        }
+                    },
+                    schema::describe_unit_struct(input),
+                )
+            }
+        },
+        Data::Enum(enu) => (
+            enum_body(enu, &container)?,
+            schema::describe_enum(input, enu, &container)?,
+        ),
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                input.span(),
+                "Can only implement resource on struct or enum",
+            ));
+        }
     };
 
-    let each_field: Vec<_> = fields
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics TryFrom<ababa_config::AbabaValue>
+            for #struct_name #ty_generics #where_clause
+        {
+            type Error = ababa_config::AbabaParseError;
+
+            fn try_from(value: ababa_config::AbabaValue) -> Result<Self, Self::Error> {
+                let span = value.span;
+                #body
+            }
+        }
+
+        #extra_impls
+    })
+}
+
+// the generics of the input, with `bound` added to every type parameter
+fn bounded(input: &DeriveInput, bound: TokenStream2) -> Generics {
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(syn::parse_quote!(#bound));
+    }
+    generics
+}
+
+// `Name(a, b)` or `(a, b)`, expects `value` and `span` in scope
+fn tuple_struct_body(struct_name: &syn::Ident, fields: &FieldsUnnamed) -> TokenStream2 {
+    let constructor = unnamed_constructor(quote!(Self), fields);
+    quote! {
+        match value.kind {
+            ababa_config::AbabaValueKind::Tuple { struct_type, items } => {
+                if struct_type.as_ref().is_some_and(|t| t != stringify!(#struct_name)) {
+                    return Err(ababa_config::AbabaParseError::StructTypeDidNotMatch {
+                        expected: stringify!(#struct_name),
+                        got: struct_type,
+                        span,
+                    });
+                }
+
+                Ok(#constructor)
+            }
+            kind => Err(ababa_config::AbabaParseError::ValueTypeDidNotMatch {
+                expected: "Tuple",
+                got: ababa_config::AbabaValue::new(kind, span),
+            }),
+        }
+    }
+}
+
+// expects `value` and `span` in scope, `check` is run before reading the fields
+fn struct_body(struct_name: &syn::Ident, check: TokenStream2) -> TokenStream2 {
+    quote! {
+        match value.kind {
+            ababa_config::AbabaValueKind::Object {
+                struct_type,
+                mut fields,
+            } => {
+                if struct_type.as_ref().is_some_and(|t| t != stringify!(#struct_name)) {
+                    return Err(ababa_config::AbabaParseError::StructTypeDidNotMatch {
+                        expected: stringify!(#struct_name),
+                        got: struct_type,
+                        span,
+                    });
+                }
+
+                #check
+                <Self as ababa_config::AbabaFromFields>::from_fields(&mut fields, span)
+            }
+            kind => Err(ababa_config::AbabaParseError::ValueTypeDidNotMatch {
+                expected: "Object",
+                got: ababa_config::AbabaValue::new(kind, span),
+            }),
+        }
+    }
+}
+
+// expects `value` and `span` in scope
+fn enum_body(enu: &DataEnum, container: &ContainerAttrs) -> syn::Result<TokenStream2> {
+    let names: Vec<_> = enu.variants.iter().map(|v| v.ident.to_string()).collect();
+
+    let arms = enu.variants.iter().map(|variant| -> syn::Result<_> {
+        let ident = &variant.ident;
+        let name = ident.to_string();
+        let path = quote!(Self::#ident);
+
+        let body = match &variant.fields {
+            Fields::Named(fields) => {
+                let constructor = named_constructor(path, fields)?;
+                let check = match container.deny_unknown_fields {
+                    true => unknown_fields_check(field_names(fields)?),
+                    false => quote!(),
+                };
+                quote! {
+                    match value.kind {
+                        ababa_config::AbabaValueKind::Object { mut fields, .. } => {
+                            #check
+                            let fields = &mut fields;
+                            Ok(#constructor)
+                        }
+                        kind => Err(ababa_config::AbabaParseError::ValueTypeDidNotMatch {
+                            expected: "Object",
+                            got: ababa_config::AbabaValue::new(kind, span),
+                        }),
+                    }
+                }
+            }
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                newtype_body(path, &fields.unnamed[0].ty)
+            }
+            Fields::Unnamed(fields) => {
+                let constructor = unnamed_constructor(path, fields);
+                quote! {
+                    match value.kind {
+                        ababa_config::AbabaValueKind::Tuple { items, .. } => Ok(#constructor),
+                        kind => Err(ababa_config::AbabaParseError::ValueTypeDidNotMatch {
+                            expected: "Tuple",
+                            got: ababa_config::AbabaValue::new(kind, span),
+                        }),
+                    }
+                }
+            }
+            Fields::Unit => unit_body(path),
+        };
+
+        Ok(quote! {
+            Some(#name) => #body,
+        })
+    });
+    let arms = arms.collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        match value.struct_type() {
+            #(#arms)*
+            struct_type => Err(ababa_config::AbabaParseError::UnknownVariant {
+                expected: vec![#(#names),*],
+                got: struct_type.map(str::to_string),
+                span,
+            }),
+        }
+    })
+}
+
+// `Name`, `Name {}` or `Name()`, the name is checked by the caller
+fn unit_body(path: TokenStream2) -> TokenStream2 {
+    quote! {
+        match value.kind {
+            ababa_config::AbabaValueKind::Ident(_) => Ok(#path),
+            ababa_config::AbabaValueKind::Object { fields, .. } if fields.is_empty() => {
+                Ok(#path)
+            }
+            ababa_config::AbabaValueKind::Tuple { items, .. } if items.is_empty() => {
+                Ok(#path)
+            }
+            kind => Err(ababa_config::AbabaParseError::ValueTypeDidNotMatch {
+                expected: "Ident",
+                got: ababa_config::AbabaValue::new(kind, span),
+            }),
+        }
+    }
+}
+
+// a single field variant is transparent: a typed tuple with one element is unwrapped, anything
+// else is passed on without the variant name
+fn newtype_body(path: TokenStream2, ty: &Type) -> TokenStream2 {
+    let item = boxed(ty, quote!(item.try_into()?));
+    let tuple = boxed(
+        ty,
+        quote! {
+            ababa_config::AbabaValue::new(
+                ababa_config::AbabaValueKind::Tuple {
+                    struct_type: None,
+                    items,
+                },
+                span,
+            )
+            .try_into()?
+        },
+    );
+    let object = boxed(
+        ty,
+        quote! {
+            ababa_config::AbabaValue::new(
+                ababa_config::AbabaValueKind::Object {
+                    struct_type: None,
+                    fields,
+                },
+                span,
+            )
+            .try_into()?
+        },
+    );
+
+    quote! {
+        match value.kind {
+            ababa_config::AbabaValueKind::Tuple { items, .. } if items.len() == 1 => {
+                let item = items.into_iter().next().expect("checked length");
+                Ok(#path(#item))
+            }
+            ababa_config::AbabaValueKind::Tuple { items, .. } => Ok(#path(#tuple)),
+            ababa_config::AbabaValueKind::Object { fields, .. } => Ok(#path(#object)),
+            kind => Err(ababa_config::AbabaParseError::ValueTypeDidNotMatch {
+                expected: "Tuple",
+                got: ababa_config::AbabaValue::new(kind, span),
+            }),
+        }
+    }
+}
+
+// `Box::new(value)` for `Box<T>` fields (see `box_inner`), so `value` is converted to `T`.
+// `Option<Box<T>>` is converted to `Option<T>` and boxed if it's `Some`.
+fn boxed(ty: &Type, value: TokenStream2) -> TokenStream2 {
+    if box_inner(ty).is_some() {
+        return quote!(Box::new(#value));
+    }
+
+    match option_inner(ty).and_then(box_inner) {
+        Some(_) => quote! {{
+            let value: Option<_> = #value;
+            value.map(Box::new)
+        }},
+        None => value,
+    }
+}
+
+// `path { field: .., .. }`, expects `fields` (a `&mut HashMap`) and `span` in scope
+fn named_constructor(path: TokenStream2, fields: &FieldsNamed) -> syn::Result<TokenStream2> {
+    let each_field = fields
         .named
-        .into_iter()
-        .filter_map(|f| f.ident)
+        .iter()
         .map(|f| {
-            quote! {
-                #f: fields.remove(stringify!(#f))
-                    .ok_or(ababa_config::AbabaParseError::StructFieldNotPresent { field: stringify!(#f) })?
-                    .try_into()?
+            let ident = f.ident.as_ref().expect("named fields have idents");
+            let attrs = FieldAttrs::parse(f)?;
+
+            let default = match &attrs.default {
+                Some(Some(default)) => Some(quote!(#default())),
+                Some(None) => Some(quote!(Default::default())),
+                None => None,
+            };
+
+            if attrs.skip {
+                let default = default.unwrap_or(quote!(Default::default()));
+                return Ok(quote!(#ident: #default));
             }
-        })
-        .collect();
 
-    let expanded = quote! {
-        impl TryFrom<ababa_config::AbabaValue> for #struct_name {
-            type Error = ababa_config::AbabaParseError;
+            if attrs.flatten {
+                return Ok(quote! {
+                    #ident: ababa_config::AbabaFromFields::from_fields(fields, span)?
+                });
+            }
 
-            fn try_from(value: ababa_config::AbabaValue) -> Result<Self, Self::Error> {
-                match value {
-                    ababa_config::AbabaValue::Object {
-                        struct_type,
-                        mut fields,
-                    } => {
-                        if struct_type.as_ref().is_some_and(|t| t != stringify!(#struct_name)) {
-                            return Err(ababa_config::AbabaParseError::StructTypeDidNotMatch {
-                                expected: stringify!(#struct_name),
-                                got: struct_type,
-                            });
+            let name = attrs.rename.clone().unwrap_or_else(|| ident.to_string());
+            let aliases = &attrs.aliases;
+
+            let constraints = schema::constraints(&attrs);
+            // the constraints are checked before converting, `validate` after. for Option<T> fields
+            // it only validates T.
+            let validate = attrs.validate.as_ref().map(|validate| {
+                let validate = quote! {
+                    if let Err(message) = #validate(value) {
+                        return Err(ababa_config::AbabaParseError::ConstraintViolated {
+                            message,
+                            span: value_span,
                         }
+                        .in_field(key));
+                    }
+                };
+                match is_option(&f.ty) {
+                    true => quote!(if let Some(value) = &value { #validate }),
+                    false => quote!({ let value = &value; #validate }),
+                }
+            });
+            let missing = match default {
+                Some(default) => default,
+                None if is_option(&f.ty) => quote!(None),
+                None => quote! {
+                    return Err(ababa_config::AbabaParseError::StructFieldNotPresent {
+                        field: #name,
+                        span,
+                    })
+                },
+            };
+            // present Option<T> fields can still be `None`, Box<T> fields are read as T (see `boxed`)
+            let ty = &f.ty;
+            let target = match (box_inner(ty), option_inner(ty).and_then(box_inner)) {
+                (Some(inner), _) => quote!(#inner),
+                (None, Some(inner)) => quote!(Option<#inner>),
+                (None, None) => quote!(#ty),
+            };
+            let present = boxed(ty, quote!(value));
 
-                        Ok(Self {
-                            #(#each_field), *
-                        })
+            Ok(quote! {
+                #ident: match ababa_config::take_field(fields, &[#name, #(#aliases),*])? {
+                    Some((key, value)) => {
+                        #(
+                            ababa_config::AbabaConstraint::check(&#constraints, &value)
+                                .map_err(|e| e.in_field(&key))?;
+                        )*
+                        let value_span = value.span;
+                        let value = <#target>::try_from(value)
+                            .map_err(|e: ababa_config::AbabaParseError| e.in_field(&key))?;
+                        #validate
+                        #present
                     }
-                    _ => Err(ababa_config::AbabaParseError::ValueTypeDidNotMatch {
-                        expected: "Object",
-                        got: value,
-                    }),
+                    None => #missing,
                 }
-            }
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        #path {
+            #(#each_field), *
         }
-    };
+    })
+}
+
+// expression of type `Option<Vec<&'static str>>` with the names of all fields that are read,
+// including flattened ones. `None` if any field is accepted, e.g. because of a flattened map.
+fn field_names(fields: &FieldsNamed) -> syn::Result<TokenStream2> {
+    let mut names = Vec::new();
+    let mut flattened = Vec::new();
 
-    TokenStream::from(expanded)
+    for f in &fields.named {
+        let attrs = FieldAttrs::parse(f)?;
+        if attrs.skip {
+            continue;
+        }
+        if attrs.flatten {
+            flattened.push(&f.ty);
+            continue;
+        }
+
+        let ident = f.ident.as_ref().expect("named fields have idents");
+        names.push(attrs.rename.unwrap_or_else(|| ident.to_string()));
+        names.extend(attrs.aliases);
+    }
+
+    Ok(quote! {{
+        let names: Option<Vec<&'static str>> = Some(vec![#(#names),*]);
+        #(
+            let names = names
+                .zip(<#flattened as ababa_config::AbabaFromFields>::field_names())
+                .map(|(mut names, flattened)| {
+                    names.extend(flattened);
+                    names
+                });
+        )*
+        names
+    }})
+}
+
+// errors on fields that aren't in `names` (see `field_names`), expects `fields` in scope
+fn unknown_fields_check(names: TokenStream2) -> TokenStream2 {
+    quote! {
+        if let Some(names) = #names {
+            ababa_config::deny_unknown_fields(&fields, &names)?;
+        }
+    }
+}
+
+// `path(.., ..)`, expects `items` and `span` in scope
+fn unnamed_constructor(path: TokenStream2, fields: &FieldsUnnamed) -> TokenStream2 {
+    let len = fields.unnamed.len();
+    let expected = len as i32;
+    let each_field = fields.unnamed.iter().enumerate().map(|(i, f)| {
+        let item = quote! {
+            items
+                .next()
+                .expect("the length was checked")
+                .try_into()
+                .map_err(|e: ababa_config::AbabaParseError| e.at_index(#i))?
+        };
+        boxed(&f.ty, item)
+    });
+
+    quote! {
+        {
+            if items.len() < #len {
+                return Err(ababa_config::AbabaParseError::NotEnoughElements {
+                    expected: #expected,
+                    got: items.len(),
+                    span,
+                });
+            }
+            if items.len() > #len {
+                return Err(ababa_config::AbabaParseError::TooManyElements {
+                    expected: #len,
+                    got: items.len(),
+                    span,
+                });
+            }
+            let mut items = items.into_iter();
+            #path(#(#each_field),*)
+        }
+    }
 }
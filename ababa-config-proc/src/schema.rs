@@ -0,0 +1,246 @@
+//! the `AbabaDescribe` impl generated by `#[derive(AbabaDeserialize)]`, describing the values the
+//! generated [TryFrom]<AbabaValue> impl accepts
+
+use crate::attr::{option_inner, ContainerAttrs, FieldAttrs};
+use crate::bounded;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, ToTokens};
+use syn::{DataEnum, DeriveInput, Fields, FieldsNamed, FieldsUnnamed, RangeLimits, Type};
+
+pub fn describe_struct(
+    input: &DeriveInput,
+    fields: &FieldsNamed,
+    container: &ContainerAttrs,
+) -> syn::Result<TokenStream2> {
+    let schema = struct_schema(&input.ident.to_string(), fields, container)?;
+    Ok(describe(
+        input,
+        quote!(ababa_config::AbabaSchema::Struct(#schema)),
+    ))
+}
+
+pub fn describe_enum(
+    input: &DeriveInput,
+    enu: &DataEnum,
+    container: &ContainerAttrs,
+) -> syn::Result<TokenStream2> {
+    let name = input.ident.to_string();
+    let variants = enu
+        .variants
+        .iter()
+        .map(|variant| {
+            let name = variant.ident.to_string();
+            Ok(match &variant.fields {
+                Fields::Named(fields) => {
+                    let schema = struct_schema(&name, fields, container)?;
+                    quote!(ababa_config::AbabaVariantSchema::Struct(#schema))
+                }
+                Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                    let ty = &fields.unnamed[0].ty;
+                    quote! {
+                        ababa_config::AbabaVariantSchema::Newtype {
+                            name: #name,
+                            inner: Box::new(<#ty as ababa_config::AbabaDescribe>::schema()),
+                        }
+                    }
+                }
+                Fields::Unnamed(fields) => {
+                    let items = item_schemas(fields);
+                    quote! {
+                        ababa_config::AbabaVariantSchema::Tuple {
+                            name: #name,
+                            items: vec![#(#items),*],
+                        }
+                    }
+                }
+                Fields::Unit => quote!(ababa_config::AbabaVariantSchema::Unit { name: #name }),
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(describe(
+        input,
+        quote! {
+            ababa_config::AbabaSchema::Enum {
+                name: #name,
+                variants: vec![#(#variants),*],
+            }
+        },
+    ))
+}
+
+/// newtypes have the schema of their field
+pub fn describe_newtype(input: &DeriveInput, ty: &Type) -> TokenStream2 {
+    describe(
+        input,
+        quote!(<#ty as ababa_config::AbabaDescribe>::schema()),
+    )
+}
+
+pub fn describe_tuple_struct(input: &DeriveInput, fields: &FieldsUnnamed) -> TokenStream2 {
+    let items = item_schemas(fields);
+    describe(
+        input,
+        quote!(ababa_config::AbabaSchema::Tuple(vec![#(#items),*])),
+    )
+}
+
+/// like an enum with a single unit variant, which is read the same way
+pub fn describe_unit_struct(input: &DeriveInput) -> TokenStream2 {
+    let name = input.ident.to_string();
+    describe(
+        input,
+        quote! {
+            ababa_config::AbabaSchema::Enum {
+                name: #name,
+                variants: vec![ababa_config::AbabaVariantSchema::Unit { name: #name }],
+            }
+        },
+    )
+}
+
+fn describe(input: &DeriveInput, schema: TokenStream2) -> TokenStream2 {
+    let ident = &input.ident;
+    let generics = bounded(input, quote!(ababa_config::AbabaDescribe));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics ababa_config::AbabaDescribe for #ident #ty_generics #where_clause {
+            fn schema() -> ababa_config::AbabaSchema {
+                #schema
+            }
+        }
+    }
+}
+
+// expression of type `AbabaStructSchema`, mirrors `named_constructor` and `field_names`
+fn struct_schema(
+    name: &str,
+    fields: &FieldsNamed,
+    container: &ContainerAttrs,
+) -> syn::Result<TokenStream2> {
+    let deny_unknown_fields = container.deny_unknown_fields;
+    let mut each_field = Vec::new();
+
+    for f in &fields.named {
+        let attrs = FieldAttrs::parse(f)?;
+        let ty = &f.ty;
+
+        if attrs.skip {
+            continue;
+        }
+        if attrs.flatten {
+            each_field.push(quote! {
+                schema.flatten(<#ty as ababa_config::AbabaDescribe>::schema());
+            });
+            continue;
+        }
+
+        let ident = f.ident.as_ref().expect("named fields have idents");
+        let name = attrs.rename.clone().unwrap_or_else(|| ident.to_string());
+        let aliases = &attrs.aliases;
+        let rust_type = type_string(ty);
+        // Option<T> fields are read as T
+        let inner = option_inner(ty);
+        let required = attrs.default.is_none() && inner.is_none();
+        let ty = inner.unwrap_or(ty);
+        let default = match &attrs.default {
+            Some(Some(path)) => {
+                let path = path_string(path);
+                quote!(Some(#path))
+            }
+            Some(None) => quote!(Some("Default::default")),
+            None => quote!(None),
+        };
+
+        let mut constraints = constraints(&attrs);
+        if let Some(validate) = &attrs.validate {
+            let name = path_string(validate);
+            constraints.push(quote!(ababa_config::AbabaConstraint::Validate { name: #name }));
+        }
+
+        each_field.push(quote! {
+            schema.fields.push(ababa_config::AbabaFieldSchema {
+                name: #name,
+                aliases: vec![#(#aliases),*],
+                ty: #rust_type,
+                schema: <#ty as ababa_config::AbabaDescribe>::schema(),
+                required: #required,
+                default: #default,
+                constraints: vec![#(#constraints),*],
+            });
+        });
+    }
+
+    Ok(quote! {{
+        let mut schema = ababa_config::AbabaStructSchema::new(#name);
+        schema.deny_unknown_fields = #deny_unknown_fields;
+        #(#each_field)*
+        schema
+    }})
+}
+
+fn item_schemas(fields: &FieldsUnnamed) -> impl Iterator<Item = TokenStream2> + '_ {
+    fields.unnamed.iter().map(|f| {
+        let ty = &f.ty;
+        quote!(<#ty as ababa_config::AbabaDescribe>::schema())
+    })
+}
+
+/// expressions of type `AbabaConstraint` for the constraints that can be checked before the value
+/// is converted, `validate` is called by the generated code itself
+pub fn constraints(attrs: &FieldAttrs) -> Vec<TokenStream2> {
+    let mut constraints = Vec::new();
+
+    if let Some(range) = &attrs.range {
+        let bound = |bound: &Option<Box<syn::Expr>>| match bound {
+            Some(bound) => quote!(Some((#bound) as f64)),
+            None => quote!(None),
+        };
+        let min = bound(&range.start);
+        let max = bound(&range.end);
+        let inclusive = matches!(range.limits, RangeLimits::Closed(_));
+        constraints.push(quote! {
+            ababa_config::AbabaConstraint::Range {
+                min: #min,
+                max: #max,
+                inclusive: #inclusive,
+            }
+        });
+    }
+    if attrs.nonzero {
+        constraints.push(quote!(ababa_config::AbabaConstraint::NonZero));
+    }
+    if let Some(unit) = &attrs.unit {
+        constraints
+            .push(quote!(ababa_config::AbabaConstraint::Unit(ababa_config::AbabaUnit::#unit)));
+    }
+
+    constraints
+}
+
+// the type like it's usually written, the token stream has spaces between all tokens
+fn type_string(ty: &Type) -> String {
+    let mut s = ty.to_token_stream().to_string();
+    for (spaced, tight) in [
+        (" <", "<"),
+        ("< ", "<"),
+        (" >", ">"),
+        (" ,", ","),
+        (" ;", ";"),
+        (" :: ", "::"),
+        (":: ", "::"),
+        ("& ", "&"),
+        ("[ ", "["),
+        (" ]", "]"),
+        ("( ", "("),
+        (" )", ")"),
+    ] {
+        s = s.replace(spaced, tight);
+    }
+    s
+}
+
+// `path::to::function` without the spaces of the token stream
+fn path_string(path: &syn::ExprPath) -> String {
+    path.to_token_stream().to_string().replace(' ', "")
+}
@@ -0,0 +1,166 @@
+//! `#[derive(AbabaSerialize)]`, the counterpart of the generated [TryFrom]<AbabaValue> impls
+
+use crate::attr::{is_option, FieldAttrs};
+use crate::bounded;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{Data, DataEnum, DeriveInput, Fields, FieldsNamed};
+
+pub fn derive(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let struct_name = &input.ident;
+
+    let body = match &input.data {
+        Data::Struct(struc) => match &struc.fields {
+            Fields::Named(fields) => {
+                let inserts = insert_fields(fields, |ident| quote!(&self.#ident))?;
+                object(&struct_name.to_string(), inserts)
+            }
+            // newtypes are transparent
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                quote!(ababa_config::AbabaSerialize::to_ababa(&self.0))
+            }
+            Fields::Unnamed(fields) => {
+                let name = struct_name.to_string();
+                let items = (0..fields.unnamed.len()).map(syn::Index::from);
+                quote! {
+                    ababa_config::AbabaValueKind::Tuple {
+                        struct_type: Some(#name.to_string()),
+                        items: vec![#(ababa_config::AbabaSerialize::to_ababa(&self.#items)),*],
+                    }
+                    .into()
+                }
+            }
+            Fields::Unit => {
+                let name = struct_name.to_string();
+                quote!(ababa_config::AbabaValueKind::Ident(#name.to_string()).into())
+            }
+        },
+        Data::Enum(enu) => enum_body(enu)?,
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                input.span(),
+                "Can only implement resource on struct or enum",
+            ));
+        }
+    };
+
+    let generics = bounded(input, quote!(ababa_config::AbabaSerialize));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ababa_config::AbabaSerialize for #struct_name #ty_generics #where_clause {
+            fn to_ababa(&self) -> ababa_config::AbabaValue {
+                #body
+            }
+        }
+    })
+}
+
+fn enum_body(enu: &DataEnum) -> syn::Result<TokenStream2> {
+    let arms = enu.variants.iter().map(|variant| -> syn::Result<_> {
+        let ident = &variant.ident;
+        let name = ident.to_string();
+
+        Ok(match &variant.fields {
+            Fields::Named(fields) => {
+                // skipped fields aren't bound, they'd be unused
+                let mut bindings = Vec::new();
+                for f in &fields.named {
+                    if !FieldAttrs::parse(f)?.skip {
+                        bindings.push(&f.ident);
+                    }
+                }
+                let inserts = insert_fields(fields, |ident| quote!(#ident))?;
+                let body = object(&name, inserts);
+                quote!(Self::#ident { #(#bindings,)* .. } => #body,)
+            }
+            Fields::Unnamed(fields) => {
+                // a single field is wrapped in a tuple as well, the generated TryFrom unwraps it
+                let bindings: Vec<_> = (0..fields.unnamed.len())
+                    .map(|i| format_ident!("field{i}"))
+                    .collect();
+                quote! {
+                    Self::#ident(#(#bindings),*) => ababa_config::AbabaValueKind::Tuple {
+                        struct_type: Some(#name.to_string()),
+                        items: vec![#(ababa_config::AbabaSerialize::to_ababa(#bindings)),*],
+                    }
+                    .into(),
+                }
+            }
+            Fields::Unit => quote! {
+                Self::#ident => ababa_config::AbabaValueKind::Ident(#name.to_string()).into(),
+            },
+        })
+    });
+    let arms = arms.collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        match self {
+            #(#arms)*
+        }
+    })
+}
+
+// an object of type `name`, with `inserts` filling the `fields` map
+fn object(name: &str, inserts: TokenStream2) -> TokenStream2 {
+    quote! {{
+        let mut fields = std::collections::HashMap::new();
+        #inserts
+        ababa_config::AbabaValueKind::Object {
+            struct_type: Some(#name.to_string()),
+            fields,
+        }
+        .into()
+    }}
+}
+
+// inserts every field into `fields`, `access` gives a reference to the rust field
+fn insert_fields(
+    fields: &FieldsNamed,
+    access: impl Fn(&syn::Ident) -> TokenStream2,
+) -> syn::Result<TokenStream2> {
+    let inserts = fields
+        .named
+        .iter()
+        .map(|f| {
+            let ident = f.ident.as_ref().expect("named fields have idents");
+            let attrs = FieldAttrs::parse(f)?;
+            let access = access(ident);
+
+            if attrs.skip {
+                return Ok(quote!());
+            }
+
+            if attrs.flatten {
+                return Ok(quote! {
+                    if let ababa_config::AbabaValueKind::Object { fields: flattened, .. } =
+                        ababa_config::AbabaSerialize::to_ababa(#access).kind
+                    {
+                        fields.extend(flattened);
+                    }
+                });
+            }
+
+            let name = attrs.rename.unwrap_or_else(|| ident.to_string());
+
+            // None is left out, which is read back as None. with a default function it's
+            // written out, the missing field would be the default.
+            let custom_default = matches!(attrs.default, Some(Some(_)));
+            Ok(match is_option(&f.ty) && !custom_default {
+                true => quote! {
+                    if let Some(value) = #access {
+                        fields.insert(
+                            #name.to_string(),
+                            ababa_config::AbabaSerialize::to_ababa(value),
+                        );
+                    }
+                },
+                false => quote! {
+                    fields.insert(#name.to_string(), ababa_config::AbabaSerialize::to_ababa(#access));
+                },
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(quote!(#(#inserts)*))
+}
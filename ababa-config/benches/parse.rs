@@ -0,0 +1,89 @@
+//! parses and deserializes a generated scene with 100 000 objects.
+//!
+//! run with `cargo bench`, every step is timed a few times and the fastest run is printed.
+
+use ababa_config::{AbabaParser, AbabaValue};
+use ababa_config_proc::AbabaDeserialize;
+use std::fmt::Write;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const OBJECTS: usize = 100_000;
+const RUNS: usize = 10;
+
+#[allow(dead_code)]
+#[derive(AbabaDeserialize)]
+struct Scene {
+    objects: Vec<Shape>,
+}
+
+#[allow(dead_code)]
+#[derive(AbabaDeserialize)]
+enum Shape {
+    Sphere {
+        position: (f64, f64, f64),
+        radius: f64,
+        material: String,
+    },
+    Triangle {
+        vertices: [(f64, f64, f64); 3],
+        material: String,
+    },
+}
+
+// spheres and triangles in turns, each with a material name
+fn scene() -> String {
+    let mut s = String::from("Scene {\n\tobjects: [\n");
+    for i in 0..OBJECTS {
+        let x = i as f64 * 0.5;
+        if i % 2 == 0 {
+            writeln!(
+                s,
+                "\t\tSphere {{ position: ({x}, 1.5, -{x}), radius: 2.25, material: \"mat_{}\" }},",
+                i % 64
+            )
+        } else {
+            writeln!(
+                s,
+                "\t\tTriangle {{ vertices: [({x}, 0, 1), (0, {x}, 1), (1, 1, {x})], material: \"tri\" }},"
+            )
+        }
+        .unwrap();
+    }
+    s.push_str("\t],\n}\n");
+    s
+}
+
+// the fastest of a few runs of `f`, with a fresh input from `setup` that isn't timed
+fn time<I, T>(name: &str, mut setup: impl FnMut() -> I, mut f: impl FnMut(I) -> T) {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let input = setup();
+        let start = Instant::now();
+        black_box(f(input));
+        best = best.min(start.elapsed());
+    }
+    println!("{name:<20} {:>8.1} ms", best.as_secs_f64() * 1000.);
+}
+
+fn main() {
+    let source = scene();
+    println!("{OBJECTS} objects, {} KiB", source.len() / 1024);
+    let parser = || AbabaParser::new(&source);
+
+    time("parse", parser, |parser| parser.parse().unwrap());
+    time("parse borrowed", parser, |parser| {
+        parser.parse_borrowed().unwrap()
+    });
+
+    let borrowed = parser().parse_borrowed().unwrap();
+    time("move into owned", || borrowed.clone(), AbabaValue::from);
+
+    let value = parser().parse().unwrap();
+    time(
+        "deserialize",
+        || value.clone(),
+        |value| Scene::try_from(value).unwrap(),
+    );
+    time("clone", || &value, AbabaValue::clone);
+}
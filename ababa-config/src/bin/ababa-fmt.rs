@@ -0,0 +1,123 @@
+//! formats `.ababa` files in place.
+//!
+//! usage: `ababa-fmt [--check] [FILE]...`
+//!
+//! with `--check`, files are only checked and the command fails if any of them isn't formatted.
+//! without any files, stdin is formatted and written to stdout.
+
+use ababa_config::{format_source, is_equivalent};
+use std::io::{Read, Write};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: ababa-fmt [--check] [FILE]...";
+
+enum Outcome {
+    Unchanged,
+    Changed,
+    Failed,
+}
+
+fn main() -> ExitCode {
+    let mut check = false;
+    let mut files = Vec::new();
+
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--check" => check = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            flag if flag.starts_with('-') => {
+                eprintln!("unknown flag `{flag}`\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+            file => files.push(file.to_string()),
+        }
+    }
+
+    let outcomes = match files.is_empty() {
+        true => vec![format_stdin(check)],
+        false => files.iter().map(|file| format_file(file, check)).collect(),
+    };
+
+    let failed = outcomes.iter().any(|outcome| match outcome {
+        Outcome::Unchanged => false,
+        Outcome::Changed => check,
+        Outcome::Failed => true,
+    });
+
+    match failed {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
+}
+
+fn format_file(file: &str, check: bool) -> Outcome {
+    let source = match std::fs::read_to_string(file) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("error: could not read {file}: {e}");
+            return Outcome::Failed;
+        }
+    };
+
+    let Some(formatted) = format(&source, file) else {
+        return Outcome::Failed;
+    };
+    if formatted == source {
+        return Outcome::Unchanged;
+    }
+
+    if check {
+        println!("{file} is not formatted");
+    } else if let Err(e) = std::fs::write(file, formatted) {
+        eprintln!("error: could not write {file}: {e}");
+        return Outcome::Failed;
+    }
+
+    Outcome::Changed
+}
+
+fn format_stdin(check: bool) -> Outcome {
+    let mut source = String::new();
+    if let Err(e) = std::io::stdin().read_to_string(&mut source) {
+        eprintln!("error: could not read stdin: {e}");
+        return Outcome::Failed;
+    }
+
+    let Some(formatted) = format(&source, "<stdin>") else {
+        return Outcome::Failed;
+    };
+
+    if check {
+        if formatted != source {
+            println!("<stdin> is not formatted");
+            return Outcome::Changed;
+        }
+    } else if let Err(e) = std::io::stdout().write_all(formatted.as_bytes()) {
+        eprintln!("error: could not write stdout: {e}");
+        return Outcome::Failed;
+    }
+
+    Outcome::Unchanged
+}
+
+// formats the source, reporting errors with the file name. the result is only used if it has the
+// same tokens as the source.
+fn format(source: &str, file: &str) -> Option<String> {
+    let formatted = match format_source(source) {
+        Ok(formatted) => formatted,
+        Err(e) => {
+            eprint!("{}", e.with_source(source).with_file_name(file));
+            return None;
+        }
+    };
+
+    if !is_equivalent(source, &formatted) {
+        eprintln!("error: formatting would change the meaning of {file}, leaving it untouched");
+        return None;
+    }
+
+    Some(formatted)
+}
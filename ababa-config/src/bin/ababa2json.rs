@@ -0,0 +1,79 @@
+//! converts an `.ababa` file to JSON, see the `json` module of `ababa-config` for how values JSON
+//! doesn't have are written.
+//!
+//! usage: `ababa2json [--compact] [FILE]`
+//!
+//! the JSON is written to stdout, indented with two spaces unless `--compact` is given. without a
+//! file, stdin is converted.
+
+use ababa_config::{to_json, to_json_pretty, AbabaParser};
+use std::io::Read;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: ababa2json [--compact] [FILE]";
+
+fn main() -> ExitCode {
+    let mut compact = false;
+    let mut file = None;
+
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--compact" => compact = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            flag if flag.starts_with('-') => {
+                eprintln!("unknown flag `{flag}`\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+            _ if file.is_some() => {
+                eprintln!("only one file can be converted at a time\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+            path => file = Some(path.to_string()),
+        }
+    }
+
+    let source = match read(file.as_deref()) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!(
+                "error: could not read {}: {e}",
+                file.as_deref().unwrap_or("stdin")
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // includes are relative to the file, or the current directory for stdin
+    let parser = match &file {
+        Some(file) => AbabaParser::new(&source).with_file(file),
+        None => AbabaParser::new(&source),
+    };
+    let value = match parser.allow_non_finite().parse() {
+        Ok(value) => value,
+        Err(e) => {
+            let file_name = file.as_deref().unwrap_or("<stdin>");
+            eprint!("{}", e.with_source(&source).with_file_name(file_name));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match compact {
+        true => println!("{}", to_json(&value)),
+        false => println!("{}", to_json_pretty(&value)),
+    }
+    ExitCode::SUCCESS
+}
+
+fn read(file: Option<&str>) -> std::io::Result<String> {
+    match file {
+        Some(file) => std::fs::read_to_string(file),
+        None => {
+            let mut source = String::new();
+            std::io::stdin().read_to_string(&mut source)?;
+            Ok(source)
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! converts JSON to ababa, the reverse of `ababa2json`. JSON written by other tools works as well,
+//! as long as it doesn't contain `null`.
+//!
+//! usage: `json2ababa [--compact] [FILE]`
+//!
+//! the ababa is written to stdout, spread over multiple lines like the scene files unless
+//! `--compact` is given. without a file, stdin is converted.
+
+use ababa_config::{from_json, AbabaPrinter};
+use std::io::Read;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: json2ababa [--compact] [FILE]";
+
+fn main() -> ExitCode {
+    let mut compact = false;
+    let mut file = None;
+
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--compact" => compact = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            flag if flag.starts_with('-') => {
+                eprintln!("unknown flag `{flag}`\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+            _ if file.is_some() => {
+                eprintln!("only one file can be converted at a time\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+            path => file = Some(path.to_string()),
+        }
+    }
+
+    let source = match read(file.as_deref()) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!(
+                "error: could not read {}: {e}",
+                file.as_deref().unwrap_or("stdin")
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let value = match from_json(&source) {
+        Ok(value) => value,
+        Err(e) => {
+            let file_name = file.as_deref().unwrap_or("<stdin>");
+            eprint!("{}", e.with_source(&source).with_file_name(file_name));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let printer = match compact {
+        true => AbabaPrinter::compact(),
+        false => AbabaPrinter::new(),
+    };
+    match printer.print(&value) {
+        Ok(printed) => println!("{printed}"),
+        Err(e) => {
+            let file_name = file.as_deref().unwrap_or("<stdin>");
+            eprint!("{}", e.with_source(&source).with_file_name(file_name));
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn read(file: Option<&str>) -> std::io::Result<String> {
+    match file {
+        Some(file) => std::fs::read_to_string(file),
+        None => {
+            let mut source = String::new();
+            std::io::stdin().read_to_string(&mut source)?;
+            Ok(source)
+        }
+    }
+}
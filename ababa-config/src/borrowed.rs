@@ -0,0 +1,395 @@
+//! values that borrow their strings from the parsed source, see [AbabaValueRef]
+//!
+//! the parser builds these and [crate::AbabaParser::parse] moves them into an [AbabaValue]
+//! afterwards, so both only differ in who owns the strings and how the fields are stored.
+
+use crate::{AbabaParseError, AbabaUnit, AbabaValue, AbabaValueKind, Span};
+use std::borrow::Cow;
+
+/// an [AbabaValue] that borrows its strings, idents, types and field names from the source it was
+/// parsed from, made by [crate::AbabaParser::parse_borrowed]. the fields of objects are kept in
+/// the order they were written in.
+///
+/// strings with escapes and values from included files, environment variables and parameters
+/// can't be borrowed and own their strings instead. [AbabaValue::from] moves the value into an
+/// [AbabaValue], only the borrowed strings are copied.
+///
+/// like with [AbabaValue], the span and unit are ignored when comparing values.
+///
+/// ```
+/// use ababa_config::{AbabaParser, AbabaValueRefKind};
+/// use std::borrow::Cow;
+///
+/// let source = "Sphere { radius: 1, material: \"glass\" }";
+/// let sphere = AbabaParser::new(source).parse_borrowed().unwrap();
+/// let AbabaValueRefKind::Object { fields, .. } = &sphere.kind else {
+///     panic!("expected an object");
+/// };
+/// let names: Vec<_> = fields.iter().map(|(name, _)| &**name).collect();
+/// assert_eq!(names, ["radius", "material"]);
+/// assert!(matches!(
+///     sphere.field("material").unwrap().kind,
+///     AbabaValueRefKind::String(Cow::Borrowed("glass"))
+/// ));
+/// ```
+#[derive(Debug, Clone)]
+pub struct AbabaValueRef<'a> {
+    pub kind: AbabaValueRefKind<'a>,
+    pub span: Span,
+    /// see [AbabaValue::unit]
+    pub unit: Option<AbabaUnit>,
+}
+
+/// the borrowed counterpart of [AbabaValueKind]. objects are equal if they have the same fields,
+/// in any order.
+#[derive(Debug, Clone)]
+pub enum AbabaValueRefKind<'a> {
+    Number(f64),
+    Object {
+        struct_type: Option<Cow<'a, str>>,
+        /// in the order of the source, the names are unique
+        fields: Vec<(Cow<'a, str>, AbabaValueRef<'a>)>,
+    },
+    List(Vec<AbabaValueRef<'a>>),
+    Tuple {
+        struct_type: Option<Cow<'a, str>>,
+        items: Vec<AbabaValueRef<'a>>,
+    },
+    String(Cow<'a, str>),
+    Bool(bool),
+    Ident(Cow<'a, str>),
+    /// see [AbabaValueKind::Spread]
+    Spread,
+}
+
+impl<'a> AbabaValueRef<'a> {
+    pub fn new(kind: AbabaValueRefKind<'a>, span: Span) -> Self {
+        AbabaValueRef {
+            kind,
+            span,
+            unit: None,
+        }
+    }
+
+    /// see [AbabaValue::struct_type]
+    pub fn struct_type(&self) -> Option<&str> {
+        match &self.kind {
+            AbabaValueRefKind::Object { struct_type, .. }
+            | AbabaValueRefKind::Tuple { struct_type, .. } => struct_type.as_deref(),
+            AbabaValueRefKind::Ident(ident) => Some(ident),
+            _ => None,
+        }
+    }
+
+    /// the field `name` of an object
+    pub fn field(&self, name: &str) -> Option<&AbabaValueRef<'a>> {
+        match &self.kind {
+            AbabaValueRefKind::Object { fields, .. } => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// the value with all of its strings owned, so it no longer borrows the source
+    pub fn into_static(self) -> AbabaValueRef<'static> {
+        use AbabaValueRefKind::*;
+
+        let kind = match self.kind {
+            Number(x) => Number(x),
+            Object {
+                struct_type,
+                fields,
+            } => Object {
+                struct_type: struct_type.map(own),
+                fields: fields
+                    .into_iter()
+                    .map(|(name, value)| (own(name), value.into_static()))
+                    .collect(),
+            },
+            List(items) => List(items.into_iter().map(Self::into_static).collect()),
+            Tuple { struct_type, items } => Tuple {
+                struct_type: struct_type.map(own),
+                items: items.into_iter().map(Self::into_static).collect(),
+            },
+            String(s) => String(own(s)),
+            Bool(b) => Bool(b),
+            Ident(ident) => Ident(own(ident)),
+            Spread => Spread,
+        };
+
+        AbabaValueRef {
+            kind,
+            span: self.span,
+            unit: self.unit,
+        }
+    }
+}
+
+fn own(s: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(s.into_owned())
+}
+
+impl PartialEq for AbabaValueRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl PartialEq for AbabaValueRefKind<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        use AbabaValueRefKind::*;
+
+        match (self, other) {
+            (Number(a), Number(b)) => a == b,
+            (
+                Object {
+                    struct_type,
+                    fields,
+                },
+                Object {
+                    struct_type: other_type,
+                    fields: other_fields,
+                },
+            ) => {
+                // the names are unique, so the same number of fields means the same names
+                struct_type == other_type
+                    && fields.len() == other_fields.len()
+                    && fields.iter().all(|(name, value)| {
+                        other_fields
+                            .iter()
+                            .any(|(other, other_value)| name == other && value == other_value)
+                    })
+            }
+            (List(a), List(b)) => a == b,
+            (
+                Tuple { struct_type, items },
+                Tuple {
+                    struct_type: other_type,
+                    items: other_items,
+                },
+            ) => struct_type == other_type && items == other_items,
+            (String(a), String(b)) | (Ident(a), Ident(b)) => a == b,
+            (Bool(a), Bool(b)) => a == b,
+            (Spread, Spread) => true,
+            _ => false,
+        }
+    }
+}
+
+/// moves the value, only the borrowed strings are copied
+impl From<AbabaValueRef<'_>> for AbabaValue {
+    fn from(value: AbabaValueRef<'_>) -> Self {
+        use AbabaValueRefKind::*;
+
+        let kind = match value.kind {
+            Number(x) => AbabaValueKind::Number(x),
+            Object {
+                struct_type,
+                fields,
+            } => AbabaValueKind::Object {
+                struct_type: struct_type.map(Cow::into_owned),
+                fields: fields
+                    .into_iter()
+                    .map(|(name, value)| (name.into_owned(), value.into()))
+                    .collect(),
+            },
+            List(items) => AbabaValueKind::List(items.into_iter().map(Into::into).collect()),
+            Tuple { struct_type, items } => AbabaValueKind::Tuple {
+                struct_type: struct_type.map(Cow::into_owned),
+                items: items.into_iter().map(Into::into).collect(),
+            },
+            String(s) => AbabaValueKind::String(s.into_owned()),
+            Bool(b) => AbabaValueKind::Bool(b),
+            Ident(ident) => AbabaValueKind::Ident(ident.into_owned()),
+            Spread => AbabaValueKind::Spread,
+        };
+
+        AbabaValue {
+            kind,
+            span: value.span,
+            unit: value.unit,
+        }
+    }
+}
+
+/// the fields of objects are ordered by their spans, which is the order they were written in
+/// unless the spans were changed
+impl From<AbabaValue> for AbabaValueRef<'static> {
+    fn from(value: AbabaValue) -> Self {
+        use AbabaValueRefKind::*;
+
+        let kind = match value.kind {
+            AbabaValueKind::Number(x) => Number(x),
+            AbabaValueKind::Object {
+                struct_type,
+                fields,
+            } => {
+                let mut fields: Vec<_> = fields
+                    .into_iter()
+                    .map(|(name, value)| (Cow::Owned(name), AbabaValueRef::from(value)))
+                    .collect();
+                fields.sort_by_key(|(_, value)| value.span.start);
+                Object {
+                    struct_type: struct_type.map(Cow::Owned),
+                    fields,
+                }
+            }
+            AbabaValueKind::List(items) => List(items.into_iter().map(Into::into).collect()),
+            AbabaValueKind::Tuple { struct_type, items } => Tuple {
+                struct_type: struct_type.map(Cow::Owned),
+                items: items.into_iter().map(Into::into).collect(),
+            },
+            AbabaValueKind::String(s) => String(Cow::Owned(s)),
+            AbabaValueKind::Bool(b) => Bool(b),
+            AbabaValueKind::Ident(ident) => Ident(Cow::Owned(ident)),
+            AbabaValueKind::Spread => Spread,
+        };
+
+        AbabaValueRef {
+            kind,
+            span: value.span,
+            unit: value.unit,
+        }
+    }
+}
+
+/// a string that is only copied if it had escapes (or wasn't borrowed to begin with)
+impl<'a> TryFrom<AbabaValueRef<'a>> for Cow<'a, str> {
+    type Error = AbabaParseError;
+
+    fn try_from(value: AbabaValueRef<'a>) -> Result<Self, Self::Error> {
+        match value.kind {
+            AbabaValueRefKind::String(s) => Ok(s),
+            _ => Err(AbabaParseError::ValueTypeDidNotMatch {
+                expected: "String",
+                got: value.into(),
+            }),
+        }
+    }
+}
+
+/// `value` with `span` for itself and all nested values
+pub(crate) fn respan(mut value: AbabaValueRef<'_>, span: Span) -> AbabaValueRef<'_> {
+    fn set(value: &mut AbabaValueRef<'_>, span: Span) {
+        value.span = span;
+        match &mut value.kind {
+            AbabaValueRefKind::Object { fields, .. } => {
+                fields.iter_mut().for_each(|(_, value)| set(value, span))
+            }
+            AbabaValueRefKind::List(items) | AbabaValueRefKind::Tuple { items, .. } => {
+                items.iter_mut().for_each(|value| set(value, span))
+            }
+            _ => {}
+        }
+    }
+
+    set(&mut value, span);
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AbabaParser;
+
+    fn parse(s: &str) -> AbabaValueRef<'_> {
+        AbabaParser::new(s).parse_borrowed().unwrap()
+    }
+
+    #[test]
+    fn borrowing() {
+        let value = parse(r#"Scene { b: [a, "x"], a: ("y\n",), c: -2 }"#);
+        let AbabaValueRefKind::Object {
+            struct_type: Some(Cow::Borrowed("Scene")),
+            fields,
+        } = &value.kind
+        else {
+            panic!("expected a borrowed type, got {value:?}");
+        };
+        let names: Vec<_> = fields.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["b", "a", "c"]);
+        assert!(fields
+            .iter()
+            .all(|(name, _)| matches!(name, Cow::Borrowed(_))));
+
+        let b = value.field("b").unwrap();
+        assert!(matches!(
+            &b.kind,
+            AbabaValueRefKind::List(items) if matches!(
+                (&items[0].kind, &items[1].kind),
+                (
+                    AbabaValueRefKind::Ident(Cow::Borrowed("a")),
+                    AbabaValueRefKind::String(Cow::Borrowed("x")),
+                )
+            )
+        ));
+        // escapes have to be resolved into a new string
+        let AbabaValueRefKind::Tuple { items, .. } = &value.field("a").unwrap().kind else {
+            panic!("expected a tuple");
+        };
+        assert!(matches!(&items[0].kind, AbabaValueRefKind::String(Cow::Owned(s)) if s == "y\n"));
+        assert_eq!(value.field("d"), None);
+        assert_eq!(value.struct_type(), Some("Scene"));
+
+        // the order is kept, but doesn't matter for comparisons
+        assert_eq!(parse("{ a: 1, b: [2] }"), parse("{ b: [2], a: 1 }"));
+        assert_ne!(parse("{ a: 1, b: 2 }"), parse("{ a: 1, c: 2 }"));
+        assert_ne!(parse("{ a: 1 }"), parse("A { a: 1 }"));
+    }
+
+    #[test]
+    fn owned_values() {
+        let source = r#"let a = { z: 1, y: "s" }; [$a, include "b.ababa", 2m]"#;
+        let files = std::collections::HashMap::from([(
+            std::path::PathBuf::from("b.ababa"),
+            "Sphere { r: 1, q: 2 }".to_string(),
+        )]);
+        let value = AbabaParser::new(source)
+            .with_resolver(&files)
+            .parse_borrowed()
+            .unwrap();
+        let AbabaValueRefKind::List(items) = &value.kind else {
+            panic!("expected a list");
+        };
+        // references are copies of the borrowed value, included values own their strings
+        assert!(matches!(
+            &items[0].kind,
+            AbabaValueRefKind::Object { fields, .. } if matches!(fields[0].0, Cow::Borrowed("z"))
+        ));
+        assert!(matches!(
+            &items[1].kind,
+            AbabaValueRefKind::Object { struct_type: Some(Cow::Owned(_)), fields }
+                if fields[0].0 == "r" && fields[1].0 == "q"
+        ));
+        assert_eq!(items[1].span, Span::new(31, 48));
+
+        // moving into an AbabaValue and back keeps everything
+        let owned = AbabaValue::from(value.clone());
+        assert_eq!(
+            owned,
+            AbabaParser::new(source)
+                .with_resolver(&files)
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(owned.span, value.span);
+        let AbabaValueKind::List(owned_items) = &owned.kind else {
+            panic!("expected a list");
+        };
+        assert_eq!(owned_items[2].unit, Some(AbabaUnit::Length));
+        assert_eq!(AbabaValueRef::from(owned), value);
+        assert_eq!(value.clone().into_static(), value);
+    }
+
+    #[test]
+    fn strings() {
+        let s: Cow<str> = parse(r#""glass""#).try_into().unwrap();
+        assert!(matches!(s, Cow::Borrowed("glass")));
+        let s: Cow<str> = parse(r#""a\tb""#).try_into().unwrap();
+        assert_eq!(s, "a\tb");
+        let err = Cow::<str>::try_from(parse("1")).unwrap_err();
+        assert_eq!(err.to_string(), "expected String, got Number");
+    }
+}
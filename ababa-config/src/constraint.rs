@@ -0,0 +1,196 @@
+//! constraints on the values of fields, from the `range`, `nonzero`, `unit` and `validate`
+//! attributes of `#[derive(AbabaDeserialize)]`
+
+use crate::{AbabaParseError, AbabaUnit, AbabaValue, AbabaValueKind};
+use ababa_config_proc::AbabaSerialize;
+use std::fmt::{Display, Formatter};
+
+/// a constraint on the value of a field, violations are [AbabaParseError::ConstraintViolated]
+/// ([AbabaParseError::UnitMismatch] for units)
+#[derive(Debug, Clone, PartialEq, AbabaSerialize)]
+pub enum AbabaConstraint {
+    /// `min..max` or `min..=max` if `inclusive`, either end can be left out
+    Range {
+        min: Option<f64>,
+        max: Option<f64>,
+        inclusive: bool,
+    },
+    /// numbers other than 0, and tuples with at least one of them (vectors with a length)
+    NonZero,
+    /// numbers written with a unit have to have this one, also the numbers in tuples and lists.
+    /// numbers without a unit are accepted.
+    Unit(AbabaUnit),
+    /// checked by the function `name` once the value is converted, so [AbabaConstraint::check]
+    /// always accepts it
+    Validate { name: &'static str },
+}
+
+impl AbabaConstraint {
+    /// checks a value before it's converted. values of the wrong kind are accepted, converting
+    /// them fails anyway.
+    pub fn check(&self, value: &AbabaValue) -> Result<(), AbabaParseError> {
+        if let AbabaConstraint::Unit(unit) = self {
+            return check_unit(*unit, value);
+        }
+
+        let violated = match (self, &value.kind) {
+            (AbabaConstraint::Range { .. }, AbabaValueKind::Number(x)) if !self.contains(*x) => {
+                format!("{x} is not in the range {self}")
+            }
+            (AbabaConstraint::NonZero, AbabaValueKind::Number(x)) if *x == 0. => {
+                "must not be zero".to_string()
+            }
+            (AbabaConstraint::NonZero, AbabaValueKind::Tuple { items, .. })
+                if !items.is_empty()
+                    && items
+                        .iter()
+                        .all(|item| item.kind == AbabaValueKind::Number(0.)) =>
+            {
+                "must not be zero".to_string()
+            }
+            _ => return Ok(()),
+        };
+
+        Err(AbabaParseError::ConstraintViolated {
+            message: violated,
+            span: value.span,
+        })
+    }
+
+    fn contains(&self, x: f64) -> bool {
+        let AbabaConstraint::Range {
+            min,
+            max,
+            inclusive,
+        } = self
+        else {
+            return true;
+        };
+
+        min.is_none_or(|min| x >= min)
+            && max.is_none_or(|max| if *inclusive { x <= max } else { x < max })
+    }
+}
+
+// the first number with a unit other than `expected`
+fn check_unit(expected: AbabaUnit, value: &AbabaValue) -> Result<(), AbabaParseError> {
+    match &value.kind {
+        AbabaValueKind::Number(_) => match value.unit {
+            Some(got) if got != expected => Err(AbabaParseError::UnitMismatch {
+                expected: Some(expected),
+                got,
+                span: value.span,
+            }),
+            _ => Ok(()),
+        },
+        AbabaValueKind::List(items) | AbabaValueKind::Tuple { items, .. } => {
+            items.iter().try_for_each(|item| check_unit(expected, item))
+        }
+        _ => Ok(()),
+    }
+}
+
+impl Display for AbabaConstraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AbabaConstraint::Range {
+                min,
+                max,
+                inclusive,
+            } => {
+                if let Some(min) = min {
+                    write!(f, "{min}")?;
+                }
+                write!(f, "{}", if *inclusive { "..=" } else { ".." })?;
+                if let Some(max) = max {
+                    write!(f, "{max}")?;
+                }
+                Ok(())
+            }
+            AbabaConstraint::NonZero => write!(f, "nonzero"),
+            AbabaConstraint::Unit(unit) => write!(f, "unit = {unit}"),
+            AbabaConstraint::Validate { name } => write!(f, "validate = {name}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AbabaParser;
+
+    fn check(constraint: &AbabaConstraint, s: &str) -> Result<(), String> {
+        let value = AbabaParser::new(s).parse().unwrap();
+        constraint.check(&value).map_err(|e| e.to_string())
+    }
+
+    fn range(min: Option<f64>, max: Option<f64>, inclusive: bool) -> AbabaConstraint {
+        AbabaConstraint::Range {
+            min,
+            max,
+            inclusive,
+        }
+    }
+
+    #[test]
+    fn ranges() {
+        let positive = range(Some(0.), None, false);
+        assert_eq!(check(&positive, "0"), Ok(()));
+        assert_eq!(check(&positive, "1000000"), Ok(()));
+        assert_eq!(
+            check(&positive, "-1"),
+            Err("-1 is not in the range 0..".to_string())
+        );
+        // other kinds are left to the conversion
+        assert_eq!(check(&positive, "\"-1\""), Ok(()));
+
+        let unit = range(Some(0.), Some(1.), false);
+        assert_eq!(check(&unit, "0.5"), Ok(()));
+        assert_eq!(
+            check(&unit, "1"),
+            Err("1 is not in the range 0..1".to_string())
+        );
+        assert_eq!(check(&range(Some(0.), Some(1.), true), "1"), Ok(()));
+        assert_eq!(
+            check(&range(None, Some(-0.5), true), "0"),
+            Err("0 is not in the range ..=-0.5".to_string())
+        );
+    }
+
+    #[test]
+    fn nonzero() {
+        let nonzero = AbabaConstraint::NonZero;
+        assert_eq!(check(&nonzero, "0.1"), Ok(()));
+        assert_eq!(check(&nonzero, "(0, 0, 1)"), Ok(()));
+        assert_eq!(check(&nonzero, "()"), Ok(()));
+        assert_eq!(check(&nonzero, "0"), Err("must not be zero".to_string()));
+        assert_eq!(check(&nonzero, "-0"), Err("must not be zero".to_string()));
+        assert_eq!(
+            check(&nonzero, "(0, 0, 0)"),
+            Err("must not be zero".to_string())
+        );
+
+        let validate = AbabaConstraint::Validate { name: "is_even" };
+        assert_eq!(check(&validate, "1"), Ok(()));
+    }
+    #[test]
+    fn units() {
+        let angle = AbabaConstraint::Unit(AbabaUnit::Angle);
+        assert_eq!(check(&angle, "45deg"), Ok(()));
+        assert_eq!(check(&angle, "(0.5rad, 1, -90deg)"), Ok(()));
+        assert_eq!(check(&angle, "1.5"), Ok(()));
+        assert_eq!(
+            check(&angle, "10cm"),
+            Err("expected an angle, got a length".to_string())
+        );
+        assert_eq!(
+            check(&angle, "[(0deg, 0deg), (0deg, 2m)]"),
+            Err("expected an angle, got a length".to_string())
+        );
+
+        let value = AbabaParser::new("(1deg, 2mm)").parse().unwrap();
+        let err = angle.check(&value).unwrap_err();
+        assert_eq!(err.span(), Some(crate::Span::new(7, 10)));
+        assert_eq!(angle.to_string(), "unit = angle");
+    }
+}
@@ -0,0 +1,262 @@
+//! rendering of [AbabaParseError] together with the input, in the style of rustc:
+//!
+//! ```txt
+//! error: expected Tuple, got Number
+//!   --> scene.ababa:16:14
+//!    |
+//! 16 |             material: 255,
+//!    |                       ^^^
+//!    = note: at `scene_objects[1].material`
+//! ```
+
+use crate::{AbabaParseError, Span};
+use std::fmt::{Display, Formatter};
+
+/// an [AbabaParseError] with the input it happened in, created by [AbabaParseError::with_source]
+pub struct AbabaDiagnostic<'a> {
+    error: &'a AbabaParseError,
+    source: &'a str,
+    file_name: Option<&'a str>,
+}
+
+impl<'a> AbabaDiagnostic<'a> {
+    pub fn new(error: &'a AbabaParseError, source: &'a str) -> Self {
+        AbabaDiagnostic {
+            error,
+            source,
+            file_name: None,
+        }
+    }
+
+    /// file name shown in front of the line and column
+    pub fn with_file_name(mut self, file_name: &'a str) -> Self {
+        self.file_name = Some(file_name);
+        self
+    }
+}
+
+/// 1-based line and column (in chars) of the byte offset `offset`
+pub(crate) fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line = before.matches('\n').count() + 1;
+    let col = before[line_start..].chars().count() + 1;
+    (line, col)
+}
+
+// tabs are expanded so that the underline lines up no matter the tab width of the terminal
+fn expand_tabs(s: &str) -> String {
+    s.replace('\t', "    ")
+}
+
+// source and file name of the file the error happened in, and the includes that lead there
+struct Location<'a> {
+    source: &'a str,
+    file_name: Option<String>,
+    span: Option<Span>,
+    /// `file:line:col` of every include, starting with the innermost one
+    included_from: Vec<String>,
+}
+
+impl<'a> AbabaDiagnostic<'a> {
+    fn location(&self) -> Location<'a> {
+        let mut source = self.source;
+        let mut file_name = self.file_name.map(str::to_string);
+        let mut included_from = Vec::new();
+        let mut err = self.error;
+
+        loop {
+            match err {
+                AbabaParseError::AtPath { inner, .. } => err = inner,
+                AbabaParseError::Included {
+                    file,
+                    source: included,
+                    span,
+                    inner,
+                } => {
+                    if let Some(span) = span {
+                        let (line, col) = line_col(source, span.start);
+                        included_from.push(match &file_name {
+                            Some(file_name) => format!("{file_name}:{line}:{col}"),
+                            None => format!("{line}:{col}"),
+                        });
+                    }
+
+                    source = included;
+                    file_name = Some(file.display().to_string());
+                    err = inner;
+                }
+                _ => break,
+            }
+        }
+
+        included_from.reverse();
+        Location {
+            source,
+            file_name,
+            span: err.root_span(),
+            included_from,
+        }
+    }
+}
+
+impl Display for AbabaDiagnostic<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error: ")?;
+        self.error.fmt_message(f)?;
+        writeln!(f)?;
+
+        let Location {
+            source,
+            file_name,
+            span,
+            included_from,
+        } = self.location();
+
+        // errors of an AbabaLoader can happen without any source
+        let mut gutter = String::new();
+        if let Some(Span { start, end }) = span.filter(|_| !source.is_empty()) {
+            let start = start.min(source.len());
+
+            let (line, col) = line_col(source, start);
+            let line_start = source[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+            let line_end = source[start..]
+                .find('\n')
+                .map(|i| start + i)
+                .unwrap_or(source.len());
+
+            // the span may reach over multiple lines, only the first one is underlined
+            let end = end.clamp(start, line_end);
+            let text = &source[line_start..line_end];
+            let padding = expand_tabs(&source[line_start..start]).chars().count();
+            let width = expand_tabs(&source[start..end]).chars().count().max(1);
+
+            gutter = " ".repeat(line.to_string().len());
+            match file_name {
+                Some(file_name) => writeln!(f, "{gutter}--> {file_name}:{line}:{col}")?,
+                None => writeln!(f, "{gutter}--> {line}:{col}")?,
+            }
+            writeln!(f, "{gutter} |")?;
+            writeln!(f, "{line} | {}", expand_tabs(text))?;
+            writeln!(f, "{gutter} | {}{}", " ".repeat(padding), "^".repeat(width))?;
+        }
+
+        for location in included_from {
+            writeln!(f, "{gutter} = note: included from {location}")?;
+        }
+
+        let path = self.error.path();
+        if !path.is_empty() {
+            writeln!(f, "{gutter} = note: at `{path}`")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AbabaParser;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    #[test]
+    fn line_and_column() {
+        let s = "ab\n\tcd\n";
+        assert_eq!(line_col(s, 0), (1, 1));
+        assert_eq!(line_col(s, 1), (1, 2));
+        assert_eq!(line_col(s, 3), (2, 1));
+        assert_eq!(line_col(s, 5), (2, 3));
+        assert_eq!(line_col(s, 7), (3, 1));
+    }
+
+    #[test]
+    fn snippet() {
+        let s = "{\n\ta: {\n\t\tb: (1, 2, @),\n\t},\n}";
+        let err = AbabaParser::new(s).parse().unwrap_err();
+
+        assert_eq!(
+            err.with_source(s).with_file_name("test.ababa").to_string(),
+            "\
+error: expected value, got unknown character '@'
+ --> test.ababa:3:13
+  |
+3 |         b: (1, 2, @),
+  |                   ^
+"
+        );
+    }
+
+    #[test]
+    fn snippet_with_path() {
+        let s = "[\n  1,\n  (1, 2),\n]";
+        let value = AbabaParser::new(s).parse().unwrap();
+        let err = Vec::<f64>::try_from(value).unwrap_err().in_field("numbers");
+
+        assert_eq!(
+            err.with_source(s).to_string(),
+            "\
+error: expected Number, got Tuple
+ --> 3:3
+  |
+3 |   (1, 2),
+  |   ^^^^^^
+  = note: at `numbers[1]`
+"
+        );
+    }
+
+    #[test]
+    fn no_span() {
+        let err = AbabaParser::new("").parse().unwrap_err();
+        assert_eq!(
+            err.with_source("").to_string(),
+            "error: input does not contain a value\n"
+        );
+    }
+
+    #[test]
+    fn included() {
+        let files = HashMap::from([
+            (
+                PathBuf::from("scenes/lights.ababa"),
+                "[\n\tinclude \"light.ababa\",\n]".to_string(),
+            ),
+            (PathBuf::from("scenes/light.ababa"), "{ a: $b }".to_string()),
+        ]);
+        let s = "{\n  lights: include \"lights.ababa\",\n}";
+        let err = AbabaParser::new(s)
+            .with_file("scenes/scene.ababa")
+            .with_resolver(&files)
+            .parse()
+            .unwrap_err();
+
+        assert_eq!(
+            err.with_source(s)
+                .with_file_name("scenes/scene.ababa")
+                .to_string(),
+            "\
+error: `$b` is not defined
+ --> scenes/light.ababa:1:6
+  |
+1 | { a: $b }
+  |      ^^
+  = note: included from scenes/lights.ababa:2:2
+  = note: included from scenes/scene.ababa:2:11
+"
+        );
+
+        // an empty file has no snippet
+        let files = HashMap::from([(PathBuf::from("empty.ababa"), String::new())]);
+        let s = "include \"empty.ababa\"";
+        let err = AbabaParser::new(s)
+            .with_resolver(&files)
+            .parse()
+            .unwrap_err();
+        assert_eq!(
+            err.with_source(s).to_string(),
+            "error: input does not contain a value\n = note: included from 1:1\n"
+        );
+    }
+}
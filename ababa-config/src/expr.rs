@@ -0,0 +1,113 @@
+//! constants and functions that can be used in arithmetic expressions.
+//!
+//! expressions are evaluated by the parser while parsing, so only the resulting numbers end up in
+//! the [crate::AbabaValue].
+
+use std::f64::consts::{PI, TAU};
+
+pub(crate) struct Function {
+    pub name: &'static str,
+    pub arity: usize,
+    pub eval: fn(&[f64]) -> f64,
+}
+
+pub(crate) const FUNCTIONS: &[Function] = &[
+    Function {
+        name: "sin",
+        arity: 1,
+        eval: |x| x[0].sin(),
+    },
+    Function {
+        name: "cos",
+        arity: 1,
+        eval: |x| x[0].cos(),
+    },
+    Function {
+        name: "tan",
+        arity: 1,
+        eval: |x| x[0].tan(),
+    },
+    Function {
+        name: "sqrt",
+        arity: 1,
+        eval: |x| x[0].sqrt(),
+    },
+    Function {
+        name: "abs",
+        arity: 1,
+        eval: |x| x[0].abs(),
+    },
+    // degrees to radians
+    Function {
+        name: "rad",
+        arity: 1,
+        eval: |x| x[0].to_radians(),
+    },
+    // radians to degrees
+    Function {
+        name: "deg",
+        arity: 1,
+        eval: |x| x[0].to_degrees(),
+    },
+    Function {
+        name: "min",
+        arity: 2,
+        eval: |x| x[0].min(x[1]),
+    },
+    Function {
+        name: "max",
+        arity: 2,
+        eval: |x| x[0].max(x[1]),
+    },
+    Function {
+        name: "pow",
+        arity: 2,
+        eval: |x| x[0].powf(x[1]),
+    },
+];
+
+pub(crate) fn function(name: &str) -> Option<&'static Function> {
+    FUNCTIONS.iter().find(|f| f.name == name)
+}
+
+// `inf` and `nan` are only constants if non-finite numbers are allowed
+pub(crate) fn constant(name: &str, non_finite: bool) -> Option<f64> {
+    match name {
+        "pi" => Some(PI),
+        "tau" => Some(TAU),
+        "inf" if non_finite => Some(f64::INFINITY),
+        "nan" if non_finite => Some(f64::NAN),
+        _ => None,
+    }
+}
+
+/// binary operators, in the order of their tokens
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Operator {
+    /// `None` on a division by zero
+    pub fn apply(self, a: f64, b: f64) -> Option<f64> {
+        match self {
+            Operator::Add => Some(a + b),
+            Operator::Sub => Some(a - b),
+            Operator::Mul => Some(a * b),
+            Operator::Div if b == 0. => None,
+            Operator::Div => Some(a / b),
+        }
+    }
+
+    pub fn symbol(self) -> char {
+        match self {
+            Operator::Add => '+',
+            Operator::Sub => '-',
+            Operator::Mul => '*',
+            Operator::Div => '/',
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! helpers for reading the fields of an object, used by the `#[derive(AbabaDeserialize)]` macro
+
+use crate::{AbabaParseError, AbabaValue, Span};
+use std::collections::HashMap;
+
+/// types that are read from the fields of an object, taking the fields they need out of it.
+///
+/// this is what allows `#[ababa(flatten)]`: the flattened field takes its fields out of the
+/// surrounding object. it is derived for structs with named fields.
+pub trait AbabaFromFields: Sized {
+    /// `span` is the span of the whole object, used for errors about missing fields
+    fn from_fields(
+        fields: &mut HashMap<String, AbabaValue>,
+        span: Span,
+    ) -> Result<Self, AbabaParseError>;
+
+    /// names of all fields that are read, or `None` if any field is accepted
+    fn field_names() -> Option<Vec<&'static str>>;
+}
+
+/// a flattened map takes all remaining fields
+impl<T: TryFrom<AbabaValue, Error = AbabaParseError>> AbabaFromFields for HashMap<String, T> {
+    fn from_fields(
+        fields: &mut HashMap<String, AbabaValue>,
+        _span: Span,
+    ) -> Result<Self, AbabaParseError> {
+        fields
+            .drain()
+            .map(|(k, v)| match T::try_from(v) {
+                Ok(v) => Ok((k, v)),
+                Err(e) => Err(e.in_field(k)),
+            })
+            .collect()
+    }
+
+    fn field_names() -> Option<Vec<&'static str>> {
+        None
+    }
+}
+
+/// removes the field that is present under one of `names` (the name and its aliases).
+/// returns which of the names was used together with the value.
+///
+/// it's an error if the field is present under more than one name.
+pub fn take_field(
+    fields: &mut HashMap<String, AbabaValue>,
+    names: &[&str],
+) -> Result<Option<(String, AbabaValue)>, AbabaParseError> {
+    let mut found = None;
+
+    for name in names {
+        if let Some((key, value)) = fields.remove_entry(*name) {
+            if found.is_some() {
+                return Err(AbabaParseError::DuplicateField {
+                    field: key,
+                    span: value.span,
+                });
+            }
+            found = Some((key, value));
+        }
+    }
+
+    Ok(found)
+}
+
+/// errors on the first field (in order of appearance) that isn't in `expected`.
+/// suggests the most similar expected field, in case it's a typo.
+pub fn deny_unknown_fields(
+    fields: &HashMap<String, AbabaValue>,
+    expected: &[&'static str],
+) -> Result<(), AbabaParseError> {
+    let unknown = fields
+        .iter()
+        .filter(|(field, _)| !expected.contains(&field.as_str()))
+        .min_by_key(|(_, value)| value.span.start);
+
+    match unknown {
+        Some((field, value)) => Err(AbabaParseError::UnknownField {
+            field: field.clone(),
+            suggestion: suggest(field, expected),
+            span: value.span,
+        }),
+        None => Ok(()),
+    }
+}
+
+// the candidate with the smallest edit distance, if it's close enough to be a typo
+pub(crate) fn suggest(field: &str, candidates: &[&'static str]) -> Option<&'static str> {
+    let max_distance = (field.chars().count() / 3).max(1);
+
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(field, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// levenshtein distance: the number of inserted, removed or replaced chars to get from a to b.
+// only keeps a single row of the usual dynamic programming matrix.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let replace = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = replace.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AbabaValueKind;
+
+    #[test]
+    fn distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("radius", "radius"), 0);
+        assert_eq!(edit_distance("radus", "radius"), 1);
+        assert_eq!(edit_distance("raidus", "radius"), 2);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("äbc", "abc"), 1);
+    }
+
+    #[test]
+    fn suggestions() {
+        let fields = ["position", "radius", "material"];
+        assert_eq!(suggest("radus", &fields), Some("radius"));
+        assert_eq!(suggest("materal", &fields), Some("material"));
+        assert_eq!(suggest("postion", &fields), Some("position"));
+        assert_eq!(suggest("color", &fields), None);
+        assert_eq!(suggest("r", &fields), None);
+    }
+
+    #[test]
+    fn unknown_fields() {
+        let number =
+            |start| AbabaValue::new(AbabaValueKind::Number(1.), Span::new(start, start + 1));
+        let fields: HashMap<_, _> = [
+            ("radius".to_string(), number(0)),
+            ("colour".to_string(), number(10)),
+            ("radus".to_string(), number(5)),
+        ]
+        .into();
+
+        assert!(deny_unknown_fields(&fields, &["radius", "radus", "colour"]).is_ok());
+        assert!(matches!(
+            deny_unknown_fields(&fields, &["radius", "colour"]),
+            Err(AbabaParseError::UnknownField { field, suggestion: Some("radius"), span })
+                if field == "radus" && span == Span::new(5, 6)
+        ));
+        assert!(matches!(
+            deny_unknown_fields(&fields, &["radius"]),
+            Err(AbabaParseError::UnknownField { field, suggestion: Some("radius"), .. })
+                if field == "radus"
+        ));
+    }
+}
@@ -0,0 +1,1155 @@
+//! formatting of ababa source, keeping comments.
+//!
+//! [crate::AbabaValue] only holds the data, so the source is parsed again into a concrete syntax tree
+//! that remembers the written text of every literal, the order of fields, comments and blank lines.
+//! printing that tree follows the same layout rules as [crate::AbabaPrinter]: tab indentation,
+//! objects spread over multiple lines, lists and tuples only if they contain anything else than
+//! literals and tuples of literals (or comments), trailing commas on multiple lines.
+//!
+//! comments on their own line stay in front of the following item, comments after an item on the
+//! same line stay there. comments inside of an expression or in front of the value of a field stay
+//! next to the value they were written at. single blank lines between items are kept, multiple ones
+//! are collapsed.
+//!
+//! ```
+//! let source = "Ababa{ a:[1,2] , // the a\n\n\n b : (3 ,) }";
+//! let formatted = ababa_config::format_source(source).unwrap();
+//! assert_eq!(formatted, "Ababa {\n\ta: [1, 2], // the a\n\n\tb: (3,),\n}\n");
+//! ```
+
+use crate::expr::{self, Operator};
+use crate::parser::{self, LOOKUPS, MAX_DEPTH};
+use crate::tokenizer::{AbabaToken, AbabaTokenizer};
+use crate::{AbabaParseError, Span};
+
+/// formats ababa source. the source has to be syntactically valid, otherwise the syntax error is
+/// returned.
+///
+/// nothing is evaluated: params without a default, unset environment variables, missing includes
+/// and loops that would be too long are formatted like everything else.
+pub fn format_source(source: &str) -> Result<String, AbabaParseError> {
+    let document = CstBuilder::new(source).build()?;
+    let mut printer = CstPrinter { out: String::new() };
+    printer.print_document(&document);
+    Ok(printer.out)
+}
+
+/// whether formatting only changed the layout of `source`, i.e. both have the same tokens apart
+/// from comments and trailing commas
+pub fn is_equivalent(source: &str, formatted: &str) -> bool {
+    tokens(source) == tokens(formatted)
+}
+
+// the tokens without comments and without the commas in front of closing braces, except the one
+// that makes a single value in parens a tuple
+fn tokens(source: &str) -> Vec<AbabaToken<'_>> {
+    let mut tokens: Vec<AbabaToken> = Vec::new();
+    // for every open brace whether it could be a single value tuple, and its number of commas
+    let mut groups = Vec::new();
+
+    for (tok, _) in AbabaTokenizer::new(source) {
+        match tok {
+            AbabaToken::LeftParen => {
+                let typed = matches!(tokens.last(), Some(AbabaToken::Ident(_)));
+                groups.push((!typed, 0));
+            }
+            AbabaToken::LeftBracket | AbabaToken::LeftBrace => groups.push((false, 0)),
+            AbabaToken::ListSeparator => {
+                if let Some((_, commas)) = groups.last_mut() {
+                    *commas += 1;
+                }
+            }
+            AbabaToken::RightParen | AbabaToken::RightBracket | AbabaToken::RightBrace => {
+                let (untyped_tuple, commas) = groups.pop().unwrap_or_default();
+                if tokens.last() == Some(&AbabaToken::ListSeparator)
+                    && !(untyped_tuple && commas == 1)
+                {
+                    tokens.pop();
+                }
+            }
+            _ => {}
+        }
+        tokens.push(tok);
+    }
+
+    tokens
+}
+
+struct Comment<'s> {
+    text: &'s str,
+    blank_line_before: bool,
+}
+
+enum GroupKind {
+    List,
+    Tuple,
+    Object,
+    /// the items of a loop
+    Body,
+}
+
+enum Node<'s> {
+    /// a number, string, bool, ident, reference or `..` as written in the source
+    Literal(&'s str),
+    /// `&name value`
+    Anchored { name: &'s str, value: Box<Node<'s>> },
+    /// `-value`
+    Negated(Box<Node<'s>>),
+    Binary {
+        op: Operator,
+        lhs: Box<Node<'s>>,
+        rhs: Box<Node<'s>>,
+    },
+    /// `(value)`, without a comma
+    Paren(Box<Node<'s>>),
+    /// a value inside of an expression with the comments written right in front of and behind it
+    Commented {
+        before: Vec<&'s str>,
+        value: Box<Node<'s>>,
+        after: Vec<&'s str>,
+    },
+    /// `include "path"` or `import "path"` (the semicolon is part of the document), the path as
+    /// written in the source
+    Include { keyword: &'s str, path: &'s str },
+    /// `for name in start..end body`, the body is a group of kind `Body`
+    For {
+        name: &'s str,
+        start: Box<Node<'s>>,
+        end: Box<Node<'s>>,
+        body: Box<Node<'s>>,
+    },
+    /// `repeat count body`
+    Repeat {
+        count: Box<Node<'s>>,
+        body: Box<Node<'s>>,
+    },
+    Group {
+        struct_type: Option<&'s str>,
+        kind: GroupKind,
+        items: Vec<Item<'s>>,
+        /// comments after the last item
+        dangling: Vec<Comment<'s>>,
+    },
+}
+
+/// element of a list or tuple, field of an object, or binding (`key` is the name), import or
+/// value of the document
+struct Item<'s> {
+    comments: Vec<Comment<'s>>,
+    blank_line_before: bool,
+    key: Option<&'s str>,
+    value: Node<'s>,
+    /// comment on the same line after the item
+    trailing: Option<&'s str>,
+}
+
+/// the bindings and imports followed by the value
+struct Document<'s> {
+    items: Vec<Item<'s>>,
+    dangling: Vec<Comment<'s>>,
+}
+
+// builds the tree from the tokens and checks the syntax on the way, the grammar is the same as in
+// the parser. nothing is evaluated.
+struct CstBuilder<'s> {
+    source: &'s str,
+    tokens: Vec<(AbabaToken<'s>, Span)>,
+    pos: usize,
+    // end of the last consumed token, comments included
+    prev_end: usize,
+    // comments that were skipped and not yet attached to anything
+    pending: Vec<Comment<'s>>,
+    depth: usize,
+}
+
+impl<'s> CstBuilder<'s> {
+    fn new(source: &'s str) -> Self {
+        CstBuilder {
+            source,
+            tokens: AbabaTokenizer::with_comments(source).collect(),
+            pos: 0,
+            prev_end: 0,
+            pending: Vec::new(),
+            depth: 0,
+        }
+    }
+
+    fn build(mut self) -> Result<Document<'s>, AbabaParseError> {
+        if self.peek().is_none() {
+            return Err(AbabaParseError::NoContent);
+        }
+
+        let mut items = Vec::new();
+
+        loop {
+            let is_binding = self.peek() == Some(&AbabaToken::Let);
+            let is_import = self.peek() == Some(&AbabaToken::Import);
+            let comments = std::mem::take(&mut self.pending);
+            let start = self.tokens.get(self.pos).map_or(0, |(_, span)| span.start);
+            let blank_line_before = self.blank_line_before(start);
+
+            let key = match is_binding {
+                true => {
+                    self.next();
+                    let name = match self.next() {
+                        Some((AbabaToken::Ident(name), _)) => name,
+                        got => return Err(self.unexpected("name", got)),
+                    };
+                    match self.next() {
+                        Some((AbabaToken::Equals, _)) => {}
+                        got => return Err(self.unexpected("'='", got)),
+                    }
+                    Some(name)
+                }
+                false => None,
+            };
+            let value = match is_import {
+                true => {
+                    let (_, span) = self.next().expect("peeked");
+                    self.include(span)?
+                }
+                false => self.node()?,
+            };
+
+            let mut trailing = self.trailing_comment();
+            if is_binding || is_import {
+                match self.next() {
+                    Some((AbabaToken::Semicolon, _)) => {}
+                    got => return Err(self.unexpected("';'", got)),
+                }
+                trailing = trailing.or_else(|| self.trailing_comment());
+            }
+
+            items.push(Item {
+                comments,
+                blank_line_before,
+                key,
+                value,
+                trailing,
+            });
+
+            if !is_binding && !is_import {
+                break;
+            }
+        }
+
+        if let Some(tok) = self.next() {
+            return Err(self.unexpected("end of input", Some(tok)));
+        }
+        Ok(Document {
+            items,
+            dangling: std::mem::take(&mut self.pending),
+        })
+    }
+
+    // whether there's an empty line between the last consumed token and `start`
+    fn blank_line_before(&self, start: usize) -> bool {
+        self.source[self.prev_end..start].matches('\n').count() >= 2
+    }
+
+    // moves all comments in front of the next token to `pending`
+    fn skip_comments(&mut self) {
+        while let Some((AbabaToken::Comment(text), span)) = self.tokens.get(self.pos) {
+            self.pending.push(Comment {
+                text,
+                blank_line_before: self.blank_line_before(span.start),
+            });
+            self.prev_end = span.end;
+            self.pos += 1;
+        }
+    }
+
+    // a comment that is on the same line as the last consumed token
+    fn trailing_comment(&mut self) -> Option<&'s str> {
+        match self.tokens.get(self.pos) {
+            Some((AbabaToken::Comment(text), span))
+                if !self.source[self.prev_end..span.start].contains('\n') =>
+            {
+                self.prev_end = span.end;
+                self.pos += 1;
+                Some(text)
+            }
+            _ => None,
+        }
+    }
+
+    fn peek(&mut self) -> Option<&AbabaToken<'s>> {
+        self.skip_comments();
+        self.tokens.get(self.pos).map(|(tok, _)| tok)
+    }
+
+    // the next token that isn't a comment, without moving the comments to `pending`. used to look
+    // for operators, so that the comments after a value can still be trailing comments.
+    fn peek_past_comments(&self) -> Option<&AbabaToken<'s>> {
+        self.tokens[self.pos..]
+            .iter()
+            .map(|(tok, _)| tok)
+            .find(|tok| !matches!(tok, AbabaToken::Comment(_)))
+    }
+
+    fn next(&mut self) -> Option<(AbabaToken<'s>, Span)> {
+        self.skip_comments();
+        let (tok, span) = *self.tokens.get(self.pos)?;
+        self.prev_end = span.end;
+        self.pos += 1;
+        Some((tok, span))
+    }
+
+    fn unexpected(
+        &self,
+        expected: &'static str,
+        got: Option<(AbabaToken, Span)>,
+    ) -> AbabaParseError {
+        parser::unexpected(expected, got, self.source.len())
+    }
+
+    // sum, product and unary are the same as in the parser
+    fn node(&mut self) -> Result<Node<'s>, AbabaParseError> {
+        let mut node = self.product()?;
+
+        loop {
+            let op = match self.peek_past_comments() {
+                Some(AbabaToken::Plus) => Operator::Add,
+                Some(AbabaToken::Minus) => Operator::Sub,
+                _ => return Ok(node),
+            };
+            node = self.comments_after(node);
+            self.next();
+
+            let rhs = self.product()?;
+            node = Node::Binary {
+                op,
+                lhs: Box::new(node),
+                rhs: Box::new(rhs),
+            };
+        }
+    }
+
+    fn product(&mut self) -> Result<Node<'s>, AbabaParseError> {
+        let mut node = self.unary()?;
+
+        loop {
+            let op = match self.peek_past_comments() {
+                Some(AbabaToken::Star) => Operator::Mul,
+                Some(AbabaToken::Slash) => Operator::Div,
+                _ => return Ok(node),
+            };
+            node = self.comments_after(node);
+            self.next();
+
+            let rhs = self.unary()?;
+            node = Node::Binary {
+                op,
+                lhs: Box::new(node),
+                rhs: Box::new(rhs),
+            };
+        }
+    }
+
+    // the comments in front of the value (but after the key of a field) belong to it. the nesting
+    // is limited here like in the parser.
+    fn unary(&mut self) -> Result<Node<'s>, AbabaParseError> {
+        self.skip_comments();
+        let before = self.take_pending();
+
+        if self.depth == MAX_DEPTH {
+            let span = self.tokens.get(self.pos).map_or(
+                Span::new(self.source.len(), self.source.len()),
+                |(_, span)| *span,
+            );
+            return Err(AbabaParseError::TooDeep {
+                limit: MAX_DEPTH,
+                span,
+            });
+        }
+
+        self.depth += 1;
+        let node = match self.peek() {
+            Some(AbabaToken::Minus) => {
+                self.next();
+                self.unary().map(|node| Node::Negated(Box::new(node)))
+            }
+            _ => self.atom(),
+        };
+        self.depth -= 1;
+        Ok(commented(before, node?, Vec::new()))
+    }
+
+    // the comments between the node and the next token
+    fn comments_after(&mut self, node: Node<'s>) -> Node<'s> {
+        self.skip_comments();
+        let after = self.take_pending();
+        commented(Vec::new(), node, after)
+    }
+
+    fn take_pending(&mut self) -> Vec<&'s str> {
+        self.pending.drain(..).map(|comment| comment.text).collect()
+    }
+
+    fn atom(&mut self) -> Result<Node<'s>, AbabaParseError> {
+        let Some((tok, span)) = self.next() else {
+            return Err(self.unexpected("value", None));
+        };
+        let literal = &self.source[span.start..span.end];
+
+        match tok {
+            AbabaToken::LeftBracket => self.group(None, GroupKind::List, span),
+            AbabaToken::LeftParen => self.group(None, GroupKind::Tuple, span),
+            AbabaToken::LeftBrace => self.group(None, GroupKind::Object, span),
+            AbabaToken::Ident(ident) => match self.peek() {
+                Some(AbabaToken::LeftParen) => {
+                    let (_, open) = self.next().expect("peeked");
+                    self.group(Some(ident), GroupKind::Tuple, open)
+                }
+                Some(AbabaToken::LeftBrace) => {
+                    let (_, open) = self.next().expect("peeked");
+                    self.group(Some(ident), GroupKind::Object, open)
+                }
+                _ => Ok(Node::Literal(literal)),
+            },
+            AbabaToken::Str(s) => {
+                parser::unescape(s, span)?;
+                Ok(Node::Literal(literal))
+            }
+            AbabaToken::Number(_)
+            | AbabaToken::Color(_)
+            | AbabaToken::Bool(_)
+            | AbabaToken::Reference(_) => Ok(Node::Literal(literal)),
+            AbabaToken::Anchor(name) => Ok(Node::Anchored {
+                name,
+                value: Box::new(self.node()?),
+            }),
+            AbabaToken::Include => self.include(span),
+            tok => Err(self.unexpected("value", Some((tok, span)))),
+        }
+    }
+
+    // the path after `include` or `import` at `span`
+    fn include(&mut self, span: Span) -> Result<Node<'s>, AbabaParseError> {
+        match self.next() {
+            Some((AbabaToken::Str(s), path)) => {
+                parser::unescape(s, path)?;
+                Ok(Node::Include {
+                    keyword: &self.source[span.start..span.end],
+                    path: &self.source[path.start..path.end],
+                })
+            }
+            got => Err(self.unexpected("path", got)),
+        }
+    }
+
+    // the opening brace at `open` has already been consumed
+    fn group(
+        &mut self,
+        struct_type: Option<&'s str>,
+        kind: GroupKind,
+        open: Span,
+    ) -> Result<Node<'s>, AbabaParseError> {
+        let (close, brace) = match kind {
+            GroupKind::List => (AbabaToken::RightBracket, ']'),
+            GroupKind::Tuple => (AbabaToken::RightParen, ')'),
+            GroupKind::Object | GroupKind::Body => (AbabaToken::RightBrace, '}'),
+        };
+        let missing = AbabaParseError::MissingEndBrace { brace, span: open };
+        let mut items = Vec::new();
+        let mut trailing_comma = false;
+
+        loop {
+            match self.peek() {
+                Some(tok) if *tok == close => {
+                    self.next();
+                    trailing_comma = !items.is_empty();
+                    break;
+                }
+                None => return Err(missing),
+                _ => {}
+            }
+
+            let comments = std::mem::take(&mut self.pending);
+            let start = self.tokens.get(self.pos).map_or(0, |(_, span)| span.start);
+            let blank_line_before = self.blank_line_before(start);
+
+            let key = match kind {
+                GroupKind::Object => {
+                    let key = match self.next() {
+                        Some((AbabaToken::Ident(key), _)) => key,
+                        got => return Err(self.unexpected("field name", got)),
+                    };
+                    match self.next() {
+                        Some((AbabaToken::FieldSeparator, _)) => {}
+                        got => return Err(self.unexpected("':'", got)),
+                    }
+                    Some(key)
+                }
+                _ => None,
+            };
+            // only lists can be merged, and loops can't be in tuples or be fields
+            let value = match (&kind, self.peek()) {
+                (GroupKind::List, Some(AbabaToken::DotDot)) => {
+                    let (_, span) = self.next().expect("peeked");
+                    Node::Literal(&self.source[span.start..span.end])
+                }
+                (GroupKind::List | GroupKind::Body, Some(AbabaToken::For | AbabaToken::Repeat)) => {
+                    self.loop_node()?
+                }
+                _ => self.node()?,
+            };
+
+            let mut trailing = self.trailing_comment();
+            let done = match self.next() {
+                Some((AbabaToken::ListSeparator, _)) => false,
+                Some((tok, _)) if tok == close => true,
+                None => return Err(missing),
+                got => return Err(self.unexpected("',' or closing brace", got)),
+            };
+            if trailing.is_none() && !done {
+                trailing = self.trailing_comment();
+            }
+
+            items.push(Item {
+                comments,
+                blank_line_before,
+                key,
+                value,
+                trailing,
+            });
+
+            if done {
+                break;
+            }
+        }
+
+        // a single value in parens without a comma isn't a tuple. comments inside of the parens
+        // stay there, around the value.
+        if matches!(kind, GroupKind::Tuple)
+            && struct_type.is_none()
+            && items.len() == 1
+            && !trailing_comma
+        {
+            let item = items.pop().expect("checked length");
+            let before = item.comments.iter().map(|comment| comment.text).collect();
+            let mut after: Vec<_> = item.trailing.into_iter().collect();
+            after.extend(self.take_pending());
+            return Ok(Node::Paren(Box::new(commented(before, item.value, after))));
+        }
+
+        Ok(Node::Group {
+            struct_type,
+            kind,
+            items,
+            dangling: std::mem::take(&mut self.pending),
+        })
+    }
+
+    // a `for` or `repeat` loop, the grammar is the same as in the parser
+    fn loop_node(&mut self) -> Result<Node<'s>, AbabaParseError> {
+        let node = match self.next() {
+            Some((AbabaToken::For, _)) => {
+                let name = match self.next() {
+                    Some((AbabaToken::Ident(name), _)) => name,
+                    got => return Err(self.unexpected("name", got)),
+                };
+                match self.next() {
+                    Some((AbabaToken::In, _)) => {}
+                    got => return Err(self.unexpected("`in`", got)),
+                }
+                let start = Box::new(self.node()?);
+                match self.next() {
+                    Some((AbabaToken::DotDot, _)) => {}
+                    got => return Err(self.unexpected("'..'", got)),
+                }
+                let end = Box::new(self.node()?);
+                Node::For {
+                    name,
+                    start,
+                    end,
+                    body: Box::new(self.body()?),
+                }
+            }
+            _ => Node::Repeat {
+                count: Box::new(self.node()?),
+                body: Box::new(self.body()?),
+            },
+        };
+        Ok(node)
+    }
+
+    fn body(&mut self) -> Result<Node<'s>, AbabaParseError> {
+        match self.next() {
+            Some((AbabaToken::LeftBrace, open)) => self.group(None, GroupKind::Body, open),
+            got => Err(self.unexpected("'{'", got)),
+        }
+    }
+}
+
+// the node with the comments around it, if there are any
+fn commented<'s>(before: Vec<&'s str>, node: Node<'s>, after: Vec<&'s str>) -> Node<'s> {
+    match before.is_empty() && after.is_empty() {
+        true => node,
+        false => Node::Commented {
+            before,
+            value: Box::new(node),
+            after,
+        },
+    }
+}
+
+fn is_line_comment(text: &str) -> bool {
+    text.starts_with("//")
+}
+
+struct CstPrinter {
+    out: String,
+}
+
+impl CstPrinter {
+    fn print_document(&mut self, document: &Document) {
+        for (i, item) in document.items.iter().enumerate() {
+            self.print_item(i, item, 0, true);
+        }
+
+        if !document.dangling.is_empty() {
+            self.out.push('\n');
+        }
+        self.print_comments(&document.dangling, 0);
+    }
+
+    // prints every comment on its own line, starting at the current position
+    fn print_comments(&mut self, comments: &[Comment], depth: usize) {
+        for (i, comment) in comments.iter().enumerate() {
+            if i > 0 && comment.blank_line_before {
+                self.out.push('\n');
+            }
+            self.indent(depth);
+            self.out.push_str(comment.text);
+            self.out.push('\n');
+        }
+    }
+
+    // a comment inside of a value, a line comment continues the value on the next line
+    fn print_inline_comment(&mut self, text: &str, depth: usize) {
+        self.out.push_str(text);
+        if is_line_comment(text) {
+            self.out.push('\n');
+            self.indent(depth + 1);
+        }
+    }
+
+    // a space, unless a line was just started
+    fn space(&mut self) {
+        if !self.out.ends_with(['\n', '\t']) {
+            self.out.push(' ');
+        }
+    }
+
+    fn indent(&mut self, depth: usize) {
+        for _ in 0..depth {
+            self.out.push('\t');
+        }
+    }
+
+    fn print_node(&mut self, node: &Node, depth: usize) {
+        let (struct_type, kind, items, dangling) = match node {
+            Node::Literal(literal) => return self.out.push_str(literal),
+            Node::Anchored { name, value } => {
+                self.out.push('&');
+                self.out.push_str(name);
+                self.out.push(' ');
+                return self.print_node(value, depth);
+            }
+            Node::Negated(value) => {
+                self.out.push('-');
+                return self.print_node(value, depth);
+            }
+            Node::Binary { op, lhs, rhs } => {
+                self.print_node(lhs, depth);
+                self.space();
+                self.out.push(op.symbol());
+                self.out.push(' ');
+                return self.print_node(rhs, depth);
+            }
+            Node::Paren(value) => {
+                self.out.push('(');
+                self.print_node(value, depth);
+                return self.out.push(')');
+            }
+            Node::Commented {
+                before,
+                value,
+                after,
+            } => {
+                for comment in before {
+                    self.print_inline_comment(comment, depth);
+                    self.space();
+                }
+                self.print_node(value, depth);
+                for comment in after {
+                    self.space();
+                    self.print_inline_comment(comment, depth);
+                }
+                return;
+            }
+            Node::Include { keyword, path } => {
+                self.out.push_str(keyword);
+                self.out.push(' ');
+                return self.out.push_str(path);
+            }
+            Node::For {
+                name,
+                start,
+                end,
+                body,
+            } => {
+                self.out.push_str("for ");
+                self.out.push_str(name);
+                self.out.push_str(" in ");
+                self.print_node(start, depth);
+                // `1...3` would be read as `1`, `..` and `.3`
+                if self.out.ends_with('.') {
+                    self.out.push(' ');
+                }
+                self.out.push_str("..");
+                self.print_node(end, depth);
+                self.out.push(' ');
+                return self.print_node(body, depth);
+            }
+            Node::Repeat { count, body } => {
+                self.out.push_str("repeat ");
+                self.print_node(count, depth);
+                self.out.push(' ');
+                return self.print_node(body, depth);
+            }
+            Node::Group {
+                struct_type,
+                kind,
+                items,
+                dangling,
+            } => (struct_type, kind, items, dangling),
+        };
+
+        let (open, close) = match kind {
+            GroupKind::List => ('[', ']'),
+            GroupKind::Tuple => ('(', ')'),
+            GroupKind::Object | GroupKind::Body => ('{', '}'),
+        };
+
+        if let Some(struct_type) = struct_type {
+            self.out.push_str(struct_type);
+            if matches!(kind, GroupKind::Object) {
+                self.out.push(' ');
+            }
+        }
+        self.out.push(open);
+
+        if items.is_empty() && dangling.is_empty() {
+            self.out.push(close);
+            return;
+        }
+
+        let inline = matches!(kind, GroupKind::List | GroupKind::Tuple)
+            && dangling.is_empty()
+            && items.iter().all(|item| is_inline(item, true));
+
+        if inline {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    self.out.push_str(", ");
+                }
+                self.print_node(&item.value, depth + 1);
+            }
+            // a single value in parens needs a comma to be read as a tuple
+            if items.len() == 1 && matches!(kind, GroupKind::Tuple) && struct_type.is_none() {
+                self.out.push(',');
+            }
+            self.out.push(close);
+            return;
+        }
+
+        self.out.push('\n');
+        for (i, item) in items.iter().enumerate() {
+            self.print_item(i, item, depth + 1, false);
+        }
+
+        if dangling.first().is_some_and(|c| c.blank_line_before) && !items.is_empty() {
+            self.out.push('\n');
+        }
+        self.print_comments(dangling, depth + 1);
+        self.indent(depth);
+        self.out.push(close);
+    }
+
+    // prints the `i`th item on its own line(s), including its comments. items of the document are
+    // either bindings or the value.
+    fn print_item(&mut self, i: usize, item: &Item, depth: usize, in_document: bool) {
+        let blank_line_before = match item.comments.first() {
+            Some(comment) => comment.blank_line_before,
+            None => item.blank_line_before,
+        };
+        if i > 0 && blank_line_before {
+            self.out.push('\n');
+        }
+
+        self.print_comments(&item.comments, depth);
+        if !item.comments.is_empty() && item.blank_line_before {
+            self.out.push('\n');
+        }
+
+        self.indent(depth);
+        match (item.key, in_document) {
+            (Some(key), false) => {
+                self.out.push_str(key);
+                self.out.push_str(": ");
+            }
+            (Some(name), true) => {
+                self.out.push_str("let ");
+                self.out.push_str(name);
+                self.out.push_str(" = ");
+            }
+            (None, _) => {}
+        }
+        self.print_node(&item.value, depth);
+        match (item.key, &item.value, in_document) {
+            (_, _, false) => self.out.push(','),
+            (Some(_), _, true)
+            | (
+                None,
+                Node::Include {
+                    keyword: "import", ..
+                },
+                true,
+            ) => self.out.push(';'),
+            (None, _, true) => {}
+        }
+
+        if let Some(trailing) = item.trailing {
+            self.out.push(' ');
+            self.out.push_str(trailing);
+        }
+        self.out.push('\n');
+    }
+}
+
+// whether the item can be printed inside of an inline list or tuple. `nested` allows tuples of
+// literals.
+fn is_inline(item: &Item, nested: bool) -> bool {
+    if !item.comments.is_empty() || item.trailing.is_some() {
+        return false;
+    }
+
+    is_inline_node(&item.value, nested)
+}
+
+fn is_inline_node(node: &Node, nested: bool) -> bool {
+    match node {
+        Node::Literal(_) | Node::Include { .. } => true,
+        Node::Anchored { value, .. } | Node::Negated(value) | Node::Paren(value) => {
+            is_inline_node(value, nested)
+        }
+        Node::Binary { lhs, rhs, .. } => is_inline_node(lhs, nested) && is_inline_node(rhs, nested),
+        Node::Commented {
+            before,
+            value,
+            after,
+        } => {
+            !before.iter().chain(after).any(|text| is_line_comment(text))
+                && is_inline_node(value, nested)
+        }
+        // function calls and lookups are part of an expression, like the operators
+        Node::Group {
+            kind: GroupKind::Tuple,
+            struct_type: Some(name),
+            items,
+            dangling,
+        } if expr::function(name).is_some() || LOOKUPS.contains(name) => {
+            dangling.is_empty() && items.iter().all(|item| is_inline(item, nested))
+        }
+        Node::Group {
+            kind: GroupKind::Tuple,
+            items,
+            dangling,
+            ..
+        } => nested && dangling.is_empty() && items.iter().all(|item| is_inline(item, false)),
+        Node::Group { .. } | Node::For { .. } | Node::Repeat { .. } => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_formats(source: &str, expected: &str) {
+        let formatted = format_source(source).unwrap();
+        assert_eq!(formatted, expected);
+        assert!(is_equivalent(source, &formatted));
+        // formatting is idempotent
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn scene_file() {
+        let scene = include_str!("../../src/scene.ababa");
+        let messy = scene.replace("\t\t", "  ").replace(",\n", "\n,");
+        assert_eq!(format_source(&messy).unwrap(), scene);
+    }
+
+    #[test]
+    fn layout() {
+        assert_formats(
+            "Ababa{a:1,b:[ 1,2 ],c:(1),i:(1,),d:[(1,2),(3,4)],e:[[1]],f:{},g:Baba(),h:Baba{}}",
+            "\
+Ababa {
+	a: 1,
+	b: [1, 2],
+	c: (1),
+	i: (1,),
+	d: [(1, 2), (3, 4)],
+	e: [
+		[1],
+	],
+	f: {},
+	g: Baba(),
+	h: Baba {},
+}
+",
+        );
+        assert_formats("  5.000  ", "5.000\n");
+        assert_formats("[\"a\\n\", true, -0.50]", "[\"a\\n\", true, -0.50]\n");
+        assert_formats("[ ..,1 ,.. ]", "[.., 1, ..]\n");
+    }
+
+    #[test]
+    fn comments() {
+        assert_formats(
+            "\
+// scene
+Ababa { // first
+  // about a
+  a: 1, // one
+  b: 2 /* two */ , c: [ // inside
+
+
+  1, 2,
+  ], d: 3
+  // the end
+} // trailing
+
+// dangling",
+            "\
+// scene
+Ababa {
+	// first
+	// about a
+	a: 1, // one
+	b: 2, /* two */
+	c: [
+		// inside
+
+		1,
+		2,
+	],
+	d: 3,
+	// the end
+} // trailing
+
+// dangling
+",
+        );
+    }
+
+    #[test]
+    fn blank_lines() {
+        assert_formats(
+            "{\n\n a: 1,\n\n\n\n b: 2,\n c: 3,\n\n // d\n d: 4,\n\n // end\n}",
+            "{\n\ta: 1,\n\n\tb: 2,\n\tc: 3,\n\n\t// d\n\td: 4,\n\n\t// end\n}\n",
+        );
+    }
+
+    #[test]
+    fn comments_in_odd_places() {
+        // comments between tokens of the syntax are moved, but never dropped
+        assert_formats(
+            "Ababa /* a */ { b /* c */ : /* d */ [1] }",
+            "\
+Ababa {
+	/* a */
+	b: /* c */ /* d */ [1],
+}
+",
+        );
+    }
+
+    #[test]
+    fn comments_in_values() {
+        assert_formats("1 + /* c */ 2", "1 + /* c */ 2\n");
+        assert_formats("(1 /* c */)", "(1 /* c */)\n");
+        assert_formats(
+            "{ a: /* before value */ 1 }",
+            "{\n\ta: /* before value */ 1,\n}\n",
+        );
+        assert_formats("[-/* a */1 /* b */*2, 3]", "[-/* a */ 1 /* b */ * 2, 3]\n");
+        // line comments end the line, the value continues on the next one
+        assert_formats("[1 + // c\n2]", "[\n\t1 + // c\n\t\t2,\n]\n");
+        assert_formats("(1 // c\n)", "(1 // c\n\t)\n");
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(
+            format_source("{ a: [1, 2 }"),
+            Err(AbabaParseError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            format_source("// nothing"),
+            Err(AbabaParseError::NoContent)
+        ));
+        assert!(matches!(
+            format_source("[1, 2] 3"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "end of input",
+                ..
+            })
+        ));
+        assert!(matches!(
+            format_source("{ a: [1, 2"),
+            Err(AbabaParseError::MissingEndBrace { brace: ']', span }) if span == Span::new(5, 6)
+        ));
+        assert!(matches!(
+            format_source("[\"a\\q\"]"),
+            Err(AbabaParseError::InvalidEscape { .. })
+        ));
+        assert!(matches!(
+            format_source("[1, \"a]"),
+            Err(AbabaParseError::Unterminated { what: "string", .. })
+        ));
+        assert!(matches!(
+            format_source("{ a: .. }"),
+            Err(AbabaParseError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            format_source("[include \"a\"; 1]"),
+            Err(AbabaParseError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            format_source("[import \"a\"]"),
+            Err(AbabaParseError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            format_source(&format!(
+                "{}1{}",
+                "[".repeat(MAX_DEPTH),
+                "]".repeat(MAX_DEPTH)
+            )),
+            Err(AbabaParseError::TooDeep { .. })
+        ));
+    }
+
+    #[test]
+    fn bindings() {
+        assert_formats(
+            "// colors\nlet white=(255,255,255) ; // white\nlet a = &b [1,2];\n\n\n[ $white,&c{x:$a}, $b,$c ]",
+            "\
+// colors
+let white = (255, 255, 255); // white
+let a = &b [1, 2];
+
+[
+	$white,
+	&c {
+		x: $a,
+	},
+	$b,
+	$c,
+]
+",
+        );
+        assert_formats("let a=1;[$a, &b $a, $b]", "let a = 1;\n[$a, &b $a, $b]\n");
+    }
+
+    #[test]
+    fn expressions() {
+        assert_formats(
+            "let a=2;[1+2*-3, (1+2)/ 4,-(pi), sin( rad(45) )*$a, ((1)), Point(1), (1,)]",
+            "let a = 2;\n[1 + 2 * -3, (1 + 2) / 4, -(pi), sin(rad(45)) * $a, ((1)), Point(1), (1,)]\n",
+        );
+        assert_formats(
+            "{ a: env( \"A\", 1 )*2, b: param(\"b\",\"x\") }",
+            "{\n\ta: env(\"A\", 1) * 2,\n\tb: param(\"b\", \"x\"),\n}\n",
+        );
+        assert_formats(
+            "{ a: 1 + /* one */ 2, b: 2 /* two */ * 3 // six\n }",
+            "{\n\ta: 1 + /* one */ 2,\n\tb: 2 /* two */ * 3, // six\n}\n",
+        );
+    }
+
+    #[test]
+    fn loops() {
+        assert_formats(
+            "let n=1;[for i in 0..$n*2{Sphere{x:$i}, // one\n repeat 2{1}},..]",
+            "\
+let n = 1;
+[
+	for i in 0..$n * 2 {
+		Sphere {
+			x: $i,
+		}, // one
+		repeat 2 {
+			1,
+		},
+	},
+	..,
+]
+",
+        );
+        assert_formats("[repeat 0 {}]", "[\n\trepeat 0 {},\n]\n");
+        assert_formats(
+            "[for i in 1. .. 3 {$i}]",
+            "[\n\tfor i in 1. ..3 {\n\t\t$i,\n\t},\n]\n",
+        );
+    }
+
+    #[test]
+    fn includes() {
+        let source =
+            "import\"materials.ababa\" ; // colors\n{lights:include   \"lights.ababa\",a:$red}";
+        assert_formats(
+            source,
+            "import \"materials.ababa\"; // colors\n{\n\tlights: include \"lights.ababa\",\n\ta: $red,\n}\n",
+        );
+    }
+
+    #[test]
+    fn unevaluated() {
+        // nothing is evaluated, so the formatting doesn't depend on the params, the environment,
+        // the included files or how long the loops are
+        assert_formats(
+            "{quality:param(\"quality\"),home:env(\"ABABA_FMT_UNSET\"),a:include \"missing.ababa\",b:$nope,c:[repeat 1000000{1}],d:1/0}",
+            "\
+{
+	quality: param(\"quality\"),
+	home: env(\"ABABA_FMT_UNSET\"),
+	a: include \"missing.ababa\",
+	b: $nope,
+	c: [
+		repeat 1000000 {
+			1,
+		},
+	],
+	d: 1 / 0,
+}
+",
+        );
+    }
+
+    #[test]
+    fn equivalent() {
+        assert!(is_equivalent("[1,2,]", "[1, 2]\n"));
+        assert!(is_equivalent("{a:1}", "{\n\ta: 1, // one\n}\n"));
+        assert!(is_equivalent("(1,)", "(1,)"));
+        assert!(is_equivalent("Point(1,)", "Point(1)"));
+        assert!(is_equivalent("(1,2,)", "(1, 2)"));
+        assert!(!is_equivalent("(1,)", "(1)"));
+        assert!(!is_equivalent("[1, 2]", "[1, 3]"));
+        assert!(!is_equivalent("[1, 2]", "[1 2]"));
+        assert!(!is_equivalent("0.50", "0.5"));
+    }
+}
@@ -1,30 +1,71 @@
-use crate::{AbabaParseError, AbabaValue};
+use crate::{AbabaParseError, AbabaValue, AbabaValueKind, Span};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 
 impl TryFrom<AbabaValue> for f64 {
     type Error = AbabaParseError;
 
     fn try_from(value: AbabaValue) -> Result<Self, Self::Error> {
-        match value {
-            AbabaValue::Number(value) => Ok(value),
-            _ => Err(AbabaParseError::ValueTypeDidNotMatch {
+        match value.kind {
+            AbabaValueKind::Number(value) => Ok(value),
+            kind => Err(AbabaParseError::ValueTypeDidNotMatch {
                 expected: "Number",
-                got: value,
+                got: AbabaValue::new(kind, value.span),
             }),
         }
     }
 }
 
+impl TryFrom<AbabaValue> for String {
+    type Error = AbabaParseError;
+
+    fn try_from(value: AbabaValue) -> Result<Self, Self::Error> {
+        match value.kind {
+            AbabaValueKind::String(value) => Ok(value),
+            kind => Err(AbabaParseError::ValueTypeDidNotMatch {
+                expected: "String",
+                got: AbabaValue::new(kind, value.span),
+            }),
+        }
+    }
+}
+
+impl TryFrom<AbabaValue> for bool {
+    type Error = AbabaParseError;
+
+    fn try_from(value: AbabaValue) -> Result<Self, Self::Error> {
+        match value.kind {
+            AbabaValueKind::Bool(value) => Ok(value),
+            kind => Err(AbabaParseError::ValueTypeDidNotMatch {
+                expected: "Bool",
+                got: AbabaValue::new(kind, value.span),
+            }),
+        }
+    }
+}
+
+impl TryFrom<AbabaValue> for PathBuf {
+    type Error = AbabaParseError;
+
+    fn try_from(value: AbabaValue) -> Result<Self, Self::Error> {
+        String::try_from(value).map(PathBuf::from)
+    }
+}
+
 macro_rules! impl_from_number {
     ($t:ty) => {
         impl TryFrom<AbabaValue> for $t {
             type Error = AbabaParseError;
 
             fn try_from(value: AbabaValue) -> Result<Self, Self::Error> {
+                let span = value.span;
                 let x = f64::try_from(value)?;
                 if x > <$t>::MAX as f64 || x < <$t>::MIN as f64 {
                     Err(AbabaParseError::NumberOutOfBounds {
                         x,
                         target_type: stringify!($t),
+                        span,
                     })
                 } else {
                     Ok(x as $t)
@@ -49,64 +90,267 @@ impl<T: TryFrom<AbabaValue, Error = AbabaParseError>> TryFrom<AbabaValue> for Ve
     type Error = AbabaParseError;
 
     fn try_from(value: AbabaValue) -> Result<Self, Self::Error> {
-        match value {
+        match value.kind {
             // Result implements FromIterator, the iterator will stop on the first Err
             // and return it, otherwise it will contain the collected structure (in this case Vec<T>)
-            AbabaValue::List(v) => Ok(v.into_iter().map(T::try_from).collect::<Result<_, _>>()?),
-            _ => Err(AbabaParseError::ValueTypeDidNotMatch {
+            AbabaValueKind::List(v) => Ok(v
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| T::try_from(v).map_err(|e| e.at_index(i)))
+                .collect::<Result<_, _>>()?),
+            kind => Err(AbabaParseError::ValueTypeDidNotMatch {
                 expected: "List",
-                got: value,
+                got: AbabaValue::new(kind, value.span),
             }),
         }
     }
 }
 
-impl<T: TryFrom<AbabaValue, Error = AbabaParseError>> TryFrom<AbabaValue> for (T, T) {
+/// `None`, or the value of `T`. fields of derived structs don't need this, a missing `Option`
+/// field is `None` already.
+impl<T: TryFrom<AbabaValue, Error = AbabaParseError>> TryFrom<AbabaValue> for Option<T> {
     type Error = AbabaParseError;
 
     fn try_from(value: AbabaValue) -> Result<Self, Self::Error> {
-        match value {
-            AbabaValue::Tuple(v) => {
-                if v.len() < 2 {
-                    Err(AbabaParseError::NotEnoughElements {
-                        expected: 2,
-                        got: v.len(),
-                    })
-                } else {
-                    Ok((v[0].to_owned().try_into()?, v[1].to_owned().try_into()?))
-                }
-            }
-            _ => Err(AbabaParseError::ValueTypeDidNotMatch {
-                expected: "Tuple",
-                got: value,
-            }),
+        match &value.kind {
+            AbabaValueKind::Ident(ident) if ident == "None" => Ok(None),
+            _ => T::try_from(value).map(Some),
         }
     }
 }
 
-impl<T: TryFrom<AbabaValue, Error = AbabaParseError>> TryFrom<AbabaValue> for (T, T, T) {
+/// a list with exactly `N` items
+impl<T: TryFrom<AbabaValue, Error = AbabaParseError>, const N: usize> TryFrom<AbabaValue>
+    for [T; N]
+{
     type Error = AbabaParseError;
 
     fn try_from(value: AbabaValue) -> Result<Self, Self::Error> {
-        match value {
-            AbabaValue::Tuple(v) => {
-                if v.len() < 3 {
-                    Err(AbabaParseError::NotEnoughElements {
-                        expected: 3,
-                        got: v.len(),
-                    })
-                } else {
-                    Ok((
-                        v[0].to_owned().try_into()?,
-                        v[1].to_owned().try_into()?,
-                        v[2].to_owned().try_into()?,
-                    ))
+        let span = value.span;
+        let items: Vec<T> = value.try_into()?;
+        check_len(items.len(), N, span)?;
+        Ok(items
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("the length was checked")))
+    }
+}
+
+/// objects with any fields, the type of the object is ignored
+impl<T: TryFrom<AbabaValue, Error = AbabaParseError>> TryFrom<AbabaValue> for HashMap<String, T> {
+    type Error = AbabaParseError;
+
+    fn try_from(value: AbabaValue) -> Result<Self, Self::Error> {
+        map_fields(value)
+    }
+}
+
+/// objects with any fields, the type of the object is ignored
+impl<T: TryFrom<AbabaValue, Error = AbabaParseError>> TryFrom<AbabaValue> for BTreeMap<String, T> {
+    type Error = AbabaParseError;
+
+    fn try_from(value: AbabaValue) -> Result<Self, Self::Error> {
+        map_fields(value)
+    }
+}
+
+// converts all fields of an object, the first error in the input is returned
+fn map_fields<T, M>(value: AbabaValue) -> Result<M, AbabaParseError>
+where
+    T: TryFrom<AbabaValue, Error = AbabaParseError>,
+    M: FromIterator<(String, T)>,
+{
+    match value.kind {
+        AbabaValueKind::Object { fields, .. } => {
+            let mut fields: Vec<_> = fields.into_iter().collect();
+            fields.sort_by_key(|(_, value)| value.span.start);
+            fields
+                .into_iter()
+                .map(|(name, value)| match T::try_from(value) {
+                    Ok(value) => Ok((name, value)),
+                    Err(e) => Err(e.in_field(&name)),
+                })
+                .collect()
+        }
+        kind => Err(AbabaParseError::ValueTypeDidNotMatch {
+            expected: "Object",
+            got: AbabaValue::new(kind, value.span),
+        }),
+    }
+}
+
+/// errors unless there are exactly `expected` items
+pub(crate) fn check_len(got: usize, expected: usize, span: Span) -> Result<(), AbabaParseError> {
+    match got.cmp(&expected) {
+        Ordering::Less => Err(AbabaParseError::NotEnoughElements {
+            expected: expected as i32,
+            got,
+            span,
+        }),
+        Ordering::Greater => Err(AbabaParseError::TooManyElements {
+            expected,
+            got,
+            span,
+        }),
+        Ordering::Equal => Ok(()),
+    }
+}
+
+// tuples with exactly as many items as the rust tuple, of any type
+macro_rules! impl_from_tuple {
+    ($len:literal: $($t:ident $i:tt),+) => {
+        impl<$($t: TryFrom<AbabaValue, Error = AbabaParseError>),+> TryFrom<AbabaValue>
+            for ($($t,)+)
+        {
+            type Error = AbabaParseError;
+
+            fn try_from(value: AbabaValue) -> Result<Self, Self::Error> {
+                match value.kind {
+                    AbabaValueKind::Tuple { items, .. } => {
+                        check_len(items.len(), $len, value.span)?;
+                        let mut items = items.into_iter();
+                        Ok(($(
+                            $t::try_from(items.next().expect("the length was checked"))
+                                .map_err(|e| e.at_index($i))?,
+                        )+))
+                    }
+                    kind => Err(AbabaParseError::ValueTypeDidNotMatch {
+                        expected: "Tuple",
+                        got: AbabaValue::new(kind, value.span),
+                    }),
                 }
             }
-            _ => Err(AbabaParseError::ValueTypeDidNotMatch {
-                expected: "Tuple",
-                got: value,
-            }),
         }
+    };
+}
+
+impl_from_tuple!(1: A 0);
+impl_from_tuple!(2: A 0, B 1);
+impl_from_tuple!(3: A 0, B 1, C 2);
+impl_from_tuple!(4: A 0, B 1, C 2, D 3);
+impl_from_tuple!(5: A 0, B 1, C 2, D 3, E 4);
+impl_from_tuple!(6: A 0, B 1, C 2, D 3, E 4, F 5);
+impl_from_tuple!(7: A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_from_tuple!(8: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+impl_from_tuple!(9: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+impl_from_tuple!(10: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+impl_from_tuple!(11: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+impl_from_tuple!(12: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AbabaParser;
+
+    fn parse<T: TryFrom<AbabaValue, Error = AbabaParseError>>(
+        s: &str,
+    ) -> Result<T, AbabaParseError> {
+        AbabaParser::new(s).parse()?.try_into()
+    }
+
+    #[test]
+    fn strings_bools_and_paths() {
+        assert_eq!(parse::<String>(r#""wood.ppm""#).unwrap(), "wood.ppm");
+        assert!(parse::<bool>("true").unwrap());
+        assert!(!parse::<bool>("false").unwrap());
+        assert_eq!(
+            parse::<PathBuf>(r#""textures/wood.ppm""#).unwrap(),
+            PathBuf::from("textures/wood.ppm")
+        );
+
+        assert!(matches!(
+            parse::<String>("5"),
+            Err(AbabaParseError::ValueTypeDidNotMatch {
+                expected: "String",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse::<bool>(r#""true""#),
+            Err(AbabaParseError::ValueTypeDidNotMatch {
+                expected: "Bool",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn options() {
+        assert_eq!(parse::<Option<u8>>("None").unwrap(), None);
+        assert_eq!(parse::<Option<u8>>("5").unwrap(), Some(5));
+        assert_eq!(
+            parse::<Vec<Option<String>>>(r#"[None, "a"]"#).unwrap(),
+            [None, Some("a".to_string())]
+        );
+        assert_eq!(
+            parse::<Option<u8>>("Some").unwrap_err().to_string(),
+            "expected Number, got Ident"
+        );
+    }
+
+    #[test]
+    fn tuples_and_arrays() {
+        assert_eq!(
+            parse::<(u8, String, bool)>(r#"(1, "a", true)"#).unwrap(),
+            (1, "a".to_string(), true)
+        );
+        assert_eq!(parse::<(f64,)>("(1,)").unwrap(), (1.,));
+        assert_eq!(
+            parse::<(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8)>(
+                "(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12)"
+            )
+            .unwrap(),
+            (1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12)
+        );
+        assert_eq!(parse::<[u8; 3]>("[1, 2, 3]").unwrap(), [1, 2, 3]);
+        assert_eq!(
+            parse::<Vec<([f64; 2], Option<bool>)>>("[([1, 2], None), ([3, 4], true)]").unwrap(),
+            [([1., 2.], None), ([3., 4.], Some(true))]
+        );
+
+        let errors = [
+            parse::<(u8, u8)>("(1, 2, 3)").unwrap_err(),
+            parse::<(u8, u8)>("(1,)").unwrap_err(),
+            parse::<(u8, String)>("(1, 2)").unwrap_err(),
+            parse::<[u8; 2]>("[1, 2, 3]").unwrap_err(),
+            parse::<[u8; 2]>("(1, 2)").unwrap_err(),
+            parse::<Vec<[u8; 2]>>("[[1, 2], [3]]").unwrap_err(),
+        ];
+        assert_eq!(
+            errors.map(|e| e.to_string()),
+            [
+                "expected 2 elements, got 3",
+                "expected 2 elements, got 1",
+                "expected String, got Number (at `[1]`)",
+                "expected 2 elements, got 3",
+                "expected List, got Tuple",
+                "expected 2 elements, got 1 (at `[1]`)",
+            ]
+        );
+    }
+
+    #[test]
+    fn maps() {
+        let map = parse::<HashMap<String, Vec<u8>>>("{ a: [1], b: [] }").unwrap();
+        assert_eq!(map, [("a".into(), vec![1]), ("b".into(), vec![])].into());
+
+        let map = parse::<BTreeMap<String, HashMap<String, bool>>>("{ b: {}, a: { c: true } }");
+        assert_eq!(
+            map.unwrap().into_iter().collect::<Vec<_>>(),
+            [
+                ("a".to_string(), [("c".to_string(), true)].into()),
+                ("b".to_string(), HashMap::new()),
+            ]
+        );
+
+        assert_eq!(
+            parse::<HashMap<String, u8>>("{ b: 1, c: \"x\", a: 256 }")
+                .unwrap_err()
+                .to_string(),
+            "expected Number, got String (at `c`)"
+        );
+        assert_eq!(
+            parse::<BTreeMap<String, u8>>("[]").unwrap_err().to_string(),
+            "expected Object, got List"
+        );
     }
 }
@@ -0,0 +1,109 @@
+//! reading of the files referred to by `include "..."` and `import "...";`, and of the
+//! environment variables of `env("NAME")`
+//!
+//! the parser only knows paths, an [AbabaResolver] turns them into source. paths are relative to
+//! the including file and are normalized before they are passed to the resolver, so the same file
+//! is always read with the same path (which is also used to detect include cycles).
+
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+
+/// provides the contents of included files
+pub trait AbabaResolver {
+    /// the contents of the file at `path`
+    fn read(&self, path: &Path) -> std::io::Result<String>;
+
+    /// the value of the environment variable `name` for `env("NAME")`, read from the process
+    /// environment by default
+    fn env(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+}
+
+/// reads included files from the file system, the default resolver of [crate::AbabaParser]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct AbabaFileResolver;
+
+impl AbabaResolver for AbabaFileResolver {
+    fn read(&self, path: &Path) -> std::io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+}
+
+/// files in memory, mostly useful for tests
+impl AbabaResolver for HashMap<PathBuf, String> {
+    fn read(&self, path: &Path) -> std::io::Result<String> {
+        self.get(path)
+            .cloned()
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "file not found"))
+    }
+}
+
+/// `path` relative to the directory of `file` (or the current directory if there's no file),
+/// without any `.` and `..` components that can be removed
+pub(crate) fn relative_to(file: Option<&Path>, path: &str) -> PathBuf {
+    let dir = file.and_then(Path::parent).unwrap_or(Path::new(""));
+    normalize(&dir.join(path))
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_paths() {
+        let path = |file: Option<&str>, path| relative_to(file.map(Path::new), path);
+
+        assert_eq!(path(None, "a.ababa"), Path::new("a.ababa"));
+        assert_eq!(path(Some("a.ababa"), "./b.ababa"), Path::new("b.ababa"));
+        assert_eq!(
+            path(Some("scenes/a.ababa"), "lights/b.ababa"),
+            Path::new("scenes/lights/b.ababa")
+        );
+        assert_eq!(
+            path(Some("scenes/lights/b.ababa"), "../materials.ababa"),
+            Path::new("scenes/materials.ababa")
+        );
+        assert_eq!(
+            path(Some("a.ababa"), "../../b.ababa"),
+            Path::new("../../b.ababa")
+        );
+        assert_eq!(
+            path(Some("scenes/a.ababa"), "/lib/b.ababa"),
+            Path::new("/lib/b.ababa")
+        );
+    }
+
+    #[test]
+    fn memory_resolver() {
+        let files = HashMap::from([(PathBuf::from("a.ababa"), "1".to_string())]);
+
+        assert_eq!(files.read(Path::new("a.ababa")).unwrap(), "1");
+        assert_eq!(
+            files.read(Path::new("b.ababa")).unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+    }
+}
@@ -0,0 +1,1113 @@
+//! conversion of [AbabaValue] to and from JSON.
+//!
+//! the conversion is lossless for everything that can be written as ababa, everything JSON doesn't
+//! have is written as an object with a key starting with `$` (field names can't start with one):
+//!
+//! | ababa               | JSON                                         |
+//! |---------------------|----------------------------------------------|
+//! | `Sphere { r: 1 }`   | `{"$type": "Sphere", "r": 1}`                |
+//! | `(1, 2)`            | `{"$tuple": [1, 2]}`                         |
+//! | `Rgb(1, 2, 3)`      | `{"$type": "Rgb", "$tuple": [1, 2, 3]}`      |
+//! | `Red`               | `{"$ident": "Red"}`                          |
+//! | `..`                | `{"$spread": true}`                          |
+//! | `inf`, `nan`        | `{"$number": "inf"}`, `{"$number": "nan"}`   |
+//!
+//! numbers, strings, bools, lists and untyped objects are the same in both. JSON written by other
+//! tools can be read as well, as long as it doesn't contain `null` and could be written as ababa:
+//! keys, types and idents have to be valid idents, `$spread` can only be an item of a list, numbers
+//! have to be finite and values can't be nested deeper than in ababa. the spans of the values read
+//! from JSON point into the JSON text.
+//!
+//! ```
+//! use ababa_config::{from_json, to_json, AbabaParser};
+//!
+//! let value = AbabaParser::new("Sphere { position: (0, 1, 0), material: Red }").parse().unwrap();
+//! let json = to_json(&value);
+//! assert_eq!(
+//!     json,
+//!     r#"{"$type":"Sphere","material":{"$ident":"Red"},"position":{"$tuple":[0,1,0]}}"#
+//! );
+//! assert_eq!(from_json(&json).unwrap(), value);
+//! ```
+
+use crate::fields::suggest;
+use crate::parser::MAX_DEPTH;
+use crate::printer::{is_ident_value, is_tuple_type, Number};
+use crate::tokenizer::is_ident;
+use crate::{AbabaParseError, AbabaValue, AbabaValueKind, Span};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Write};
+
+const SPECIAL_KEYS: &[&str] = &["$type", "$tuple", "$ident", "$spread", "$number"];
+
+/// `value` as JSON on a single line
+pub fn to_json(value: &AbabaValue) -> String {
+    let mut s = String::new();
+    write_value(&mut s, value, None, 0);
+    s
+}
+
+/// `value` as JSON, indented with two spaces
+pub fn to_json_pretty(value: &AbabaValue) -> String {
+    let mut s = String::new();
+    write_value(&mut s, value, Some("  "), 0);
+    s
+}
+
+/// reads a JSON text written by [to_json], or any other JSON without `null`
+pub fn from_json(s: &str) -> Result<AbabaValue, AbabaParseError> {
+    let mut reader = JsonReader {
+        s,
+        pos: 0,
+        depth: 0,
+    };
+
+    reader.skip_whitespace();
+    if reader.pos == s.len() {
+        return Err(AbabaParseError::NoContent);
+    }
+
+    let value = not_spread(reader.read_value()?)?;
+
+    reader.skip_whitespace();
+    match reader.pos == s.len() {
+        true => Ok(value),
+        false => Err(reader.unexpected("end of input")),
+    }
+}
+
+/// any JSON value, including `null`. used by the language server for its messages, which aren't
+/// ababa values. objects keep the order of their keys.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// reads any JSON text, the errors are the ones of [from_json]
+    pub fn parse(s: &str) -> Result<JsonValue, AbabaParseError> {
+        let mut reader = JsonReader {
+            s,
+            pos: 0,
+            depth: 0,
+        };
+        let value = reader.read_any()?;
+
+        reader.skip_whitespace();
+        match reader.pos == s.len() {
+            true => Ok(value),
+            false => Err(reader.unexpected("end of input")),
+        }
+    }
+
+    /// an object with the entries in this order
+    pub fn object<const N: usize>(entries: [(&str, JsonValue); N]) -> JsonValue {
+        JsonValue::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// the value of `key`, `Null` if it's missing or this isn't an object
+    pub fn get(&self, key: &str) -> &JsonValue {
+        match self {
+            JsonValue::Object(entries) => entries
+                .iter()
+                .find(|(k, _)| k == key)
+                .map_or(&JsonValue::Null, |(_, value)| value),
+            _ => &JsonValue::Null,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            JsonValue::Number(x) if *x >= 0. && x.fract() == 0. => Some(*x as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> &[JsonValue] {
+        match self {
+            JsonValue::Array(items) => items,
+            _ => &[],
+        }
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(s: &str) -> Self {
+        JsonValue::String(s.to_string())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(s: String) -> Self {
+        JsonValue::String(s)
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(b: bool) -> Self {
+        JsonValue::Bool(b)
+    }
+}
+
+impl From<usize> for JsonValue {
+    fn from(x: usize) -> Self {
+        JsonValue::Number(x as f64)
+    }
+}
+
+impl From<Vec<JsonValue>> for JsonValue {
+    fn from(items: Vec<JsonValue>) -> Self {
+        JsonValue::Array(items)
+    }
+}
+
+/// on a single line. numbers that aren't finite can't be written and are `null`.
+impl Display for JsonValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonValue::Null => write!(f, "null"),
+            JsonValue::Bool(b) => write!(f, "{b}"),
+            JsonValue::Number(x) if x.is_finite() => write!(f, "{}", Number(*x)),
+            JsonValue::Number(_) => write!(f, "null"),
+            JsonValue::String(s) => {
+                let mut out = String::new();
+                write_string(&mut out, s);
+                f.write_str(&out)
+            }
+            JsonValue::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+            JsonValue::Object(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{value}", JsonValue::String(key.clone()))?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_value(out: &mut String, value: &AbabaValue, indent: Option<&str>, depth: usize) {
+    match &value.kind {
+        AbabaValueKind::Number(x) if x.is_finite() => {
+            write!(out, "{}", Number(*x)).expect("writing to a String never fails")
+        }
+        AbabaValueKind::Number(x) => {
+            let x = Number(*x).to_string();
+            write_object(out, vec![("$number", Json::Str(&x))], indent, depth)
+        }
+        AbabaValueKind::String(s) => write_string(out, s),
+        AbabaValueKind::Bool(b) => write!(out, "{b}").expect("writing to a String never fails"),
+        AbabaValueKind::Ident(ident) => {
+            write_object(out, vec![("$ident", Json::Str(ident))], indent, depth)
+        }
+        AbabaValueKind::Spread => write_object(out, vec![("$spread", Json::True)], indent, depth),
+        AbabaValueKind::List(items) => write_array(out, items, indent, depth),
+        AbabaValueKind::Tuple { struct_type, items } => {
+            let mut entries = Vec::new();
+            if let Some(struct_type) = struct_type {
+                entries.push(("$type", Json::Str(struct_type)));
+            }
+            entries.push(("$tuple", Json::Array(items)));
+            write_object(out, entries, indent, depth)
+        }
+        AbabaValueKind::Object {
+            struct_type,
+            fields,
+        } => {
+            let mut entries = Vec::new();
+            if let Some(struct_type) = struct_type {
+                entries.push(("$type", Json::Str(struct_type)));
+            }
+            // like the printer, fields are sorted since their order isn't known
+            let mut fields: Vec<_> = fields.iter().collect();
+            fields.sort_by_key(|(name, _)| *name);
+            entries.extend(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name.as_str(), Json::Value(value))),
+            );
+            write_object(out, entries, indent, depth)
+        }
+    }
+}
+
+// the values of the entries of an object, most of them aren't an [AbabaValue]
+enum Json<'a> {
+    Value(&'a AbabaValue),
+    Array(&'a [AbabaValue]),
+    Str(&'a str),
+    True,
+}
+
+fn write_object(out: &mut String, entries: Vec<(&str, Json)>, indent: Option<&str>, depth: usize) {
+    write_group(out, ('{', '}'), entries.len(), indent, depth, |out, i| {
+        let (key, value) = &entries[i];
+        write_string(out, key);
+        out.push_str(if indent.is_some() { ": " } else { ":" });
+        match value {
+            Json::Value(value) => write_value(out, value, indent, depth + 1),
+            Json::Array(items) => write_array(out, items, indent, depth + 1),
+            Json::Str(s) => write_string(out, s),
+            Json::True => out.push_str("true"),
+        }
+    })
+}
+
+fn write_array(out: &mut String, items: &[AbabaValue], indent: Option<&str>, depth: usize) {
+    write_group(out, ('[', ']'), items.len(), indent, depth, |out, i| {
+        write_value(out, &items[i], indent, depth + 1)
+    })
+}
+
+// writes `len` items with `write_item` between the braces, one per line if indented
+fn write_group(
+    out: &mut String,
+    (open, close): (char, char),
+    len: usize,
+    indent: Option<&str>,
+    depth: usize,
+    mut write_item: impl FnMut(&mut String, usize),
+) {
+    out.push(open);
+
+    for i in 0..len {
+        if i > 0 {
+            out.push(',');
+        }
+        if let Some(indent) = indent {
+            out.push('\n');
+            out.push_str(&indent.repeat(depth + 1));
+        }
+        write_item(out, i);
+    }
+
+    if let Some(indent) = indent.filter(|_| len > 0) {
+        out.push('\n');
+        out.push_str(&indent.repeat(depth));
+    }
+    out.push(close);
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                write!(out, "\\u{:04x}", c as u32).expect("writing to a String never fails")
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+struct JsonReader<'s> {
+    s: &'s str,
+    /// byte offset of the next char
+    pos: usize,
+    /// how many values the next one is nested in
+    depth: usize,
+}
+
+impl JsonReader<'_> {
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.s[self.pos..];
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    // consumes `c` (after whitespace)
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += c.len_utf8();
+        }
+        found
+    }
+
+    // an error for the next char
+    fn unexpected(&self, expected: &'static str) -> AbabaParseError {
+        match self.peek() {
+            Some(c) => AbabaParseError::UnexpectedToken {
+                expected,
+                got: format!("{c:?}"),
+                span: Span::new(self.pos, self.pos + c.len_utf8()),
+            },
+            None => AbabaParseError::UnexpectedToken {
+                expected,
+                got: "end of input".to_string(),
+                span: Span::new(self.pos, self.pos),
+            },
+        }
+    }
+
+    // reads a value nested one level deeper, limited like in the parser so that deeply nested
+    // input can't overflow the stack
+    fn nested<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<T, AbabaParseError>,
+    ) -> Result<T, AbabaParseError> {
+        self.skip_whitespace();
+        if self.depth == MAX_DEPTH {
+            let len = self.peek().map_or(0, char::len_utf8);
+            return Err(AbabaParseError::TooDeep {
+                limit: MAX_DEPTH,
+                span: Span::new(self.pos, self.pos + len),
+            });
+        }
+
+        self.depth += 1;
+        let value = read(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn read_value(&mut self) -> Result<AbabaValue, AbabaParseError> {
+        self.nested(Self::read_value_inner)
+    }
+
+    fn read_value_inner(&mut self) -> Result<AbabaValue, AbabaParseError> {
+        let start = self.pos;
+
+        let kind = match self.peek() {
+            Some('{') => return self.read_object(),
+            Some('[') => {
+                let (items, span) = self.read_array()?;
+                return Ok(AbabaValue::new(AbabaValueKind::List(items), span));
+            }
+            Some('"') => AbabaValueKind::String(self.read_string()?),
+            Some('-' | '0'..='9') => AbabaValueKind::Number(self.read_number()?),
+            Some('a'..='z') => {
+                let rest = &self.s[start..];
+                let word = &rest[..rest
+                    .find(|c: char| !c.is_ascii_alphanumeric())
+                    .unwrap_or(rest.len())];
+                let kind = match word {
+                    "true" => AbabaValueKind::Bool(true),
+                    "false" => AbabaValueKind::Bool(false),
+                    "null" => {
+                        return Err(AbabaParseError::UnexpectedToken {
+                            expected: "a value (ababa has no null)",
+                            got: "`null`".to_string(),
+                            span: Span::new(start, start + word.len()),
+                        })
+                    }
+                    _ => return Err(self.unexpected("value")),
+                };
+                self.pos += word.len();
+                kind
+            }
+            _ => return Err(self.unexpected("value")),
+        };
+
+        Ok(AbabaValue::new(kind, Span::new(start, self.pos)))
+    }
+
+    // any JSON value, unlike `read_value` this allows `null` and doesn't look at `$` keys
+    fn read_any(&mut self) -> Result<JsonValue, AbabaParseError> {
+        self.nested(Self::read_any_inner)
+    }
+
+    fn read_any_inner(&mut self) -> Result<JsonValue, AbabaParseError> {
+        let open = Span::new(self.pos, self.pos + 1);
+
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                let mut entries = Vec::new();
+                if !self.eat('}') {
+                    loop {
+                        self.skip_whitespace();
+                        if self.peek() != Some('"') {
+                            return Err(self.unexpected("key"));
+                        }
+                        let key = self.read_string()?;
+                        if !self.eat(':') {
+                            return Err(self.unexpected("':'"));
+                        }
+                        entries.push((key, self.read_any()?));
+                        if self.eat('}') {
+                            break;
+                        }
+                        self.separator('}', open)?;
+                    }
+                }
+                Ok(JsonValue::Object(entries))
+            }
+            Some('[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if !self.eat(']') {
+                    loop {
+                        items.push(self.read_any()?);
+                        if self.eat(']') {
+                            break;
+                        }
+                        self.separator(']', open)?;
+                    }
+                }
+                Ok(JsonValue::Array(items))
+            }
+            Some('"') => Ok(JsonValue::String(self.read_string()?)),
+            Some('-' | '0'..='9') => Ok(JsonValue::Number(self.read_number()?)),
+            _ => {
+                let rest = &self.s[self.pos..];
+                let (len, value) = if rest.starts_with("true") {
+                    (4, JsonValue::Bool(true))
+                } else if rest.starts_with("false") {
+                    (5, JsonValue::Bool(false))
+                } else if rest.starts_with("null") {
+                    (4, JsonValue::Null)
+                } else {
+                    return Err(self.unexpected("value"));
+                };
+                self.pos += len;
+                Ok(value)
+            }
+        }
+    }
+
+    // `[` is the next char
+    fn read_array(&mut self) -> Result<(Vec<AbabaValue>, Span), AbabaParseError> {
+        let open = Span::new(self.pos, self.pos + 1);
+        self.pos += 1;
+        let mut items = Vec::new();
+
+        if !self.eat(']') {
+            loop {
+                items.push(self.read_value()?);
+                if self.eat(']') {
+                    break;
+                }
+                self.separator(']', open)?;
+            }
+        }
+
+        Ok((items, open.to(Span::new(self.pos - 1, self.pos))))
+    }
+
+    // `,` between items, or the missing closing brace
+    fn separator(&mut self, close: char, open: Span) -> Result<(), AbabaParseError> {
+        if self.eat(',') {
+            return Ok(());
+        }
+        match self.peek() {
+            None => Err(AbabaParseError::MissingEndBrace {
+                brace: close,
+                span: open,
+            }),
+            Some(_) if close == ']' => Err(self.unexpected("',' or ']'")),
+            Some(_) => Err(self.unexpected("',' or '}'")),
+        }
+    }
+
+    // `{` is the next char. the keys starting with `$` decide what kind of value it is.
+    fn read_object(&mut self) -> Result<AbabaValue, AbabaParseError> {
+        let open = Span::new(self.pos, self.pos + 1);
+        self.pos += 1;
+        let mut fields = HashMap::new();
+        let mut special = HashMap::new();
+
+        if !self.eat('}') {
+            loop {
+                self.skip_whitespace();
+                if self.peek() != Some('"') {
+                    return Err(match self.peek() {
+                        None => AbabaParseError::MissingEndBrace {
+                            brace: '}',
+                            span: open,
+                        },
+                        Some(_) => self.unexpected("key"),
+                    });
+                }
+                let key_start = self.pos;
+                let key = self.read_string()?;
+                let key_span = Span::new(key_start, self.pos);
+
+                if !self.eat(':') {
+                    return Err(self.unexpected("':'"));
+                }
+
+                if key.starts_with('$') && !SPECIAL_KEYS.contains(&key.as_str()) {
+                    return Err(AbabaParseError::UnknownField {
+                        suggestion: suggest(&key, SPECIAL_KEYS),
+                        field: key,
+                        span: key_span,
+                    });
+                }
+                if !key.starts_with('$') && !is_ident(&key) {
+                    return Err(AbabaParseError::InvalidName {
+                        name: key,
+                        span: key_span,
+                    });
+                }
+                let value = match key.as_str() {
+                    "$tuple" => {
+                        self.skip_whitespace();
+                        if self.peek() != Some('[') {
+                            let value = self.read_value()?;
+                            return Err(AbabaParseError::ValueTypeDidNotMatch {
+                                expected: "List",
+                                got: value,
+                            });
+                        }
+                        let (items, span) = self.read_array()?;
+                        let items = items
+                            .into_iter()
+                            .map(not_spread)
+                            .collect::<Result<_, _>>()?;
+                        AbabaValue::new(AbabaValueKind::List(items), span)
+                    }
+                    "$spread" => self.read_value()?,
+                    _ => not_spread(self.read_value()?)?,
+                };
+
+                let entries = match key.starts_with('$') {
+                    true => &mut special,
+                    false => &mut fields,
+                };
+                if entries.contains_key(&key) {
+                    return Err(AbabaParseError::DuplicateField {
+                        field: key,
+                        span: key_span,
+                    });
+                }
+                entries.insert(key, (key_span, value));
+
+                if self.eat('}') {
+                    break;
+                }
+                self.separator('}', open)?;
+            }
+        }
+
+        let span = open.to(Span::new(self.pos - 1, self.pos));
+        let kind = object_kind(fields, special, span)?;
+        Ok(AbabaValue::new(kind, span))
+    }
+
+    // `"` is the next char
+    fn read_string(&mut self) -> Result<String, AbabaParseError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut s = String::new();
+
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(AbabaParseError::Unterminated {
+                    what: "string",
+                    span: Span::new(start, self.pos),
+                });
+            };
+            let escape_start = self.pos;
+            self.pos += c.len_utf8();
+
+            match c {
+                '"' => return Ok(s),
+                c if (c as u32) < 0x20 => {
+                    return Err(AbabaParseError::UnexpectedToken {
+                        expected: "escape sequence",
+                        got: format!("control character {c:?}"),
+                        span: Span::new(escape_start, self.pos),
+                    })
+                }
+                '\\' => {
+                    let escaped = match self.peek() {
+                        Some('u') => {
+                            self.pos += 1;
+                            self.read_unicode_escape(escape_start)?
+                        }
+                        Some(c) => {
+                            self.pos += c.len_utf8();
+                            match c {
+                                '"' => '"',
+                                '\\' => '\\',
+                                '/' => '/',
+                                'b' => '\u{8}',
+                                'f' => '\u{c}',
+                                'n' => '\n',
+                                'r' => '\r',
+                                't' => '\t',
+                                _ => return Err(self.invalid_escape(escape_start)),
+                            }
+                        }
+                        None => continue,
+                    };
+                    s.push(escaped);
+                }
+                c => s.push(c),
+            }
+        }
+    }
+
+    // the char of `\uXXXX` (after the `u`), or of a surrogate pair `\uXXXX\uXXXX`
+    fn read_unicode_escape(&mut self, escape_start: usize) -> Result<char, AbabaParseError> {
+        let high = self.read_hex4(escape_start)?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.invalid_escape(escape_start));
+        }
+
+        if !self.s[self.pos..].starts_with("\\u") {
+            return Err(self.invalid_escape(escape_start));
+        }
+        self.pos += 2;
+        let low = self.read_hex4(escape_start)?;
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err(self.invalid_escape(escape_start));
+        }
+
+        let c = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+        char::from_u32(c).ok_or_else(|| self.invalid_escape(escape_start))
+    }
+
+    fn read_hex4(&mut self, escape_start: usize) -> Result<u32, AbabaParseError> {
+        let digits = self.s.get(self.pos..self.pos + 4);
+        match digits.filter(|d| d.chars().all(|c| c.is_ascii_hexdigit())) {
+            Some(digits) => {
+                self.pos += 4;
+                Ok(u32::from_str_radix(digits, 16).expect("checked the digits"))
+            }
+            None => Err(self.invalid_escape(escape_start)),
+        }
+    }
+
+    // the escape from `start` up to the current position
+    fn invalid_escape(&self, start: usize) -> AbabaParseError {
+        AbabaParseError::InvalidEscape {
+            escape: self.s[start..self.pos].to_string(),
+            span: Span::new(start, self.pos),
+        }
+    }
+
+    // number ::= '-'? ('0' | [1-9][0-9]*) ('.' [0-9]+)? ([eE] [+-]? [0-9]+)?
+    fn read_number(&mut self) -> Result<f64, AbabaParseError> {
+        let start = self.pos;
+        let rest = &self.s[start..];
+        let len = rest
+            .find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+            .unwrap_or(rest.len());
+        let s = &rest[..len];
+        self.pos += len;
+
+        let invalid = || AbabaParseError::InvalidNumber {
+            s: s.to_string(),
+            span: Span::new(start, start + len),
+        };
+
+        let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let unsigned = s.strip_prefix('-').unwrap_or(s);
+        let int = digits(unsigned);
+        let mut rest = &unsigned[int..];
+        if int == 0 || (int > 1 && unsigned.starts_with('0')) {
+            return Err(invalid());
+        }
+        if let Some(fraction) = rest.strip_prefix('.') {
+            let len = digits(fraction);
+            if len == 0 {
+                return Err(invalid());
+            }
+            rest = &fraction[len..];
+        }
+        if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+            let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+            let len = digits(exponent);
+            if len == 0 {
+                return Err(invalid());
+            }
+            rest = &exponent[len..];
+        }
+        if !rest.is_empty() {
+            return Err(invalid());
+        }
+
+        let x: f64 = s.parse().map_err(|_| invalid())?;
+        match x.is_finite() {
+            true => Ok(x),
+            false => Err(AbabaParseError::NotFinite {
+                span: Span::new(start, start + len),
+            }),
+        }
+    }
+}
+
+// the kind of a JSON object with the `special` keys (starting with `$`) and other `fields`
+fn object_kind(
+    fields: HashMap<String, (Span, AbabaValue)>,
+    mut special: HashMap<String, (Span, AbabaValue)>,
+    span: Span,
+) -> Result<AbabaValueKind, AbabaParseError> {
+    // idents, numbers and `..` are objects with a single key, tuples can have a type
+    let single = ["$ident", "$number", "$spread"]
+        .into_iter()
+        .find(|key| special.contains_key(*key));
+    let allowed: &[&str] = match (single, special.contains_key("$tuple")) {
+        (Some(key), _) => &[key],
+        (None, true) => &["$tuple", "$type"],
+        (None, false) => &[],
+    };
+    if !allowed.is_empty() {
+        let other = special
+            .iter()
+            .filter(|(key, _)| !allowed.contains(&key.as_str()))
+            .chain(&fields)
+            .min_by_key(|(_, (key_span, _))| key_span.start);
+        if let Some((field, (key_span, _))) = other {
+            return Err(AbabaParseError::UnknownField {
+                field: field.clone(),
+                suggestion: None,
+                span: *key_span,
+            });
+        }
+    }
+
+    let is_tuple = special.contains_key("$tuple");
+    let mut take = |key: &str| special.remove(key).map(|(_, value)| value);
+    // the string of `key`, checked with `valid` if it's a name
+    let string = |key: &str, value: Option<AbabaValue>, valid: Option<fn(&str) -> bool>| {
+        let err = match value {
+            Some(AbabaValue {
+                kind: AbabaValueKind::String(s),
+                span,
+                ..
+            }) => match valid {
+                Some(valid) if !valid(&s) => AbabaParseError::InvalidName { name: s, span },
+                _ => return Ok(Some(s)),
+            },
+            Some(value) => AbabaParseError::ValueTypeDidNotMatch {
+                expected: "String",
+                got: value,
+            },
+            None => return Ok(None),
+        };
+        Err(err.in_field(key))
+    };
+
+    let type_name = match is_tuple {
+        true => is_tuple_type,
+        false => is_ident,
+    };
+    let struct_type = string("$type", take("$type"), Some(type_name))?;
+    if let Some(ident) = string("$ident", take("$ident"), Some(is_ident_value))? {
+        return Ok(AbabaValueKind::Ident(ident));
+    }
+    if let Some(number) = string("$number", take("$number"), None)? {
+        return match number.as_str() {
+            "inf" => Ok(AbabaValueKind::Number(f64::INFINITY)),
+            "-inf" => Ok(AbabaValueKind::Number(f64::NEG_INFINITY)),
+            "nan" => Ok(AbabaValueKind::Number(f64::NAN)),
+            _ => Err(AbabaParseError::InvalidNumber { s: number, span }.in_field("$number")),
+        };
+    }
+    if let Some(value) = take("$spread") {
+        return match value.kind {
+            AbabaValueKind::Bool(true) => Ok(AbabaValueKind::Spread),
+            _ => Err(AbabaParseError::ValueTypeDidNotMatch {
+                expected: "`true`",
+                got: value,
+            }
+            .in_field("$spread")),
+        };
+    }
+    if let Some(list) = take("$tuple") {
+        let AbabaValueKind::List(items) = list.kind else {
+            unreachable!("`$tuple` is always read as a list")
+        };
+        return Ok(AbabaValueKind::Tuple { struct_type, items });
+    }
+
+    let fields = fields
+        .into_iter()
+        .map(|(name, (_, value))| (name, value))
+        .collect();
+    Ok(AbabaValueKind::Object {
+        struct_type,
+        fields,
+    })
+}
+
+// `..` is only an item of a list, like in ababa
+fn not_spread(value: AbabaValue) -> Result<AbabaValue, AbabaParseError> {
+    match value.kind {
+        AbabaValueKind::Spread => Err(AbabaParseError::UnexpectedToken {
+            expected: "value",
+            got: "'..' outside of a list".to_string(),
+            span: value.span,
+        }),
+        _ => Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AbabaParser;
+
+    fn parse(s: &str) -> AbabaValue {
+        AbabaParser::new(s).allow_non_finite().parse().unwrap()
+    }
+
+    fn error(json: &str) -> String {
+        from_json(json).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn round_trip() {
+        let values = [
+            "1",
+            "-0.25",
+            "1e300",
+            "\"a \\\"b\\\" \\\\ \\n \\t \\u{1} ä 🦀\"",
+            "[true, false, [], {}, ()]",
+            "Ababa { a: (1, \"b\"), c: Rgb(1, 2, 3), d: [.., Red] }",
+            "(Empty(), Empty {}, (1,))",
+            "[inf, -inf]",
+            "[1e300, 5e-324, 1.5e-7]",
+        ];
+
+        for s in values {
+            let value = parse(s);
+            for json in [to_json(&value), to_json_pretty(&value)] {
+                assert_eq!(from_json(&json).unwrap(), value, "{json}");
+            }
+        }
+
+        let nan = from_json(&to_json(&parse("nan"))).unwrap();
+        assert!(matches!(nan.kind, AbabaValueKind::Number(x) if x.is_nan()));
+
+        // without `allow_non_finite` these are idents, `pi` is always the number
+        let value = AbabaParser::new("[inf, nan, pi]").parse().unwrap();
+        assert_eq!(
+            to_json(&value),
+            r#"[{"$ident":"inf"},{"$ident":"nan"},3.141592653589793]"#
+        );
+        assert_eq!(from_json(&to_json(&value)).unwrap(), value);
+    }
+
+    #[test]
+    fn writing() {
+        let value = parse("Ababa { b: [1, (2, [..])], a: \"\\n\", c: {} }");
+        assert_eq!(
+            to_json(&value),
+            r#"{"$type":"Ababa","a":"\n","b":[1,{"$tuple":[2,[{"$spread":true}]]}],"c":{}}"#
+        );
+        assert_eq!(
+            to_json_pretty(&value),
+            r#"{
+  "$type": "Ababa",
+  "a": "\n",
+  "b": [
+    1,
+    {
+      "$tuple": [
+        2,
+        [
+          {
+            "$spread": true
+          }
+        ]
+      ]
+    }
+  ],
+  "c": {}
+}"#
+        );
+        assert_eq!(to_json(&parse("nan")), r#"{"$number":"nan"}"#);
+        assert_eq!(to_json(&parse("-inf")), r#"{"$number":"-inf"}"#);
+        assert_eq!(to_json(&parse("[1e300, 5e-324]")), "[1e300,5e-324]");
+    }
+
+    #[test]
+    fn reading() {
+        // plain JSON from other tools
+        let json =
+            r#" { "name": "a\u00e4\ud83e\udd80\/", "list": [1.5e3, -0, 2E-1], "ok": true } "#;
+        assert_eq!(
+            from_json(json).unwrap(),
+            parse(r#"{ name: "aä🦀/", list: [1500, -0, 0.2], ok: true }"#)
+        );
+
+        let value = from_json(r#"[1, {"$tuple": []}]"#).unwrap();
+        assert_eq!(value.span, Span::new(0, 19));
+        let AbabaValueKind::List(items) = value.kind else {
+            panic!("not a list");
+        };
+        assert_eq!(items[1].span, Span::new(4, 18));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(from_json(" "), Err(AbabaParseError::NoContent)));
+        assert_eq!(error("[1, 2"), "missing closing ']'");
+        assert_eq!(error(r#"{"a": 1"#), "missing closing '}'");
+        assert_eq!(error("[1 2]"), "expected ',' or ']', got '2'");
+        assert_eq!(error(r#"{"a" 1}"#), "expected ':', got '1'");
+        assert_eq!(error("{a: 1}"), "expected key, got 'a'");
+        assert_eq!(error("[1,]"), "expected value, got ']'");
+        assert_eq!(error("1 2"), "expected end of input, got '2'");
+        assert_eq!(error("nul"), "expected value, got 'n'");
+        assert_eq!(error(".5"), "expected value, got '.'");
+        assert_eq!(
+            error("[null]"),
+            "expected a value (ababa has no null), got `null`"
+        );
+        for number in ["01", "1.", "1e", "--1", "-", "1.2.3", "1e5e5", "1+2"] {
+            assert!(
+                matches!(
+                    from_json(number),
+                    Err(AbabaParseError::InvalidNumber { .. })
+                ),
+                "{number}"
+            );
+        }
+        assert_eq!(error(r#""abc"#), "unterminated string");
+        assert_eq!(error(r#""\x""#), "invalid escape sequence `\\x`");
+        assert_eq!(error(r#""\ud800""#), "invalid escape sequence `\\ud800`");
+        assert_eq!(
+            error(r#"{"a": 1, "a": 2}"#),
+            "field `a` is defined more than once"
+        );
+
+        assert_eq!(
+            error(r#"{"$typ": "A"}"#),
+            "unknown field `$typ`, did you mean `$type`?"
+        );
+        assert_eq!(
+            error(r#"{"$type": 1}"#),
+            "expected String, got Number (at `$type`)"
+        );
+        assert_eq!(error(r#"{"$tuple": 1}"#), "expected List, got Number");
+        assert_eq!(error(r#"{"$tuple": [], "a": 1}"#), "unknown field `a`");
+        assert_eq!(
+            error(r#"{"$ident": "A", "$type": "B"}"#),
+            "unknown field `$type`"
+        );
+        assert_eq!(
+            error(r#"{"$number": "infinity"}"#),
+            "invalid number `infinity` (at `$number`)"
+        );
+        assert_eq!(
+            error(r#"{"$spread": false}"#),
+            "expected `true`, got Bool (at `$spread`)"
+        );
+    }
+
+    #[test]
+    fn unprintable() {
+        // everything that is read can be printed as ababa and read back
+        let err = from_json(r#"{"a b": 1}"#).unwrap_err();
+        assert_eq!(err.to_string(), "\"a b\" is not a valid name");
+        assert_eq!(err.span(), Some(Span::new(1, 6)));
+        for key in ["", "for", "true", "1x", "a.b"] {
+            let json = format!("{{\"{key}\": 1}}");
+            assert_eq!(error(&json), format!("{key:?} is not a valid name"));
+        }
+
+        let err = from_json(r#"{"$type": "for", "x": 1}"#).unwrap_err();
+        assert_eq!(err.to_string(), "\"for\" is not a valid name (at `$type`)");
+        assert_eq!(err.span(), Some(Span::new(10, 15)));
+        assert_eq!(
+            error(r#"{"$type": "sqrt", "$tuple": [1]}"#),
+            "\"sqrt\" is not a valid name (at `$type`)"
+        );
+        assert_eq!(
+            error(r#"{"$ident": "x y"}"#),
+            "\"x y\" is not a valid name (at `$ident`)"
+        );
+        // the parser never makes an ident of `pi`, it would be read back as the number
+        assert_eq!(
+            error(r#"{"$ident": "pi"}"#),
+            "\"pi\" is not a valid name (at `$ident`)"
+        );
+        // the same names are fine where they don't mean anything else
+        assert!(from_json(r#"{"$type": "sqrt", "pi": {"$ident": "sqrt"}}"#).is_ok());
+
+        for json in [
+            r#"{"$spread": true}"#,
+            r#"{"a": {"$spread": true}}"#,
+            r#"{"$tuple": [{"$spread": true}]}"#,
+        ] {
+            assert!(
+                matches!(
+                    from_json(json),
+                    Err(AbabaParseError::UnexpectedToken {
+                        expected: "value",
+                        ..
+                    })
+                ),
+                "{json}"
+            );
+        }
+        let err = from_json(r#"[{"a": {"$spread": true}}]"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected value, got '..' outside of a list"
+        );
+        assert_eq!(err.span(), Some(Span::new(7, 24)));
+
+        let err = from_json("[\"a\nb\"]").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected escape sequence, got control character '\\n'"
+        );
+        assert_eq!(err.span(), Some(Span::new(3, 4)));
+        assert!(from_json("\"a\tb\"").is_err());
+
+        for (json, span) in [("1e400", Span::new(0, 5)), ("[-1e309]", Span::new(1, 7))] {
+            let err = from_json(json).unwrap_err();
+            assert!(
+                matches!(err, AbabaParseError::NotFinite { span: s } if s == span),
+                "{json}"
+            );
+        }
+
+        let nested = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let value = from_json(&nested(MAX_DEPTH)).unwrap();
+        assert!(AbabaParser::new(&value.to_string()).parse().is_ok());
+        let err = from_json(&nested(MAX_DEPTH + 1)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "values are nested more than 128 levels deep"
+        );
+        assert_eq!(err.span(), Some(Span::new(MAX_DEPTH, MAX_DEPTH + 1)));
+        assert!(from_json(&nested(200_000)).is_err());
+        assert!(JsonValue::parse(&nested(200_000)).is_err());
+    }
+}
@@ -3,43 +3,205 @@
 //! the input is parsed into tokens using [AbabaTokenizer], then parsed into an AST
 //! of [AbabaValue] with [AbabaParser].
 //!
+//! [AbabaParser::parse_borrowed] returns an [AbabaValueRef] instead, which borrows its strings from
+//! the input and keeps the fields in the order they were written in. it's what the parser builds
+//! anyway, so it saves copying every string and hashing every field name. `cargo bench` times both
+//! on a generated scene with 100 000 objects.
+//!
 //! ## grammar
 //!
 //! this grammar is LL(1) and is parsed using recursive descent.
 //!
-//! - all whitespace is ignored
+//! a document is a single value, optionally preceded by `let` bindings. `$name` refers to the value
+//! of a binding, `&name value` binds a value right where it is used (an anchor) so it can be
+//! referred to afterwards. names have to be defined before they are used and can't be redefined.
+//! references are replaced with a copy of the value while parsing, so [AbabaValue] never contains
+//! any of them. the references of a file can copy at most 1000000 values in total.
+//!
+//! `include "lights.ababa"` is replaced by the value of another file, `import "materials.ababa";`
+//! makes the bindings of another file available (the value of that file is optional). paths are
+//! relative to the including file (see [AbabaParser::with_file]) and are read by an
+//! [AbabaResolver], [AbabaFileResolver] by default. the bindings of an included file stay private
+//! to it, files that include themselves are an error.
+//!
+//! `..` in a list stands for the items of the list it's merged onto, see [AbabaValue::merge] and
+//! [AbabaLoader] for stacking files on top of each other.
+//!
+//! lists can contain loops, which are expanded into items while parsing.
+//! `for i in 0..n { .. }` adds the items in the braces once for every integer from `0` up to (but
+//! not including) `n`, with `$i` bound to it. like in rust, the range is empty unless `n` is larger
+//! than the start. `repeat n { .. }` does the same without a name, but `n` can't be negative.
+//! loops can be nested, the bindings made inside of one only last for a single iteration. all
+//! loops of a file together can run at most 100 000 times, so a typo can't make the parser run
+//! forever.
+//!
+//! ```
+//! use ababa_config::AbabaParser;
+//!
+//! let grid = AbabaParser::new("[for x in 0..2 { for y in 1..3 { ($x, $y * 2) } }]");
+//! let pairs = AbabaParser::new("[(0, 2), (0, 4), (1, 2), (1, 4)]");
+//! assert_eq!(grid.parse().unwrap(), pairs.parse().unwrap());
+//! ```
+//!
+//! `env("NAME", default)` is the value of an environment variable and `param("name", default)`
+//! the one of a parameter passed in with [AbabaParser::with_params] (or [AbabaLoader::with_params]),
+//! so the same file can be rendered at another size without editing it. the default is used if
+//! the name isn't set and can be left out, which makes a missing name an error. environment
+//! variables are read by the [AbabaResolver] and parsed as a value, unless the default is a
+//! string. the value is parsed like it was written in place of the lookup, with the same resolver,
+//! params and nesting limit.
+//!
+//! ```
+//! use ababa_config::{AbabaParser, AbabaValueKind};
+//! use std::collections::HashMap;
+//!
+//! let params = HashMap::from([("width".to_string(), AbabaValueKind::Number(128.).into())]);
+//! let source = "(param(\"width\", 64), param(\"height\", 48))";
+//! let size = AbabaParser::new(source).with_params(&params).parse().unwrap();
+//! assert_eq!(size, AbabaParser::new("(128, 48)").parse().unwrap());
+//! ```
+//!
+//! numbers can be written as arithmetic expressions like `2 * pi / 3` or `-sqrt($r)`, which are
+//! evaluated while parsing as well. `pi` and `tau` are constants, the functions are `sin`, `cos`,
+//! `tan`, `sqrt`, `abs`, `rad` (degrees to radians), `deg`, `min`, `max` and `pow`.
+//!
+//! decimal numbers can have a unit: angles in `deg` or `rad`, lengths in `mm`, `cm` or `m`. they're
+//! converted to radians and meters while parsing, so `90deg` and `10cm` are the same as `1.5707..`
+//! and `0.1`. the value remembers the [AbabaUnit], fields with `#[ababa(unit = "angle")]` reject
+//! lengths and the other way around. in expressions, numbers without a unit mix with any unit,
+//! while adding an angle to a length is an error. functions take and return plain numbers.
+//!
+//! - all whitespace is ignored, as well as line comments (`// ...`) and block comments (`/* ... */`)
 //! - ε represents the empty string
 //! - text in single quotes represent literals, no quotes represent variables
 //! - expressions using backticks are to be interpreted as regex (although it's not used in this
 //!   project to stay true to the "no dependency" rule)
-//! - objects can optionally specify a type (ident), but it can be left out
+//! - objects and tuples can optionally specify a type (ident), but it can be left out
+//! - a lone ident is a value as well, it's used for enum variants without any data
+//! - an ident followed by a tuple is a typed tuple unless it's a function, even if it starts with
+//!   a lowercase letter like `foo(1)`. only as an operand, where a tuple is an error anyway, such
+//!   a tuple is reported as an unknown function
 //! - idents do not start with a minus or a digit in order to differentiate from a number
-//! - lists, tuples and objects have optional trailing commas
-//! 
+//! - numbers don't have a sign, `-1` is a negation. they can contain `_` between the digits, be
+//!   written in hex (`0xff`) or binary (`0b101`) and have to be separated from a following ident,
+//!   except for the unit of a decimal number (`45deg`)
+//! - `inf` and `nan` are only numbers with [AbabaParser::allow_non_finite], idents otherwise.
+//!   without it, numbers that are too large for a f64 (`1e400`) are an error as well
+//! - `true`, `false`, `let`, `include`, `import`, `for`, `in` and `repeat` are reserved and can't
+//!   be used as idents, neither can `pi` and `tau` as lone values or the function names, `env`
+//!   and `param` followed by `(`
+//! - idents may contain a minus, so `a-1` is an ident while `$a - 1` is a subtraction
+//! - parentheses around a single value without a trailing comma group an expression, `(1,)` is a
+//!   tuple with one item
+//! - the operands of an expression have to be numbers, dividing by zero is an error, and so is a
+//!   result that isn't finite (like `sqrt(-1)`) unless [AbabaParser::allow_non_finite] is used
+//! - there's no whitespace between `$` or `&` and the name
+//! - a color `#rrggbb` is the tuple `(r, g, b)` of its bytes (0 to 255), `#rrggbbaa` has the alpha
+//!   as a fourth item
+//! - strings support the escapes `\\`, `\"`, `\n`, `\r`, `\t`, `\0` and `\u{..}`
+//! - lists, tuples and objects have optional trailing commas and can be empty
+//! - values can be nested at most 128 levels deep, counting negations
+//!
 //! ```txt
-//! value       ::= list | tuple | object | number
+//! document    ::= header* value
+//! header      ::= binding | import
+//! binding     ::= 'let' ident '=' value ';'
+//! import      ::= 'import' string ';'
+//!
+//! value       ::= sum
+//! sum         ::= product (('+' | '-') product)*
+//! product     ::= unary (('*' | '/') unary)*
+//! unary       ::= '-' unary | atom
+//! atom        ::= list | tuple | object | typed | call | constant | number | string | bool
+//!               | color | reference | anchored | include | lookup
+//! call        ::= function tuple
+//! function    ::= 'sin' | 'cos' | 'tan' | 'sqrt' | 'abs' | 'rad' | 'deg' | 'min' | 'max' | 'pow'
+//! constant    ::= 'pi' | 'tau'
+//! reference   ::= '$' ident
+//! anchored    ::= '&' ident value
+//! include     ::= 'include' string
+//! lookup      ::= ('env' | 'param') '(' string (',' value)? ')'
 //!
-//! list        ::= '[' items ']'
+//! typed       ::= ident typed-cont
+//! typed-cont  ::= tuple | object | ε
+//!
+//! list        ::= '[' list-items ']'
 //! tuple       ::= '(' items ')'
 //!
-//! items       ::= value item-cont
-//! items-cont  ::= ',' item | ',' | ε
+//! items       ::= value items-cont | ε
+//! items-cont  ::= ',' items | ε
+//! list-items  ::= list-item list-cont | ε
+//! list-cont   ::= ',' list-items | ε
+//! list-item   ::= value | '..' | loop
+//!
+//! loop        ::= 'for' ident 'in' value '..' value body | 'repeat' value body
+//! body        ::= '{' body-items '}'
+//! body-items  ::= body-item body-cont | ε
+//! body-cont   ::= ',' body-items | ε
+//! body-item   ::= value | loop
 //!
-//! object      ::= ident '{' fields '}'
-//! fields      ::= ident ':' value field-cont
-//! fields-cont ::= ',' field | ',' | ε
-//! 
-//! ident       ::= `[a-zA-Z_][0-9a-zA-Z-_]+`
-//! number      ::= whatever rust uses honestly
+//! object      ::= '{' fields '}'
+//! fields      ::= ident ':' value fields-cont | ε
+//! fields-cont ::= ',' fields | ε
+//!
+//! ident       ::= `[a-zA-Z_][0-9a-zA-Z-_]*`
+//! number      ::= decimal unit? | '0x' `[0-9a-fA-F_]+` | '0b' `[01_]+`
+//! decimal     ::= `[0-9][0-9_]*(\.[0-9_]*)?([eE][+-]?[0-9_]+)?`
+//! unit        ::= 'deg' | 'rad' | 'mm' | 'cm' | 'm'
+//! string      ::= '"' `([^"\\]|\\.)*` '"'
+//! bool        ::= 'true' | 'false'
+//! color       ::= '#' `[0-9a-fA-F]{6}([0-9a-fA-F]{2})?`
+//! ```
+//!
+//! ## errors
+//!
+//! every token and every [AbabaValue] carries the [Span] (byte range) it was parsed from, so errors
+//! can point at the offending part of the input. errors that happen while converting a value
+//! (e.g. in a derived impl) also remember the path to the value, like `scene_objects[2].material`.
+//! errors in included files carry the source of that file and the chain of includes that lead
+//! there, values from included files have the span of the include.
+//!
+//! the [Display] impl of [AbabaParseError] only prints the message, path and included files, use
+//! [AbabaParseError::with_source] to render a snippet of the input with line and column.
+//!
+//! ```
+//! use ababa_config::{AbabaParseError, AbabaParser};
+//!
+//! let s = "Ababa { a: [1, 2 }";
+//! let err = AbabaParser::new(s).parse().unwrap_err();
+//! assert_eq!(
+//!     err.with_source(s).with_file_name("ababa.ababa").to_string(),
+//!     "\
+//! error: expected ',' or closing brace, got '}'
+//!  --> ababa.ababa:1:18
+//!   |
+//! 1 | Ababa { a: [1, 2 }
+//!   |                  ^
+//! "
+//! );
 //! ```
 //!
+//! [AbabaParser::parse] stops at the first error. [AbabaParser::parse_recovering] skips ahead to
+//! the next `,`, closing brace or `;` instead and returns all errors, together with the part of
+//! the value that could be parsed.
+//!
 //! ## implementing your own deserialization
 //!
 //! the [TryFrom]<AbabaValue> trait is implemented for some types, such as all number types and vectors.
-//! please do use it when creating your own impls for your own types.
+//! please do use it when creating your own impls for your own types. when converting nested values,
+//! wrap their errors with [AbabaParseError::in_field] or [AbabaParseError::at_index] so the error
+//! path stays intact.
+//!
+//! arrays `[T; N]` are read from lists and tuples up to 12 items from tuples, both need exactly
+//! that many items. `Option<T>` is the ident `None` or a `T`, maps with [String] keys are read from
+//! objects. `Box<T>` can't have an impl, the derive reads such fields as `T` and boxes them.
 //!
 //! you can use the `#[derive(AbabaDeserialize)]` macro (implemented in [ababa_config_proc]) to
-//! derive [TryFrom]<String> for your own structs. no guarantees though.
+//! derive [TryFrom]<String> for your own structs and enums. no guarantees though.
+//! enums pick their variant by the type of the value, e.g. `Sphere { .. }`, `Point(1, 2)` or `Empty`.
+//! for trait objects, register a constructor for every type in an [AbabaRegistry].
+//! fields can be made optional, renamed, flattened or constrained (e.g. `#[ababa(range = 0.0..)]`)
+//! with `#[ababa(...)]` attributes, see [ababa_config_proc::AbabaDeserialize] for all of them.
 //!
 //! ```
 //! use ababa_config::{AbabaParseError, AbabaValue};
@@ -61,6 +223,7 @@
 //! ```
 //!
 //! ```
+//! # fn main() -> Result<(), ababa_config::AbabaParseError> {
 //! use ababa_config::{AbabaParser, AbabaValue};
 //! use ababa_config_proc::AbabaDeserialize;
 //!
@@ -74,22 +237,186 @@
 //! // you can use the TryFrom<String> trait of AbabaValue, or use AbabaParser directly
 //! let s = "Ababa { a: 5.0, ba: 64 }".to_string();
 //! let a: Ababa = AbabaParser::new(&s).parse()?.try_into()?;
+//! assert_eq!(a.ba, 64);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## serialization
+//!
+//! the other way around, [AbabaSerialize] converts rust values into an [AbabaValue], which can be
+//! printed with [AbabaPrinter] or its [Display] impl. `#[derive(AbabaSerialize)]` generates it and
+//! respects the same `#[ababa(...)]` attributes, so the printed text deserializes into the same
+//! value again.
+//!
+//! ```
+//! # fn main() -> Result<(), ababa_config::AbabaParseError> {
+//! use ababa_config::{AbabaParser, AbabaSerialize};
+//! use ababa_config_proc::{AbabaDeserialize, AbabaSerialize};
+//!
+//! #[derive(AbabaDeserialize, AbabaSerialize)]
+//! pub struct Ababa {
+//!     a: f64,
+//!     ba: (i64, i64),
+//! }
+//!
+//! let s = Ababa { a: 0.5, ba: (6, 4) }.to_ababa().to_string();
+//! assert_eq!(s, "Ababa { a: 0.5, ba: (6, 4) }");
+//!
+//! let a: Ababa = AbabaParser::new(&s).parse()?.try_into()?;
+//! assert_eq!(a.ba, (6, 4));
+//! # Ok(())
+//! # }
 //! ```
+//!
+//! ## schemas
+//!
+//! `#[derive(AbabaDeserialize)]` also derives [AbabaDescribe], an [AbabaSchema] with the fields,
+//! types, defaults and variants the type accepts. [AbabaSchema::validate] checks a value against
+//! it and reports all errors at once, [AbabaLoader::validate] does the same for merged layers.
+//!
+//! ## formatting
+//!
+//! printing an [AbabaValue] loses comments and the order of fields, so to reformat source files
+//! [format_source] works on the source directly. the `ababa-fmt` binary uses it to format files in
+//! place, or with `--check` to only report unformatted ones. only the syntax is checked, params,
+//! includes and loops aren't evaluated.
+//!
+//! ## JSON
+//!
+//! [to_json] and [from_json] convert values to and from JSON without losing anything, types,
+//! tuples and idents are written as objects with keys like `"$type"`. the `ababa2json` and
+//! `json2ababa` binaries convert files (or stdin) for tools that only speak JSON.
+//!
+//! ## language server
+//!
+//! [AbabaLanguageServer] speaks the language server protocol for files of one [AbabaSchema], with
+//! diagnostics, hover, completion, go to definition and formatting. the raytracer's `ababa-lsp`
+//! binary runs it for scene files over stdio.
+pub use crate::borrowed::{AbabaValueRef, AbabaValueRefKind};
+pub use crate::constraint::AbabaConstraint;
+pub use crate::diagnostic::AbabaDiagnostic;
+pub use crate::fields::{deny_unknown_fields, take_field, AbabaFromFields};
+pub use crate::formatter::{format_source, is_equivalent};
+pub use crate::include::{AbabaFileResolver, AbabaResolver};
+pub use crate::json::{from_json, to_json, to_json_pretty};
+pub use crate::loader::AbabaLoader;
+pub use crate::lsp::AbabaLanguageServer;
 pub use crate::parser::AbabaParser;
+pub use crate::printer::AbabaPrinter;
+pub use crate::registry::AbabaRegistry;
+pub use crate::schema::{
+    AbabaDescribe, AbabaFieldSchema, AbabaSchema, AbabaStructSchema, AbabaVariantSchema,
+};
+pub use crate::serialize::AbabaSerialize;
+pub use crate::unit::AbabaUnit;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
 
 pub mod impls;
 
+// lets the derive macros refer to `ababa_config::...` inside of this crate as well
+extern crate self as ababa_config;
+
+mod borrowed;
+mod constraint;
+mod diagnostic;
+mod expr;
+mod fields;
+mod formatter;
+mod include;
+mod json;
+mod loader;
+mod lsp;
+mod merge;
 mod parser;
+mod printer;
+mod registry;
+mod schema;
+mod serialize;
 mod tokenizer;
+mod unit;
+
+/// byte range `start..end` into the parsed input
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// smallest span that covers both spans
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+/// one step of the path to a nested value, see [AbabaParseError::AtPath]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AbabaPathSegment {
+    Field(String),
+    Index(usize),
+}
+
+// the span of a root error (no wrappers), works for references and mutable references
+macro_rules! root_span {
+    ($root:expr) => {{
+        use AbabaParseError::*;
+        match $root {
+            ValueTypeDidNotMatch {
+                got: AbabaValue { span, .. },
+                ..
+            }
+            | NumberOutOfBounds { span, .. }
+            | StructTypeDidNotMatch { span, .. }
+            | StructFieldNotPresent { span, .. }
+            | MissingEndBrace { span, .. }
+            | UnexpectedToken { span, .. }
+            | InvalidNumber { span, .. }
+            | InvalidColor { span, .. }
+            | DuplicateField { span, .. }
+            | InvalidEscape { span, .. }
+            | Unterminated { span, .. }
+            | NotEnoughElements { span, .. }
+            | TooManyElements { span, .. }
+            | UnknownVariant { span, .. }
+            | UnknownField { span, .. }
+            | UndefinedName { span, .. }
+            | CyclicReference { span, .. }
+            | DuplicateName { span, .. }
+            | DivisionByZero { span }
+            | NotFinite { span }
+            | UnknownFunction { span, .. }
+            | WrongArgumentCount { span, .. }
+            | TooManyIterations { span, .. }
+            | TooManyCopies { span, .. }
+            | TooDeep { span, .. }
+            | UnitMismatch { span, .. }
+            | UnsetParameter { span, .. }
+            | InvalidEnvironment { span, .. }
+            | InvalidName { span, .. }
+            | IncludeFailed { span, .. }
+            | IncludeCycle { span, .. }
+            | InvalidOverride { span }
+            | ConstraintViolated { span, .. } => Some(span),
+            NoContent => None,
+            AtPath { .. } | Included { .. } => unreachable!("root() never returns wrappers"),
+        }
+    }};
+}
 
 #[derive(Debug)]
 pub enum AbabaParseError {
     NumberOutOfBounds {
         x: f64,
         target_type: &'static str,
+        span: Span,
     },
     ValueTypeDidNotMatch {
         expected: &'static str,
@@ -98,37 +425,489 @@ pub enum AbabaParseError {
     StructTypeDidNotMatch {
         expected: &'static str,
         got: Option<String>,
+        span: Span,
     },
     StructFieldNotPresent {
         field: &'static str,
+        span: Span,
     },
     NoContent,
     MissingEndBrace {
         brace: char,
+        span: Span,
+    },
+    UnexpectedToken {
+        expected: &'static str,
+        got: String,
+        span: Span,
+    },
+    InvalidNumber {
+        s: String,
+        span: Span,
+    },
+    /// a `#` followed by something other than 6 or 8 hex digits, `s` is without the `#`
+    InvalidColor {
+        s: String,
+        span: Span,
+    },
+    DuplicateField {
+        field: String,
+        span: Span,
+    },
+    InvalidEscape {
+        escape: String,
+        span: Span,
+    },
+    Unterminated {
+        what: &'static str,
+        span: Span,
     },
     NotEnoughElements {
         expected: i32,
         got: usize,
+        span: Span,
+    },
+    /// a tuple or array with more elements than the rust type has
+    TooManyElements {
+        expected: usize,
+        got: usize,
+        span: Span,
+    },
+    UnknownVariant {
+        expected: Vec<&'static str>,
+        got: Option<String>,
+        span: Span,
+    },
+    UnknownField {
+        field: String,
+        suggestion: Option<&'static str>,
+        span: Span,
+    },
+    UndefinedName {
+        name: String,
+        span: Span,
+    },
+    /// a reference to a value that is still being defined, e.g. `&a [$a]`
+    CyclicReference {
+        name: String,
+        span: Span,
+    },
+    DuplicateName {
+        name: String,
+        span: Span,
+    },
+    /// `span` is the divisor
+    DivisionByZero {
+        span: Span,
+    },
+    /// a number that is infinite or not a number without [AbabaParser::allow_non_finite]
+    NotFinite {
+        span: Span,
+    },
+    UnknownFunction {
+        name: String,
+        span: Span,
+    },
+    WrongArgumentCount {
+        function: &'static str,
+        expected: usize,
+        got: usize,
+        span: Span,
+    },
+    /// the loops of a file would run more than `limit` times in total, `span` is the loop that
+    /// went over
+    TooManyIterations {
+        limit: usize,
+        span: Span,
+    },
+    /// the references of a file would copy more than `limit` values in total, `span` is the
+    /// reference that went over
+    TooManyCopies {
+        limit: usize,
+        span: Span,
+    },
+    /// values nested more than `limit` levels deep, `span` is the first value that is too deep
+    TooDeep {
+        limit: usize,
+        span: Span,
+    },
+    /// a number with the unit `got` where `expected` was needed, `None` is a number without a unit
+    UnitMismatch {
+        expected: Option<AbabaUnit>,
+        got: AbabaUnit,
+        span: Span,
+    },
+    /// `env("name")` or `param("name")` without a default for a name that isn't set, `function`
+    /// is `env` or `param`
+    UnsetParameter {
+        function: &'static str,
+        name: String,
+        span: Span,
+    },
+    /// the environment variable `name` isn't a valid value, `message` tells why
+    InvalidEnvironment {
+        name: String,
+        message: String,
+        span: Span,
+    },
+    /// `name` is used as a field name, ident or struct type but wouldn't be read back as one,
+    /// e.g. a keyword or a string with spaces
+    InvalidName {
+        name: String,
+        span: Span,
+    },
+    /// the included file `file` could not be read
+    IncludeFailed {
+        file: PathBuf,
+        error: std::io::Error,
+        span: Span,
+    },
+    /// a file includes itself, `chain` starts and ends with that file
+    IncludeCycle {
+        chain: Vec<PathBuf>,
+        span: Span,
+    },
+    /// an override of an [AbabaLoader] that isn't of the form `key.path=value`
+    InvalidOverride {
+        span: Span,
+    },
+    /// a value that doesn't satisfy a constraint of its field, see [AbabaConstraint]
+    ConstraintViolated {
+        message: String,
+        span: Span,
+    },
+    /// the wrapped error happened in the included file `file` with the content `source`.
+    /// `span` is the include in the including file, `None` for the files of an [AbabaLoader].
+    Included {
+        file: PathBuf,
+        source: String,
+        span: Option<Span>,
+        inner: Box<AbabaParseError>,
+    },
+    /// the wrapped error happened inside of the field or element described by `segment`
+    AtPath {
+        segment: AbabaPathSegment,
+        inner: Box<AbabaParseError>,
     },
 }
 
+impl AbabaParseError {
+    /// marks the error as having happened inside of the field `field`
+    pub fn in_field(self, field: impl Into<String>) -> Self {
+        AbabaParseError::AtPath {
+            segment: AbabaPathSegment::Field(field.into()),
+            inner: Box::new(self),
+        }
+    }
+
+    /// marks the error as having happened inside of the list or tuple element `index`
+    pub fn at_index(self, index: usize) -> Self {
+        AbabaParseError::AtPath {
+            segment: AbabaPathSegment::Index(index),
+            inner: Box::new(self),
+        }
+    }
+
+    /// the error without any [AbabaParseError::AtPath] and [AbabaParseError::Included] wrappers
+    pub fn root(&self) -> &AbabaParseError {
+        match self {
+            AbabaParseError::AtPath { inner, .. } | AbabaParseError::Included { inner, .. } => {
+                inner.root()
+            }
+            _ => self,
+        }
+    }
+
+    /// the included files the error happened in, starting with the one included by the parsed
+    /// input. empty if the error happened in the input itself.
+    pub fn include_chain(&self) -> Vec<&Path> {
+        let mut chain = Vec::new();
+        let mut err = self;
+
+        loop {
+            match err {
+                AbabaParseError::AtPath { inner, .. } => err = inner,
+                AbabaParseError::Included { file, inner, .. } => {
+                    chain.push(file.as_path());
+                    err = inner;
+                }
+                _ => return chain,
+            }
+        }
+    }
+
+    /// path to the value the error happened in, e.g. `scene_objects[2].material`.
+    /// empty if the error happened at the top level.
+    pub fn path(&self) -> String {
+        let mut path = String::new();
+        let mut err = self;
+
+        loop {
+            match err {
+                AbabaParseError::AtPath { segment, inner } => {
+                    match segment {
+                        AbabaPathSegment::Field(field) if path.is_empty() => path.push_str(field),
+                        AbabaPathSegment::Field(field) => {
+                            path.push('.');
+                            path.push_str(field);
+                        }
+                        AbabaPathSegment::Index(index) => path.push_str(&format!("[{index}]")),
+                    }
+                    err = inner;
+                }
+                AbabaParseError::Included { inner, .. } => err = inner,
+                _ => return path,
+            }
+        }
+    }
+
+    /// location of the error in the input, if known. for errors in included files, this is the
+    /// location of the include.
+    pub fn span(&self) -> Option<Span> {
+        use AbabaParseError::*;
+        match self {
+            AtPath { inner, .. } => inner.span(),
+            Included { span, .. } => *span,
+            _ => self.root_span(),
+        }
+    }
+
+    // span of the root error, in the file it happened in
+    pub(crate) fn root_span(&self) -> Option<Span> {
+        root_span!(self.root()).copied()
+    }
+
+    pub(crate) fn root_span_mut(&mut self) -> Option<&mut Span> {
+        root_span!(self.root_mut())
+    }
+
+    fn root_mut(&mut self) -> &mut AbabaParseError {
+        match self {
+            AbabaParseError::AtPath { inner, .. } | AbabaParseError::Included { inner, .. } => {
+                inner.root_mut()
+            }
+            _ => self,
+        }
+    }
+
+    /// attaches the parsed input to the error, for rendering the error with a snippet of it
+    pub fn with_source<'a>(&'a self, source: &'a str) -> AbabaDiagnostic<'a> {
+        AbabaDiagnostic::new(self, source)
+    }
+
+    /// for rendering errors that carry their source, like the ones of an [AbabaLoader]
+    pub fn diagnostic(&self) -> AbabaDiagnostic<'_> {
+        AbabaDiagnostic::new(self, "")
+    }
+
+    fn fmt_message(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use AbabaParseError::*;
+        match self.root() {
+            NumberOutOfBounds { x, target_type, .. } => {
+                let x = printer::Number(*x);
+                write!(f, "number {x} does not fit into {target_type}")
+            }
+            ValueTypeDidNotMatch { expected, got } => {
+                write!(f, "expected {expected}, got {}", got.type_name())
+            }
+            StructTypeDidNotMatch {
+                expected,
+                got: Some(got),
+                ..
+            } => write!(f, "expected object of type {expected}, got {got}"),
+            StructTypeDidNotMatch { expected, .. } => {
+                write!(f, "expected object of type {expected}")
+            }
+            StructFieldNotPresent { field, .. } => write!(f, "missing field `{field}`"),
+            NoContent => write!(f, "input does not contain a value"),
+            MissingEndBrace { brace, .. } => write!(f, "missing closing '{brace}'"),
+            UnexpectedToken { expected, got, .. } => write!(f, "expected {expected}, got {got}"),
+            InvalidNumber { s, .. } => write!(f, "invalid number `{s}`"),
+            InvalidColor { s, .. } => {
+                write!(f, "invalid color `#{s}`, expected `#rrggbb` or `#rrggbbaa`")
+            }
+            DuplicateField { field, .. } => write!(f, "field `{field}` is defined more than once"),
+            InvalidEscape { escape, .. } => write!(f, "invalid escape sequence `{escape}`"),
+            Unterminated { what, .. } => write!(f, "unterminated {what}"),
+            NotEnoughElements { expected, got, .. } => {
+                write!(f, "expected {expected} elements, got {got}")
+            }
+            TooManyElements { expected, got, .. } => {
+                write!(f, "expected {expected} elements, got {got}")
+            }
+            UnknownVariant { expected, got, .. } => {
+                let expected = expected
+                    .iter()
+                    .map(|v| format!("`{v}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                match got {
+                    Some(got) => write!(f, "unknown type `{got}`, expected one of {expected}"),
+                    None => write!(f, "missing type, expected one of {expected}"),
+                }
+            }
+            UnknownField {
+                field,
+                suggestion: Some(suggestion),
+                ..
+            } => write!(f, "unknown field `{field}`, did you mean `{suggestion}`?"),
+            UnknownField { field, .. } => write!(f, "unknown field `{field}`"),
+            UndefinedName { name, .. } => write!(f, "`${name}` is not defined"),
+            CyclicReference { name, .. } => {
+                write!(f, "`${name}` is used inside of its own definition")
+            }
+            DuplicateName { name, .. } => write!(f, "name `{name}` is defined more than once"),
+            DivisionByZero { .. } => write!(f, "division by zero"),
+            NotFinite { .. } => write!(f, "the number is not finite"),
+            UnknownFunction { name, .. } => write!(f, "unknown function `{name}`"),
+            WrongArgumentCount {
+                function,
+                expected,
+                got,
+                ..
+            } => write!(f, "`{function}` takes {expected} arguments, got {got}"),
+            TooManyIterations { limit, .. } => {
+                write!(f, "loops run more than {limit} times")
+            }
+            TooManyCopies { limit, .. } => {
+                write!(f, "references copy more than {limit} values")
+            }
+            TooDeep { limit, .. } => write!(f, "values are nested more than {limit} levels deep"),
+            UnitMismatch { expected, got, .. } => write!(
+                f,
+                "expected {}, got {}",
+                AbabaUnit::describe(*expected),
+                AbabaUnit::describe(Some(*got))
+            ),
+            UnsetParameter { function, name, .. } => {
+                write!(f, "`{function}(\"{name}\")` is not set and has no default")
+            }
+            InvalidEnvironment { name, message, .. } => {
+                write!(
+                    f,
+                    "environment variable `{name}` is not a valid value: {message}"
+                )
+            }
+            InvalidName { name, .. } => write!(f, "{name:?} is not a valid name"),
+            IncludeFailed { file, error, .. } => {
+                write!(f, "could not read `{}`: {error}", file.display())
+            }
+            IncludeCycle { chain, .. } => {
+                let chain = chain
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                write!(f, "file includes itself: {chain}")
+            }
+            InvalidOverride { .. } => write!(f, "invalid override, expected `key.path=value`"),
+            ConstraintViolated { message, .. } => write!(f, "{message}"),
+            AtPath { .. } | Included { .. } => unreachable!("root() never returns wrappers"),
+        }
+    }
+}
+
 impl Display for AbabaParseError {
-    fn fmt(&self, _f: &mut Formatter<'_>) -> std::fmt::Result {
-        todo!()
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.fmt_message(f)?;
+
+        let path = self.path();
+        if !path.is_empty() {
+            write!(f, " (at `{path}`)")?;
+        }
+
+        for (i, file) in self.include_chain().iter().rev().enumerate() {
+            match i {
+                0 => write!(f, " (in `{}`", file.display())?,
+                _ => write!(f, ", included from `{}`", file.display())?,
+            }
+        }
+        if !self.include_chain().is_empty() {
+            write!(f, ")")?;
+        }
+
+        Ok(())
     }
 }
 
 impl Error for AbabaParseError {}
 
+/// a node of the ababa AST, see [AbabaValueKind] for the possible values.
+///
+/// the span and unit are only metadata and are ignored when comparing values.
 #[derive(Debug, Clone)]
-pub enum AbabaValue {
+pub struct AbabaValue {
+    pub kind: AbabaValueKind,
+    pub span: Span,
+    /// the unit a number was written with (like `10cm`), the number is already converted to
+    /// meters or radians
+    pub unit: Option<AbabaUnit>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbabaValueKind {
     Number(f64),
     Object {
         struct_type: Option<String>,
         fields: HashMap<String, AbabaValue>,
     },
     List(Vec<AbabaValue>),
-    Tuple(Vec<AbabaValue>),
+    Tuple {
+        struct_type: Option<String>,
+        items: Vec<AbabaValue>,
+    },
+    String(String),
+    Bool(bool),
+    Ident(String),
+    /// `..` in a list, stands for the items of the list it's merged onto (see [AbabaValue::merge])
+    Spread,
+}
+
+impl AbabaValue {
+    pub fn new(kind: AbabaValueKind, span: Span) -> Self {
+        AbabaValue {
+            kind,
+            span,
+            unit: None,
+        }
+    }
+
+    /// type of a typed object or tuple, or the ident itself for lone idents.
+    /// used to pick the variant when deserializing enums.
+    pub fn struct_type(&self) -> Option<&str> {
+        match &self.kind {
+            AbabaValueKind::Object { struct_type, .. }
+            | AbabaValueKind::Tuple { struct_type, .. } => struct_type.as_deref(),
+            AbabaValueKind::Ident(ident) => Some(ident),
+            _ => None,
+        }
+    }
+
+    /// name of the kind of value, used in error messages
+    pub fn type_name(&self) -> &'static str {
+        match self.kind {
+            AbabaValueKind::Number(_) => "Number",
+            AbabaValueKind::Object { .. } => "Object",
+            AbabaValueKind::List(_) => "List",
+            AbabaValueKind::Tuple { .. } => "Tuple",
+            AbabaValueKind::String(_) => "String",
+            AbabaValueKind::Bool(_) => "Bool",
+            AbabaValueKind::Ident(_) => "Ident",
+            AbabaValueKind::Spread => "Spread",
+        }
+    }
+}
+
+impl PartialEq for AbabaValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl From<AbabaValueKind> for AbabaValue {
+    fn from(kind: AbabaValueKind) -> Self {
+        AbabaValue::new(kind, Span::default())
+    }
 }
 
 impl TryFrom<String> for AbabaValue {
@@ -138,3 +917,59 @@ impl TryFrom<String> for AbabaValue {
         AbabaParser::new(&value).parse()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ababa_config_proc::AbabaDeserialize;
+
+    #[allow(unused)]
+    #[derive(Debug, AbabaDeserialize)]
+    struct Inner {
+        material: (u8, u8, u8),
+    }
+
+    #[allow(unused)]
+    #[derive(Debug, AbabaDeserialize)]
+    struct Outer {
+        scene_objects: Vec<Inner>,
+    }
+
+    #[test]
+    fn error_path() {
+        let err = AbabaParseError::NoContent
+            .in_field("material")
+            .at_index(2)
+            .in_field("scene_objects");
+
+        assert_eq!(err.path(), "scene_objects[2].material");
+        assert!(matches!(err.root(), AbabaParseError::NoContent));
+        assert_eq!(
+            err.to_string(),
+            "input does not contain a value (at `scene_objects[2].material`)"
+        );
+    }
+
+    #[test]
+    fn derived_error_path() {
+        let s = "{ scene_objects: [{ material: (1, 2, 3) }, { material: (1, 256, 3) }] }";
+        let value = AbabaParser::new(s).parse().unwrap();
+        let err = Outer::try_from(value).unwrap_err();
+
+        assert_eq!(err.path(), "scene_objects[1].material[1]");
+        assert_eq!(err.span(), Some(Span::new(59, 62)));
+        assert!(matches!(
+            err.root(),
+            AbabaParseError::NumberOutOfBounds {
+                target_type: "u8",
+                ..
+            }
+        ));
+
+        let s = "{ scene_objects: [{ materal: (1, 2, 3) }] }";
+        let value = AbabaParser::new(s).parse().unwrap();
+        let err = Outer::try_from(value).unwrap_err();
+        assert_eq!(err.path(), "scene_objects[0]");
+        assert_eq!(err.span(), Some(Span::new(18, 40)));
+    }
+}
@@ -0,0 +1,586 @@
+//! loading of a value from a stack of layers, each one merged on top of the previous ones.
+//!
+//! the layers come from different sources, but the merged value only has a single [Span] for each
+//! value. so every layer is parsed with its spans moved behind the ones of the previous layers,
+//! which tells the layer (and the position in it) of any span in the merged value.
+
+use crate::merge::{respan, shift_spans};
+use crate::tokenizer::is_ident;
+use crate::{
+    AbabaFileResolver, AbabaParseError, AbabaParser, AbabaPathSegment, AbabaResolver, AbabaSchema,
+    AbabaValue, AbabaValueKind, Span,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// file name of overrides in errors
+const OVERRIDE: &str = "<override>";
+
+/// loads a value from files, values and overrides, each one merged on top of the previous ones
+/// with [AbabaValue::merge].
+///
+/// errors carry the source of the layer they happened in, render them with
+/// [AbabaParseError::diagnostic].
+///
+/// ```
+/// use ababa_config::AbabaLoader;
+/// use ababa_config_proc::AbabaDeserialize;
+/// use std::collections::HashMap;
+/// use std::path::PathBuf;
+///
+/// #[derive(AbabaDeserialize)]
+/// struct Camera {
+///     width: u32,
+///     height: u32,
+/// }
+///
+/// let files = HashMap::from([
+///     (PathBuf::from("base.ababa"), "Camera { width: 64, height: 48 }".to_string()),
+///     (PathBuf::from("shot.ababa"), "{ height: 96 }".to_string()),
+/// ]);
+/// let camera: Camera = AbabaLoader::new()
+///     .with_resolver(&files)
+///     .file("base.ababa")
+///     .file("shot.ababa")
+///     .set("width=128")
+///     .load()
+///     .unwrap();
+/// assert_eq!((camera.width, camera.height), (128, 96));
+/// ```
+pub struct AbabaLoader<'a> {
+    resolver: &'a dyn AbabaResolver,
+    params: Option<&'a HashMap<String, AbabaValue>>,
+    layers: Vec<Layer>,
+}
+
+enum Layer {
+    File(PathBuf),
+    Value(AbabaValue),
+    /// `key.path=value`
+    Override(String),
+}
+
+/// the source of a layer, and where its spans start in the merged value
+struct Source {
+    file: PathBuf,
+    source: String,
+    offset: usize,
+}
+
+impl<'a> AbabaLoader<'a> {
+    pub fn new() -> Self {
+        AbabaLoader {
+            resolver: &AbabaFileResolver,
+            params: None,
+            layers: Vec::new(),
+        }
+    }
+
+    /// reads the files with `resolver` instead of from the file system
+    pub fn with_resolver(mut self, resolver: &'a dyn AbabaResolver) -> Self {
+        self.resolver = resolver;
+        self
+    }
+
+    /// the values of `param("name")` in the files and overrides, see [AbabaParser::with_params]
+    pub fn with_params(mut self, params: &'a HashMap<String, AbabaValue>) -> Self {
+        self.params = Some(params);
+        self
+    }
+
+    /// adds the file at `path` on top of the previous layers
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.layers.push(Layer::File(path.into()));
+        self
+    }
+
+    /// adds a value on top of the previous layers, e.g. the defaults
+    pub fn value(mut self, value: AbabaValue) -> Self {
+        self.layers.push(Layer::Value(value));
+        self
+    }
+
+    /// adds an override like `camera.position=(0, 0, -10)` on top of the previous layers.
+    ///
+    /// the path consists of field names and indices, like `scene_objects[1].radius`. the value is
+    /// merged onto the value at the path, missing objects on the way are created.
+    pub fn set(mut self, assignment: impl Into<String>) -> Self {
+        self.layers.push(Layer::Override(assignment.into()));
+        self
+    }
+
+    /// loads all layers and converts the merged value
+    pub fn load<T: TryFrom<AbabaValue, Error = AbabaParseError>>(
+        &self,
+    ) -> Result<T, AbabaParseError> {
+        let (value, sources) = self.merge_layers()?;
+        value.try_into().map_err(|err| locate(err, &sources))
+    }
+
+    /// checks the merged value of all layers against `schema`, reporting all errors at once
+    pub fn validate(&self, schema: &AbabaSchema) -> Result<(), Vec<AbabaParseError>> {
+        let (value, sources) = self.merge_layers().map_err(|err| vec![err])?;
+        schema
+            .validate(&value)
+            .map_err(|errors| errors.into_iter().map(|e| locate(e, &sources)).collect())
+    }
+
+    /// the merged value of all layers. its spans don't point into any of the layers.
+    pub fn load_value(&self) -> Result<AbabaValue, AbabaParseError> {
+        let (value, _) = self.merge_layers()?;
+        Ok(respan(value, Span::default()))
+    }
+
+    fn merge_layers(&self) -> Result<(AbabaValue, Vec<Source>), AbabaParseError> {
+        let mut merged: Option<AbabaValue> = None;
+        let mut sources = Vec::new();
+        // spans at 0 are the ones of values without a source
+        let mut offset = 1;
+
+        for layer in &self.layers {
+            merged = Some(match layer {
+                Layer::File(file) => {
+                    let source = self.resolver.read(file).map_err(|error| {
+                        AbabaParseError::IncludeFailed {
+                            file: file.clone(),
+                            error,
+                            span: Span::default(),
+                        }
+                    })?;
+                    let mut value = self
+                        .parser(&source)
+                        .with_file(file)
+                        .parse()
+                        .map_err(|err| in_file(file.clone(), source.clone(), err))?;
+
+                    shift_spans(&mut value, offset);
+                    sources.push(Source {
+                        file: file.clone(),
+                        source,
+                        offset,
+                    });
+
+                    match merged {
+                        Some(merged) => merged.merge(value),
+                        None => value.without_spreads(),
+                    }
+                }
+                Layer::Value(value) => {
+                    let value = respan(value.clone(), Span::default());
+                    match merged {
+                        Some(merged) => merged.merge(value),
+                        None => value.without_spreads(),
+                    }
+                }
+                Layer::Override(assignment) => {
+                    let (path, mut value) = self
+                        .parse_override(assignment)
+                        .map_err(|err| in_file(OVERRIDE.into(), assignment.clone(), err))?;
+
+                    shift_spans(&mut value, offset);
+                    sources.push(Source {
+                        file: OVERRIDE.into(),
+                        source: assignment.clone(),
+                        offset,
+                    });
+
+                    let mut merged = merged.unwrap_or_else(empty_object);
+                    set(&mut merged, &path, value).map_err(|err| locate(err, &sources))?;
+                    merged
+                }
+            });
+
+            offset = sources
+                .last()
+                .map_or(offset, |s| s.offset + s.source.len() + 1);
+        }
+
+        match merged {
+            Some(merged) => Ok((merged, sources)),
+            None => Err(AbabaParseError::NoContent),
+        }
+    }
+
+    // the path and value of `key.path=value`, the spans point into the whole assignment
+    fn parse_override(
+        &self,
+        assignment: &str,
+    ) -> Result<(Vec<AbabaPathSegment>, AbabaValue), AbabaParseError> {
+        let Some((path, value)) = assignment.split_once('=') else {
+            return Err(AbabaParseError::InvalidOverride {
+                span: Span::new(0, assignment.len()),
+            });
+        };
+        let Some(path) = parse_path(path) else {
+            return Err(AbabaParseError::InvalidOverride {
+                span: Span::new(0, path.len()),
+            });
+        };
+
+        let offset = assignment.len() - value.len();
+        match self.parser(value).parse() {
+            Ok(mut value) => {
+                shift_spans(&mut value, offset);
+                Ok((path, value))
+            }
+            Err(mut err) => {
+                if let Some(span) = err.root_span_mut() {
+                    *span = Span::new(span.start + offset, span.end + offset);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    fn parser<'s>(&self, source: &'s str) -> AbabaParser<'s>
+    where
+        'a: 's,
+    {
+        let parser = AbabaParser::new(source).with_resolver(self.resolver);
+        match self.params {
+            Some(params) => parser.with_params(params),
+            None => parser,
+        }
+    }
+}
+
+impl Default for AbabaLoader<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn in_file(file: PathBuf, source: String, err: AbabaParseError) -> AbabaParseError {
+    AbabaParseError::Included {
+        file,
+        source,
+        span: None,
+        inner: Box::new(err),
+    }
+}
+
+// moves an error about the merged value into the layer its span belongs to
+fn locate(mut err: AbabaParseError, sources: &[Source]) -> AbabaParseError {
+    let Some(span) = err.root_span_mut() else {
+        return err;
+    };
+    let Some(source) = sources
+        .iter()
+        .find(|s| (s.offset..=s.offset + s.source.len()).contains(&span.start))
+    else {
+        return err;
+    };
+
+    *span = Span::new(span.start - source.offset, span.end - source.offset);
+    in_file(source.file.clone(), source.source.clone(), err)
+}
+
+fn empty_object() -> AbabaValue {
+    AbabaValueKind::Object {
+        struct_type: None,
+        fields: HashMap::new(),
+    }
+    .into()
+}
+
+// `camera.position` or `scene_objects[1].radius`
+fn parse_path(path: &str) -> Option<Vec<AbabaPathSegment>> {
+    let mut segments = Vec::new();
+
+    for part in path.split('.') {
+        let (name, mut indices) = part.split_at(part.find('[').unwrap_or(part.len()));
+
+        match name {
+            // `[0].a` for lists at the top
+            "" if segments.is_empty() && !indices.is_empty() => {}
+            name if is_ident(name) => segments.push(AbabaPathSegment::Field(name.to_string())),
+            _ => return None,
+        }
+
+        while !indices.is_empty() {
+            let (index, rest) = indices.strip_prefix('[')?.split_once(']')?;
+            segments.push(AbabaPathSegment::Index(index.parse().ok()?));
+            indices = rest;
+        }
+    }
+
+    Some(segments)
+}
+
+// merges `new` onto the value at `path` in `value`
+fn set(
+    value: &mut AbabaValue,
+    path: &[AbabaPathSegment],
+    new: AbabaValue,
+) -> Result<(), AbabaParseError> {
+    let Some((segment, path)) = path.split_first() else {
+        let old = std::mem::replace(value, empty_object());
+        *value = old.merge(new);
+        return Ok(());
+    };
+
+    let span = value.span;
+    match (segment, &mut value.kind) {
+        (AbabaPathSegment::Field(name), AbabaValueKind::Object { fields, .. }) => {
+            let field = fields.entry(name.clone()).or_insert_with(empty_object);
+            set(field, path, new).map_err(|err| err.in_field(name))
+        }
+        (
+            AbabaPathSegment::Index(i),
+            AbabaValueKind::List(items) | AbabaValueKind::Tuple { items, .. },
+        ) => {
+            let got = items.len();
+            let item = items
+                .get_mut(*i)
+                .ok_or(AbabaParseError::NotEnoughElements {
+                    expected: *i as i32 + 1,
+                    got,
+                    span,
+                })?;
+            set(item, path, new).map_err(|err| err.at_index(*i))
+        }
+        (segment, _) => Err(AbabaParseError::ValueTypeDidNotMatch {
+            expected: match segment {
+                AbabaPathSegment::Field(_) => "Object",
+                AbabaPathSegment::Index(_) => "List",
+            },
+            got: value.clone(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ababa_config_proc::AbabaDeserialize;
+    use std::path::Path;
+
+    #[allow(unused)]
+    #[derive(Debug, AbabaDeserialize)]
+    struct Fields {
+        a: f64,
+        b: (f64, f64),
+    }
+
+    fn files(files: &[(&str, &str)]) -> HashMap<PathBuf, String> {
+        files
+            .iter()
+            .map(|(path, source)| (PathBuf::from(path), source.to_string()))
+            .collect()
+    }
+
+    fn parse(s: &str) -> AbabaValue {
+        AbabaParser::new(s).parse().unwrap()
+    }
+
+    #[test]
+    fn paths() {
+        use AbabaPathSegment::*;
+        let field = |s: &str| Field(s.to_string());
+
+        assert_eq!(parse_path("a"), Some(vec![field("a")]));
+        assert_eq!(
+            parse_path("camera.position"),
+            Some(vec![field("camera"), field("position")])
+        );
+        assert_eq!(
+            parse_path("scene_objects[1][2].screen-width"),
+            Some(vec![
+                field("scene_objects"),
+                Index(1),
+                Index(2),
+                field("screen-width")
+            ])
+        );
+        assert_eq!(parse_path("[0].a"), Some(vec![Index(0), field("a")]));
+
+        for invalid in [
+            "", "a.", ".a", "a..b", "a[", "a[x]", "a[1]b", "a.[1]", "a b", "true",
+        ] {
+            assert_eq!(parse_path(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn layers() {
+        let files = files(&[
+            (
+                "base.ababa",
+                "Scene { camera: { position: (0, 0, 0), width: 64 }, objects: [1, 2] }",
+            ),
+            ("shot.ababa", "{ camera: { width: 128 }, objects: [.., 3] }"),
+        ]);
+        let loader = AbabaLoader::new()
+            .with_resolver(&files)
+            .file("base.ababa")
+            .file("shot.ababa");
+
+        assert_eq!(
+            loader.load_value().unwrap(),
+            parse("Scene { camera: { position: (0, 0, 0), width: 128 }, objects: [1, 2, 3] }")
+        );
+
+        let loader = loader
+            .set("camera.position=(0, 0, -10)")
+            .set("objects[2]=4")
+            .set("camera.fov=rad(90) / 2")
+            .set("light.color={ r: 1 }");
+        assert_eq!(
+            loader.load_value().unwrap(),
+            parse(
+                "Scene {
+                    camera: { position: (0, 0, -10), width: 128, fov: pi / 4 },
+                    objects: [1, 2, 4],
+                    light: { color: { r: 1 } },
+                }"
+            )
+        );
+
+        // values have no source, and the first layer has nothing to merge onto
+        let value = AbabaLoader::new()
+            .value(parse("{ a: [1], b: 2 }"))
+            .value(parse("{ a: [.., 2] }"))
+            .set("c=[.., 3]")
+            .load_value()
+            .unwrap();
+        assert_eq!(value, parse("{ a: [1, 2], b: 2, c: [3] }"));
+        assert_eq!(value.span, Span::default());
+
+        assert!(matches!(
+            AbabaLoader::new().load_value(),
+            Err(AbabaParseError::NoContent)
+        ));
+    }
+
+    #[test]
+    fn params() {
+        let files = files(&[("a.ababa", "{ width: param(\"width\", 64), height: 48 }")]);
+        let params = HashMap::from([("width".to_string(), parse("128"))]);
+        let loader = AbabaLoader::new().with_resolver(&files).file("a.ababa");
+
+        assert_eq!(
+            loader.load_value().unwrap(),
+            parse("{ width: 64, height: 48 }")
+        );
+        assert_eq!(
+            loader
+                .with_params(&params)
+                .set("height=param(\"width\") / 2")
+                .load_value()
+                .unwrap(),
+            parse("{ width: 128, height: 64 }")
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let files = files(&[
+            ("a.ababa", "{ a: 1, b: (1, 2) }"),
+            ("b.ababa", "{ a: \"x\", }"),
+            ("broken.ababa", "{ a: }"),
+        ]);
+        let loader = || AbabaLoader::new().with_resolver(&files).file("a.ababa");
+
+        // errors in a file
+        let err = loader().file("broken.ababa").load_value().unwrap_err();
+        assert_eq!(err.include_chain(), [Path::new("broken.ababa")]);
+        assert!(matches!(
+            err.root(),
+            AbabaParseError::UnexpectedToken { span, .. } if *span == Span::new(5, 6)
+        ));
+
+        let err = loader().file("missing.ababa").load_value().unwrap_err();
+        assert!(matches!(err, AbabaParseError::IncludeFailed { .. }));
+
+        // conversion errors are located in the layer the value comes from
+        let err = loader().file("b.ababa").load::<Fields>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected Number, got String (at `a`) (in `b.ababa`)"
+        );
+        assert!(matches!(
+            err.root(),
+            AbabaParseError::ValueTypeDidNotMatch { got, .. } if got.span == Span::new(5, 8)
+        ));
+
+        let err = loader().set("a=(1, 2)").load::<Fields>().unwrap_err();
+        assert_eq!(
+            err.diagnostic().to_string(),
+            "\
+error: expected Number, got Tuple
+ --> <override>:1:3
+  |
+1 | a=(1, 2)
+  |   ^^^^^^
+  = note: at `a`
+"
+        );
+
+        // invalid overrides
+        let err = loader().set("a").load_value().unwrap_err();
+        assert!(matches!(
+            err.root(),
+            AbabaParseError::InvalidOverride { span } if *span == Span::new(0, 1)
+        ));
+        let err = loader().set("a[x]=1").load_value().unwrap_err();
+        assert!(matches!(
+            err.root(),
+            AbabaParseError::InvalidOverride { span } if *span == Span::new(0, 4)
+        ));
+        let err = loader().set("a.b=1 +").load_value().unwrap_err();
+        assert!(matches!(
+            err.root(),
+            AbabaParseError::UnexpectedToken { span, .. } if *span == Span::new(7, 7)
+        ));
+
+        // paths that don't exist
+        let err = loader().set("a.b=1").load_value().unwrap_err();
+        assert_eq!(err.path(), "a");
+        assert!(matches!(
+            err.root(),
+            AbabaParseError::ValueTypeDidNotMatch { expected: "Object", got } if got.span == Span::new(5, 6)
+        ));
+        assert_eq!(err.include_chain(), [Path::new("a.ababa")]);
+
+        let err = loader().set("b[2]=1").load_value().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected 3 elements, got 2 (at `b`) (in `a.ababa`)"
+        );
+    }
+
+    #[test]
+    fn validation() {
+        use crate::AbabaDescribe;
+
+        let files = files(&[
+            ("a.ababa", "{ a: 1, b: (1, 2) }"),
+            ("b.ababa", "{ b: (1, \"x\") }"),
+        ]);
+        let loader = AbabaLoader::new()
+            .with_resolver(&files)
+            .file("a.ababa")
+            .file("b.ababa");
+
+        let errors = loader
+            .set("a=true")
+            .validate(&Fields::schema())
+            .unwrap_err();
+        let errors: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            [
+                "expected Number, got String (at `b[1]`) (in `b.ababa`)",
+                "expected Number, got Bool (at `a`) (in `<override>`)",
+            ]
+        );
+
+        let loader = AbabaLoader::new().with_resolver(&files).file("a.ababa");
+        assert!(loader.validate(&Fields::schema()).is_ok());
+        let errors = loader.file("missing.ababa").validate(&Fields::schema());
+        assert!(matches!(
+            &errors.unwrap_err()[..],
+            [AbabaParseError::IncludeFailed { .. }]
+        ));
+    }
+}
@@ -0,0 +1,1236 @@
+//! a language server for ababa files, speaking the language server protocol over any reader and
+//! writer, usually stdin and stdout. the JSON-RPC messages are read and written by hand, like
+//! everything else in this crate.
+//!
+//! the server is given the [AbabaSchema] of the documents, which it uses for:
+//!
+//! - diagnostics: all parse errors, or the errors of [AbabaSchema::validate] if there are none
+//! - hover: the rust type of the field under the cursor
+//! - completion: the fields that are still missing in an object, and the types of the objects
+//!   that are accepted at the cursor
+//! - go to definition: the file of an `include` or `import`, the binding of a `$name`
+//! - formatting with [crate::format_source]
+//!
+//! the schema only applies to documents whose outermost value has one of the types of the schema
+//! (like `Scene { .. }`), other files like included parts of a scene only get parse errors.
+//! documents are synced as a whole, open documents are read from the editor instead of the file
+//! system, also when they're included.
+//!
+//! ```no_run
+//! use ababa_config::{AbabaDescribe, AbabaLanguageServer};
+//! use ababa_config_proc::AbabaDeserialize;
+//!
+//! #[derive(AbabaDeserialize)]
+//! struct Scene {
+//!     objects: Vec<String>,
+//! }
+//!
+//! let mut server = AbabaLanguageServer::new(Scene::schema());
+//! server.run(std::io::stdin().lock(), std::io::stdout()).unwrap();
+//! ```
+
+use crate::include::relative_to;
+use crate::json::JsonValue;
+use crate::parser::unescape;
+use crate::tokenizer::{AbabaToken, AbabaTokenizer};
+use crate::{
+    format_source, AbabaFieldSchema, AbabaFileResolver, AbabaParser, AbabaResolver, AbabaSchema,
+    AbabaStructSchema, AbabaValue, AbabaValueKind, AbabaVariantSchema, Span,
+};
+use std::collections::HashMap;
+use std::io::{BufRead, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+// error codes of JSON-RPC and the protocol
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+const REQUEST_FAILED: i32 = -32803;
+
+// kinds of completion items
+const FIELD: usize = 5;
+const ENUM_MEMBER: usize = 20;
+const STRUCT: usize = 22;
+
+pub struct AbabaLanguageServer {
+    schema: AbabaSchema,
+    /// the text of all open documents, by uri
+    documents: HashMap<String, String>,
+    shutting_down: bool,
+}
+
+// the error of a request, sent back instead of a result
+struct ResponseError {
+    code: i32,
+    message: String,
+}
+
+impl ResponseError {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        ResponseError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+// an open document a request refers to
+struct Document<'a> {
+    text: &'a str,
+    path: Option<PathBuf>,
+}
+
+impl AbabaLanguageServer {
+    pub fn new(schema: AbabaSchema) -> Self {
+        AbabaLanguageServer {
+            schema,
+            documents: HashMap::new(),
+            shutting_down: false,
+        }
+    }
+
+    /// answers the messages from `input` until the client sends `exit` or closes the input.
+    ///
+    /// exiting without a `shutdown` request first is an error, as the protocol demands.
+    pub fn run(&mut self, mut input: impl BufRead, mut output: impl Write) -> std::io::Result<()> {
+        while let Some(body) = read_message(&mut input)? {
+            let message = match JsonValue::parse(&body) {
+                Ok(message) => message,
+                Err(err) => {
+                    let error = ResponseError::new(PARSE_ERROR, err.to_string());
+                    write_message(&mut output, &response(&JsonValue::Null, Err(error)))?;
+                    continue;
+                }
+            };
+
+            if message.get("method").as_str() == Some("exit") {
+                return match self.shutting_down {
+                    true => Ok(()),
+                    false => Err(Error::other("exit without shutdown")),
+                };
+            }
+
+            for reply in self.handle(&message) {
+                write_message(&mut output, &reply)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // the responses and notifications for a message of the client
+    fn handle(&mut self, message: &JsonValue) -> Vec<JsonValue> {
+        let params = message.get("params");
+        let (id, method) = (message.get("id"), message.get("method").as_str());
+
+        match (id, method) {
+            (JsonValue::Null, Some(method)) => self.notification(method, params),
+            (id, Some(method)) => vec![response(id, self.request(method, params))],
+            // a response, but the server never sends requests
+            (_, None) => vec![],
+        }
+    }
+
+    fn request(&mut self, method: &str, params: &JsonValue) -> Result<JsonValue, ResponseError> {
+        if self.shutting_down {
+            return Err(ResponseError::new(
+                INVALID_REQUEST,
+                "the server is shutting down",
+            ));
+        }
+
+        match method {
+            "initialize" => Ok(capabilities()),
+            "shutdown" => {
+                self.shutting_down = true;
+                Ok(JsonValue::Null)
+            }
+            "textDocument/hover" => {
+                let document = self.document(params)?;
+                let offset = offset(document.text, params.get("position"));
+                Ok(self.hover(&document, offset))
+            }
+            "textDocument/completion" => {
+                let document = self.document(params)?;
+                let offset = offset(document.text, params.get("position"));
+                Ok(self.completion(&document, offset))
+            }
+            "textDocument/definition" => {
+                let document = self.document(params)?;
+                let offset = offset(document.text, params.get("position"));
+                Ok(self.definition(&document, offset))
+            }
+            "textDocument/formatting" => {
+                let document = self.document(params)?;
+                let formatted = format_source(document.text)
+                    .map_err(|err| ResponseError::new(REQUEST_FAILED, err.to_string()))?;
+
+                let edits = match formatted == document.text {
+                    true => vec![],
+                    false => vec![JsonValue::object([
+                        (
+                            "range",
+                            range(document.text, Span::new(0, document.text.len())),
+                        ),
+                        ("newText", formatted.into()),
+                    ])],
+                };
+                Ok(edits.into())
+            }
+            _ => Err(ResponseError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method `{method}`"),
+            )),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &JsonValue) -> Vec<JsonValue> {
+        let Some(uri) = params.get("textDocument").get("uri").as_str() else {
+            return vec![];
+        };
+
+        match method {
+            "textDocument/didOpen" => {
+                let text = params.get("textDocument").get("text").as_str();
+                self.documents
+                    .insert(uri.to_string(), text.unwrap_or("").to_string());
+                vec![self.diagnostics(uri)]
+            }
+            // the whole document is sent, see `capabilities`
+            "textDocument/didChange" => {
+                let changes = params.get("contentChanges").as_array();
+                if let Some(text) = changes.last().and_then(|c| c.get("text").as_str()) {
+                    self.documents.insert(uri.to_string(), text.to_string());
+                }
+                vec![self.diagnostics(uri)]
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                vec![publish_diagnostics(uri, vec![])]
+            }
+            _ => vec![],
+        }
+    }
+
+    fn document(&self, params: &JsonValue) -> Result<Document<'_>, ResponseError> {
+        let uri = params.get("textDocument").get("uri").as_str().unwrap_or("");
+        match self.documents.get(uri) {
+            Some(text) => Ok(Document {
+                text,
+                path: uri_to_path(uri),
+            }),
+            None => Err(ResponseError::new(
+                INVALID_PARAMS,
+                format!("`{uri}` isn't open"),
+            )),
+        }
+    }
+
+    // the value of the document, as far as it could be parsed, and the errors
+    fn parse(&self, document: &Document) -> (Option<AbabaValue>, Vec<crate::AbabaParseError>) {
+        let resolver = OpenDocuments(&self.documents);
+        let parser = AbabaParser::new(document.text).with_resolver(&resolver);
+        match &document.path {
+            Some(path) => parser.with_file(path).parse_recovering(),
+            None => parser.parse_recovering(),
+        }
+    }
+
+    // the schema of the outermost value, if it applies to the document
+    fn schema_of(&self, value: &AbabaValue) -> Option<&AbabaSchema> {
+        let names = type_names(&self.schema);
+        let applies = value
+            .struct_type()
+            .is_some_and(|t| names.iter().any(|(name, _)| *name == t));
+        applies.then_some(&self.schema)
+    }
+
+    fn diagnostics(&self, uri: &str) -> JsonValue {
+        let text = &self.documents[uri];
+        let document = Document {
+            text,
+            path: uri_to_path(uri),
+        };
+
+        let (value, mut errors) = self.parse(&document);
+        // a partial value would be missing a lot more than what's broken
+        if let (Some(value), true) = (&value, errors.is_empty()) {
+            if let Some(Err(invalid)) = self.schema_of(value).map(|s| s.validate(value)) {
+                errors = invalid;
+            }
+        }
+
+        let diagnostics = errors
+            .iter()
+            .map(|err| {
+                let span = err.span().unwrap_or(Span::new(0, 0));
+                JsonValue::object([
+                    ("range", range(text, span)),
+                    ("severity", 1usize.into()),
+                    ("source", "ababa".into()),
+                    ("message", err.to_string().into()),
+                ])
+            })
+            .collect();
+        publish_diagnostics(uri, diagnostics)
+    }
+
+    // the field the value under the cursor belongs to, or the field name under the cursor
+    fn hover(&self, document: &Document, offset: usize) -> JsonValue {
+        let tokens: Vec<_> = AbabaTokenizer::new(document.text).collect();
+        let (Some(i), (Some(value), _)) = (token_at(&tokens, offset), self.parse(document)) else {
+            return JsonValue::Null;
+        };
+
+        let (token, span) = tokens[i];
+        let steps = path_to(&value, self.schema_of(&value), |s| {
+            s.start <= span.start && span.start < s.end
+        });
+        let last = steps
+            .last()
+            .expect("the path starts at the outermost value");
+
+        let field = match (token, tokens.get(i + 1)) {
+            // a field name, in the object that's the last step
+            (AbabaToken::Ident(name), Some((AbabaToken::FieldSeparator, _))) => last
+                .schema
+                .and_then(|schema| struct_of(schema, last.value))
+                .and_then(|schema| field(schema, name)),
+            _ => last.field,
+        };
+
+        match field {
+            Some(field) => JsonValue::object([(
+                "contents",
+                JsonValue::object([
+                    ("kind", "markdown".into()),
+                    ("value", hover_text(field).into()),
+                ]),
+            )]),
+            None => JsonValue::Null,
+        }
+    }
+
+    // field names in objects, type names of values
+    fn completion(&self, document: &Document, offset: usize) -> JsonValue {
+        let text = document.text;
+        let tokens: Vec<_> = AbabaTokenizer::new(text)
+            .take_while(|(_, span)| span.end <= offset)
+            .collect();
+        // without the name that's being typed
+        let before = match tokens.last() {
+            Some((AbabaToken::Ident(_), span)) if span.end == offset => &tokens[..tokens.len() - 1],
+            _ => &tokens[..],
+        };
+
+        let value = self.parse(document).0;
+        let schema = value.as_ref().and_then(|value| self.schema_of(value));
+        let (Some(value), Some(schema)) = (&value, schema) else {
+            // the outermost value
+            return match before.is_empty() {
+                true => completions(&self.schema),
+                false => JsonValue::Array(vec![]),
+            };
+        };
+
+        // the innermost list, tuple or object around the cursor. unclosed ones end with the text.
+        let steps = path_to(value, Some(schema), |s| {
+            s.start < offset && (offset < s.end || s.end == text.len())
+        });
+        let container = steps
+            .iter()
+            .rev()
+            .find(|step| {
+                matches!(
+                    step.value.kind,
+                    AbabaValueKind::Object { .. }
+                        | AbabaValueKind::List(_)
+                        | AbabaValueKind::Tuple { .. }
+                )
+            })
+            .filter(|step| step.value.span.start < offset);
+        let Some(container) = container else {
+            return match before.is_empty() {
+                true => completions(&self.schema),
+                false => JsonValue::Array(vec![]),
+            };
+        };
+        let structure = container.schema.and_then(|s| struct_of(s, container.value));
+
+        let items = match (&container.value.kind, before) {
+            (
+                AbabaValueKind::Object { fields, .. },
+                [.., (AbabaToken::LeftBrace | AbabaToken::ListSeparator, _)],
+            ) => {
+                let present = |f: &&AbabaFieldSchema| {
+                    fields.contains_key(f.name) || f.aliases.iter().any(|a| fields.contains_key(*a))
+                };
+                let missing = structure
+                    .iter()
+                    .flat_map(|s| &s.fields)
+                    .filter(|f| !present(f));
+                missing
+                    .map(|f| {
+                        JsonValue::object([
+                            ("label", f.name.into()),
+                            ("kind", FIELD.into()),
+                            ("detail", f.ty.into()),
+                        ])
+                    })
+                    .collect()
+            }
+            (
+                AbabaValueKind::Object { .. },
+                [.., (AbabaToken::Ident(name), _), (AbabaToken::FieldSeparator, _)],
+            ) => {
+                let field = structure.and_then(|s| field(s, name)).map(|f| &f.schema);
+                let schema = field.or_else(|| structure.and_then(|s| s.other_fields.as_deref()));
+                return schema.map_or(JsonValue::Array(vec![]), completions);
+            }
+            (
+                AbabaValueKind::List(items) | AbabaValueKind::Tuple { items, .. },
+                [.., (AbabaToken::LeftBracket | AbabaToken::LeftParen | AbabaToken::ListSeparator, _)],
+            ) => {
+                let index = items.iter().filter(|item| item.span.end <= offset).count();
+                let item = container
+                    .schema
+                    .and_then(|s| item_schema(s, container.value, index));
+                return item.map_or(JsonValue::Array(vec![]), completions);
+            }
+            _ => vec![],
+        };
+        JsonValue::Array(items)
+    }
+
+    // the included file, or the binding of a reference
+    fn definition(&self, document: &Document, offset: usize) -> JsonValue {
+        let tokens: Vec<_> = AbabaTokenizer::new(document.text).collect();
+        let Some(i) = token_at(&tokens, offset) else {
+            return JsonValue::Null;
+        };
+
+        match tokens[i] {
+            (AbabaToken::Str(path), span)
+                if i > 0 && matches!(tokens[i - 1].0, AbabaToken::Include | AbabaToken::Import) =>
+            {
+                match unescape(path, span) {
+                    Ok(path) => {
+                        let file = relative_to(document.path.as_deref(), &path);
+                        location(&path_to_uri(&file), "", Span::new(0, 0))
+                    }
+                    Err(_) => JsonValue::Null,
+                }
+            }
+            (AbabaToken::Reference(name), _) => {
+                if let Some(span) = binding(&tokens[..i], name) {
+                    let uri = document
+                        .path
+                        .as_deref()
+                        .map(path_to_uri)
+                        .unwrap_or_default();
+                    return location(&uri, document.text, span);
+                }
+
+                // the bindings of imported files, which are only in front of the value
+                let resolver = OpenDocuments(&self.documents);
+                for window in tokens[..i].windows(2) {
+                    let [(AbabaToken::Import, _), (AbabaToken::Str(path), span)] = window else {
+                        continue;
+                    };
+                    let Ok(path) = unescape(path, *span) else {
+                        continue;
+                    };
+                    let file = relative_to(document.path.as_deref(), &path);
+                    let Ok(text) = resolver.read(&file) else {
+                        continue;
+                    };
+
+                    let imported: Vec<_> = AbabaTokenizer::new(&text).collect();
+                    if let Some(span) = binding(&imported, name) {
+                        return location(&path_to_uri(&file), &text, span);
+                    }
+                }
+                JsonValue::Null
+            }
+            _ => JsonValue::Null,
+        }
+    }
+}
+
+// reads open documents from the editor, all other files from the file system
+struct OpenDocuments<'a>(&'a HashMap<String, String>);
+
+impl AbabaResolver for OpenDocuments<'_> {
+    fn read(&self, path: &Path) -> std::io::Result<String> {
+        let open = self
+            .0
+            .iter()
+            .find(|(uri, _)| uri_to_path(uri).as_deref() == Some(path));
+        match open {
+            Some((_, text)) => Ok(text.clone()),
+            None => AbabaFileResolver.read(path),
+        }
+    }
+}
+
+// a value containing the cursor, with its schema and the field it's (part of) the value of
+struct Step<'v, 's> {
+    value: &'v AbabaValue,
+    schema: Option<&'s AbabaSchema>,
+    field: Option<&'s AbabaFieldSchema>,
+}
+
+// the values from `value` down to the innermost one whose span is `inside`
+fn path_to<'v, 's>(
+    value: &'v AbabaValue,
+    schema: Option<&'s AbabaSchema>,
+    inside: impl Fn(Span) -> bool,
+) -> Vec<Step<'v, 's>> {
+    let mut steps = vec![Step {
+        value,
+        schema,
+        field: None,
+    }];
+
+    loop {
+        let last = steps.last().expect("there's always the outermost value");
+        let next = match &last.value.kind {
+            AbabaValueKind::Object { fields, .. } => fields
+                .iter()
+                .find(|(_, v)| inside(v.span))
+                .map(|(name, v)| {
+                    let structure = last.schema.and_then(|s| struct_of(s, last.value));
+                    let field = structure.and_then(|s| field(s, name));
+                    let schema = match field {
+                        Some(field) => Some(&field.schema),
+                        None => last.schema.and_then(|s| other_fields(s, last.value)),
+                    };
+                    Step {
+                        value: v,
+                        schema,
+                        field,
+                    }
+                }),
+            AbabaValueKind::List(items) | AbabaValueKind::Tuple { items, .. } => items
+                .iter()
+                .enumerate()
+                .find(|(_, item)| inside(item.span))
+                .map(|(i, item)| Step {
+                    value: item,
+                    schema: last.schema.and_then(|s| item_schema(s, last.value, i)),
+                    field: last.field,
+                }),
+            _ => None,
+        };
+
+        match next {
+            Some(step) => steps.push(step),
+            None => return steps,
+        }
+    }
+}
+
+// the schema that describes `value`, without options and single field variants around it
+fn unwrap_schema<'s>(mut schema: &'s AbabaSchema, value: &AbabaValue) -> &'s AbabaSchema {
+    loop {
+        schema = match schema {
+            AbabaSchema::Option(inner) => inner,
+            AbabaSchema::Enum { variants, .. } => match variant(variants, value) {
+                Some(AbabaVariantSchema::Newtype { inner, .. }) => inner,
+                _ => return schema,
+            },
+            _ => return schema,
+        };
+    }
+}
+
+fn variant<'s>(
+    variants: &'s [AbabaVariantSchema],
+    value: &AbabaValue,
+) -> Option<&'s AbabaVariantSchema> {
+    let struct_type = value.struct_type()?;
+    variants.iter().find(|v| v.name() == struct_type)
+}
+
+// the struct an object is read as
+fn struct_of<'s>(schema: &'s AbabaSchema, value: &AbabaValue) -> Option<&'s AbabaStructSchema> {
+    match unwrap_schema(schema, value) {
+        AbabaSchema::Struct(schema) => Some(schema),
+        AbabaSchema::Enum { variants, .. } => match variant(variants, value) {
+            Some(AbabaVariantSchema::Struct(schema)) => Some(schema),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn field<'s>(schema: &'s AbabaStructSchema, name: &str) -> Option<&'s AbabaFieldSchema> {
+    schema
+        .fields
+        .iter()
+        .find(|f| f.name == name || f.aliases.contains(&name))
+}
+
+// the schema of the fields of an object that aren't in its struct
+fn other_fields<'s>(schema: &'s AbabaSchema, value: &AbabaValue) -> Option<&'s AbabaSchema> {
+    match unwrap_schema(schema, value) {
+        AbabaSchema::Map(inner) => Some(inner),
+        _ => struct_of(schema, value)?.other_fields.as_deref(),
+    }
+}
+
+// the schema of item `i` of a list or tuple
+fn item_schema<'s>(
+    schema: &'s AbabaSchema,
+    value: &AbabaValue,
+    i: usize,
+) -> Option<&'s AbabaSchema> {
+    match unwrap_schema(schema, value) {
+        AbabaSchema::List(item) | AbabaSchema::Array { item, .. } => Some(item),
+        AbabaSchema::Tuple(items) => items.get(i),
+        AbabaSchema::Enum { variants, .. } => match variant(variants, value) {
+            Some(AbabaVariantSchema::Tuple { items, .. }) => items.get(i),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// the types a value of the schema can have, with the kind of completion item
+fn type_names(schema: &AbabaSchema) -> Vec<(&'static str, usize)> {
+    match schema {
+        AbabaSchema::Struct(schema) => vec![(schema.name, STRUCT)],
+        AbabaSchema::Enum { variants, .. } => variants
+            .iter()
+            .map(|v| match v {
+                AbabaVariantSchema::Unit { name } => (*name, ENUM_MEMBER),
+                v => (v.name(), STRUCT),
+            })
+            .collect(),
+        AbabaSchema::Option(inner) => {
+            let mut names = vec![("None", ENUM_MEMBER)];
+            names.extend(type_names(inner));
+            names
+        }
+        _ => vec![],
+    }
+}
+
+fn completions(schema: &AbabaSchema) -> JsonValue {
+    type_names(schema)
+        .into_iter()
+        .map(|(name, kind)| JsonValue::object([("label", name.into()), ("kind", kind.into())]))
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn hover_text(field: &AbabaFieldSchema) -> String {
+    let mut text = format!("```rust\n{}: {}\n```", field.name, field.ty);
+    if !field.aliases.is_empty() {
+        let aliases: Vec<_> = field.aliases.iter().map(|a| format!("`{a}`")).collect();
+        text.push_str(&format!("\n\nalso called {}", aliases.join(", ")));
+    }
+    if let Some(default) = field.default {
+        text.push_str(&format!("\n\ndefaults to `{default}()`"));
+    }
+    text
+}
+
+// the span of the name of `let name = ..`, `for name in ..` or `&name`
+fn binding(tokens: &[(AbabaToken, Span)], name: &str) -> Option<Span> {
+    tokens
+        .iter()
+        .enumerate()
+        .find_map(|(i, (token, span))| match token {
+            AbabaToken::Anchor(anchor) if *anchor == name => Some(*span),
+            AbabaToken::Ident(ident)
+                if *ident == name
+                    && i > 0
+                    && matches!(tokens[i - 1].0, AbabaToken::Let | AbabaToken::For) =>
+            {
+                Some(*span)
+            }
+            _ => None,
+        })
+}
+
+// the token under the cursor, or the one right in front of it
+fn token_at(tokens: &[(AbabaToken, Span)], offset: usize) -> Option<usize> {
+    tokens
+        .iter()
+        .position(|(_, span)| span.start <= offset && offset < span.end)
+        .or_else(|| tokens.iter().position(|(_, span)| span.end == offset))
+}
+
+fn capabilities() -> JsonValue {
+    let triggers = ["{", "[", "(", ",", ":"].map(JsonValue::from);
+    JsonValue::object([
+        (
+            "capabilities",
+            JsonValue::object([
+                // the whole document on every change
+                ("textDocumentSync", 1usize.into()),
+                ("hoverProvider", true.into()),
+                (
+                    "completionProvider",
+                    JsonValue::object([("triggerCharacters", triggers.to_vec().into())]),
+                ),
+                ("definitionProvider", true.into()),
+                ("documentFormattingProvider", true.into()),
+            ]),
+        ),
+        (
+            "serverInfo",
+            JsonValue::object([("name", "ababa-lsp".into())]),
+        ),
+    ])
+}
+
+fn response(id: &JsonValue, result: Result<JsonValue, ResponseError>) -> JsonValue {
+    let (key, value) = match result {
+        Ok(result) => ("result", result),
+        Err(error) => (
+            "error",
+            JsonValue::object([
+                ("code", JsonValue::Number(error.code.into())),
+                ("message", error.message.into()),
+            ]),
+        ),
+    };
+    JsonValue::object([("jsonrpc", "2.0".into()), ("id", id.clone()), (key, value)])
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<JsonValue>) -> JsonValue {
+    JsonValue::object([
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/publishDiagnostics".into()),
+        (
+            "params",
+            JsonValue::object([("uri", uri.into()), ("diagnostics", diagnostics.into())]),
+        ),
+    ])
+}
+
+fn location(uri: &str, text: &str, span: Span) -> JsonValue {
+    JsonValue::object([("uri", uri.into()), ("range", range(text, span))])
+}
+
+fn range(text: &str, span: Span) -> JsonValue {
+    JsonValue::object([
+        ("start", position(text, span.start)),
+        ("end", position(text, span.end)),
+    ])
+}
+
+// positions count the UTF-16 code units in the line, like the protocol does by default
+fn position(text: &str, offset: usize) -> JsonValue {
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = before.matches('\n').count();
+    let character: usize = before[line_start..].chars().map(char::len_utf16).sum();
+    JsonValue::object([("line", line.into()), ("character", character.into())])
+}
+
+// the byte offset of a position, positions after the end of a line are at its end
+fn offset(text: &str, position: &JsonValue) -> usize {
+    let line = position.get("line").as_usize().unwrap_or(0);
+    let character = position.get("character").as_usize().unwrap_or(0);
+
+    let line_start = match line {
+        0 => 0,
+        line => match text.match_indices('\n').nth(line - 1) {
+            Some((i, _)) => i + 1,
+            None => return text.len(),
+        },
+    };
+
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = path
+            .get(i + 1..i + 3)
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
+fn path_to_uri(path: &Path) -> String {
+    let mut uri = "file://".to_string();
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            byte => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    uri
+}
+
+// the body of the next message, `None` at the end of the input
+fn read_message(input: &mut impl BufRead) -> std::io::Result<Option<String>> {
+    let mut length = None;
+
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+        match line.split_once(':') {
+            Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                length = value.trim().parse().ok();
+            }
+            // other headers, like `Content-Type`
+            Some(_) => {}
+            None if line.is_empty() && length.is_some() => break,
+            None if line.is_empty() => {}
+            None => return Err(Error::new(ErrorKind::InvalidData, "invalid header")),
+        }
+    }
+
+    let mut body = vec![0; length.expect("the headers end after the length")];
+    input.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err))
+}
+
+fn write_message(output: &mut impl Write, message: &JsonValue) -> std::io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AbabaDescribe;
+    use ababa_config_proc::AbabaDeserialize;
+
+    #[allow(dead_code)]
+    #[derive(AbabaDeserialize)]
+    struct Scene {
+        camera: Camera,
+        objects: Vec<Shape>,
+        background: Option<Shape>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(AbabaDeserialize)]
+    struct Camera {
+        position: (f64, f64, f64),
+        #[ababa(alias = "fov")]
+        field_of_view: f64,
+        #[ababa(default)]
+        exposure: f64,
+    }
+
+    #[allow(dead_code)]
+    #[derive(AbabaDeserialize)]
+    enum Shape {
+        Sphere { radius: f64 },
+        Plane { height: f64 },
+        Sky,
+    }
+
+    const URI: &str = "file:///scenes/main.ababa";
+
+    fn request(id: usize, method: &str, params: JsonValue) -> JsonValue {
+        JsonValue::object([
+            ("jsonrpc", "2.0".into()),
+            ("id", id.into()),
+            ("method", method.into()),
+            ("params", params),
+        ])
+    }
+
+    fn notification(method: &str, params: JsonValue) -> JsonValue {
+        JsonValue::object([
+            ("jsonrpc", "2.0".into()),
+            ("method", method.into()),
+            ("params", params),
+        ])
+    }
+
+    fn open(uri: &str, text: &str) -> JsonValue {
+        notification(
+            "textDocument/didOpen",
+            JsonValue::object([(
+                "textDocument",
+                JsonValue::object([("uri", uri.into()), ("text", text.into())]),
+            )]),
+        )
+    }
+
+    // a request about the position of the first `at` in `text`, shifted by `shift` bytes
+    fn at(method: &str, text: &str, at: &str, shift: usize) -> JsonValue {
+        let offset = text.find(at).expect("the text contains `at`") + shift;
+        request(
+            2,
+            method,
+            JsonValue::object([
+                ("textDocument", JsonValue::object([("uri", URI.into())])),
+                ("position", position(text, offset)),
+            ]),
+        )
+    }
+
+    // runs the server on the messages, returns everything it sent back
+    fn session(messages: &[JsonValue]) -> (Vec<JsonValue>, std::io::Result<()>) {
+        let mut input = Vec::new();
+        for message in messages {
+            write_message(&mut input, message).unwrap();
+        }
+
+        let mut output = Vec::new();
+        let result = AbabaLanguageServer::new(Scene::schema()).run(&input[..], &mut output);
+
+        let mut replies = Vec::new();
+        let mut output = &output[..];
+        while let Some(body) = read_message(&mut output).unwrap() {
+            replies.push(JsonValue::parse(&body).unwrap());
+        }
+        (replies, result)
+    }
+
+    // the result of a request about the only open document
+    fn result(text: &str, request: JsonValue) -> JsonValue {
+        let (mut replies, _) = session(&[open(URI, text), request]);
+        replies.pop().unwrap().get("result").clone()
+    }
+
+    fn diagnostics(text: &str) -> Vec<(String, String)> {
+        let (replies, _) = session(&[open(URI, text)]);
+        let params = replies[0].get("params");
+        assert_eq!(params.get("uri").as_str(), Some(URI));
+
+        params
+            .get("diagnostics")
+            .as_array()
+            .iter()
+            .map(|d| {
+                let start = d.get("range").get("start");
+                (
+                    start.to_string(),
+                    d.get("message").as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    fn labels(completion: &JsonValue) -> Vec<&str> {
+        let items = completion.as_array().iter();
+        items
+            .map(|item| item.get("label").as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn lifecycle() {
+        let (replies, result) = session(&[
+            request(1, "initialize", JsonValue::object([])),
+            notification("initialized", JsonValue::object([])),
+            request(2, "textDocument/rename", JsonValue::object([])),
+            request(3, "shutdown", JsonValue::Null),
+            request(4, "initialize", JsonValue::object([])),
+            notification("exit", JsonValue::Null),
+        ]);
+        assert!(result.is_ok());
+        assert_eq!(replies.len(), 4);
+
+        let capabilities = replies[0].get("result").get("capabilities");
+        assert_eq!(capabilities.get("textDocumentSync").as_usize(), Some(1));
+        assert_eq!(capabilities.get("hoverProvider"), &JsonValue::Bool(true));
+        assert_eq!(replies[0].get("id").as_usize(), Some(1));
+
+        let code = |reply: &JsonValue| reply.get("error").get("code").clone();
+        assert_eq!(
+            code(&replies[1]),
+            JsonValue::Number(METHOD_NOT_FOUND.into())
+        );
+        assert_eq!(
+            replies[2].to_string(),
+            r#"{"jsonrpc":"2.0","id":3,"result":null}"#
+        );
+        assert_eq!(code(&replies[3]), JsonValue::Number(INVALID_REQUEST.into()));
+
+        let (_, result) = session(&[notification("exit", JsonValue::Null)]);
+        assert!(result.is_err());
+        // the client went away
+        let (_, result) = session(&[request(1, "initialize", JsonValue::object([]))]);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn framing() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"shutdown"}"#;
+        let input = format!(
+            "Content-Type: application/vscode-jsonrpc; charset=utf-8\r\n\
+             content-length: {}\r\n\r\n{body}Content-Length: 5\r\n\r\n{{1: 2",
+            body.len()
+        );
+
+        let mut output = Vec::new();
+        let mut server = AbabaLanguageServer::new(Scene::schema());
+        server.run(input.as_bytes(), &mut output).unwrap();
+
+        let mut output = &output[..];
+        let shutdown = read_message(&mut output).unwrap().unwrap();
+        assert_eq!(shutdown, r#"{"jsonrpc":"2.0","id":1,"result":null}"#);
+        let invalid = JsonValue::parse(&read_message(&mut output).unwrap().unwrap()).unwrap();
+        assert_eq!(invalid.get("id"), &JsonValue::Null);
+        assert_eq!(
+            invalid.get("error").get("code"),
+            &JsonValue::Number(PARSE_ERROR.into())
+        );
+        assert_eq!(read_message(&mut output).unwrap(), None);
+
+        let mut server = AbabaLanguageServer::new(Scene::schema());
+        let err = server
+            .run("hello\r\n\r\n".as_bytes(), Vec::new())
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn diagnostics_of_documents() {
+        assert_eq!(
+            diagnostics("{ a: 1 +, b: [2, 3 4] }"),
+            [
+                (
+                    r#"{"line":0,"character":8}"#.to_string(),
+                    "expected value, got ','".to_string()
+                ),
+                (
+                    r#"{"line":0,"character":19}"#.to_string(),
+                    "expected ',' or closing brace, got number `4`".to_string()
+                ),
+            ]
+        );
+
+        let scene = "Scene {\n  camera: Camera { position: (0, 0, 0) },\n  objects: [Cube {}],\n}";
+        let found = diagnostics(scene);
+        let starts: Vec<_> = found.iter().map(|(start, _)| start.as_str()).collect();
+        assert_eq!(
+            starts,
+            [
+                r#"{"line":1,"character":10}"#,
+                r#"{"line":2,"character":12}"#
+            ]
+        );
+
+        // not a scene, so there's nothing to check
+        assert_eq!(diagnostics("Camera { position: 1 }"), []);
+        assert_eq!(diagnostics("[Cube {}]"), []);
+
+        // fixed, then closed
+        let change = notification(
+            "textDocument/didChange",
+            JsonValue::object([
+                ("textDocument", JsonValue::object([("uri", URI.into())])),
+                (
+                    "contentChanges",
+                    vec![JsonValue::object([("text", "[1, 2]".into())])].into(),
+                ),
+            ]),
+        );
+        let close = notification(
+            "textDocument/didClose",
+            JsonValue::object([("textDocument", JsonValue::object([("uri", URI.into())]))]),
+        );
+        let (replies, _) = session(&[open(URI, "[1 2]"), change, close]);
+        let count = |reply: &JsonValue| reply.get("params").get("diagnostics").as_array().len();
+        assert_eq!(replies.iter().map(count).collect::<Vec<_>>(), [1, 0, 0]);
+    }
+
+    #[test]
+    fn hover() {
+        let scene =
+            "Scene {\n  camera: Camera { position: (1, 2, 3), fov: 90 },\n  objects: [],\n}";
+        let hover = |at_: &str, shift| {
+            let hover = result(scene, at("textDocument/hover", scene, at_, shift));
+            hover
+                .get("contents")
+                .get("value")
+                .as_str()
+                .map(str::to_string)
+        };
+
+        assert_eq!(
+            hover("fov", 1).as_deref(),
+            Some("```rust\nfield_of_view: f64\n```\n\nalso called `fov`")
+        );
+        assert_eq!(
+            hover("2", 0).as_deref(),
+            Some("```rust\nposition: (f64, f64, f64)\n```")
+        );
+        assert_eq!(
+            hover("objects", 0).as_deref(),
+            Some("```rust\nobjects: Vec<Shape>\n```")
+        );
+        assert_eq!(hover("Scene", 0), None);
+    }
+
+    #[test]
+    fn completion() {
+        let complete = |text: &str, at_: &str, shift| {
+            let completion = result(text, at("textDocument/completion", text, at_, shift));
+            labels(&completion)
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(complete("", "", 0), ["Scene"]);
+        assert_eq!(complete("Sc", "Sc", 2), ["Scene"]);
+
+        let scene = "Scene { camera: Camera { position: (0, 0, 0), f }, objects: [S";
+        assert_eq!(complete(scene, "Scene", 7), ["background"]);
+        assert_eq!(complete(scene, "f }", 1), ["field_of_view", "exposure"]);
+        assert_eq!(complete(scene, "[S", 2), ["Sphere", "Plane", "Sky"]);
+        assert_eq!(complete(scene, "(0", 1), Vec::<String>::new());
+
+        let scene = "Scene { camera: Camera { position: (0, 0, 0), fov: 1 }, background: ";
+        assert_eq!(
+            complete(scene, "background: ", 12),
+            ["Sphere", "Plane", "Sky"]
+        );
+        assert_eq!(complete(scene, "camera: ", 8), ["Camera"]);
+
+        let detail = result(scene, at("textDocument/completion", scene, "Scene", 7));
+        assert_eq!(
+            detail.as_array()[0].get("detail").as_str(),
+            Some("Vec<Shape>")
+        );
+        assert_eq!(detail.as_array()[0].get("kind").as_usize(), Some(FIELD));
+    }
+
+    #[test]
+    fn definition() {
+        let scene = "import \"../lib/shapes.ababa\";\nlet camera = Camera { fov: 1 };\n\
+                     [$camera, include \"sky part.ababa\", $sphere]";
+        let shapes = "let sphere = Sphere { radius: 1 };";
+        let definition = |at_: &str, shift| {
+            let (mut replies, _) = session(&[
+                open("file:///lib/shapes.ababa", shapes),
+                open(URI, scene),
+                at("textDocument/definition", scene, at_, shift),
+            ]);
+            replies.pop().unwrap().get("result").to_string()
+        };
+
+        assert_eq!(
+            definition("$camera", 3),
+            r#"{"uri":"file:///scenes/main.ababa","range":{"start":{"line":1,"character":4},"end":{"line":1,"character":10}}}"#
+        );
+        assert_eq!(
+            definition("$sphere", 0),
+            r#"{"uri":"file:///lib/shapes.ababa","range":{"start":{"line":0,"character":4},"end":{"line":0,"character":10}}}"#
+        );
+        assert_eq!(
+            definition("sky", 0),
+            r#"{"uri":"file:///scenes/sky%20part.ababa","range":{"start":{"line":0,"character":0},"end":{"line":0,"character":0}}}"#
+        );
+        assert!(definition("../lib", 0).contains("file:///lib/shapes.ababa"));
+        assert_eq!(definition("Camera", 0), "null");
+
+        let scene = "[for i in 0..2 { $i }]";
+        let (mut replies, _) = session(&[
+            open(URI, scene),
+            at("textDocument/definition", scene, "$i", 1),
+        ]);
+        let range = replies
+            .pop()
+            .unwrap()
+            .get("result")
+            .get("range")
+            .to_string();
+        assert_eq!(
+            range,
+            r#"{"start":{"line":0,"character":5},"end":{"line":0,"character":6}}"#
+        );
+    }
+
+    #[test]
+    fn formatting() {
+        let formatting = || {
+            request(
+                2,
+                "textDocument/formatting",
+                JsonValue::object([("textDocument", JsonValue::object([("uri", URI.into())]))]),
+            )
+        };
+
+        let edits = result("[1,2]", formatting());
+        let edit = &edits.as_array()[0];
+        assert_eq!(
+            edit.get("range").to_string(),
+            r#"{"start":{"line":0,"character":0},"end":{"line":0,"character":5}}"#
+        );
+        let formatted = edit.get("newText").as_str().unwrap();
+        assert_eq!(result(formatted, formatting()), JsonValue::Array(vec![]));
+
+        // only the syntax has to be valid
+        let edits = result("{a:param(\"a\"),b:include \"missing.ababa\"}", formatting());
+        assert_eq!(edits.as_array().len(), 1);
+
+        let (replies, _) = session(&[open(URI, "[1 2]"), formatting()]);
+        let code = replies[1].get("error").get("code");
+        assert_eq!(code, &JsonValue::Number(REQUEST_FAILED.into()));
+
+        // never opened
+        let (replies, _) = session(&[formatting()]);
+        let code = replies[0].get("error").get("code");
+        assert_eq!(code, &JsonValue::Number(INVALID_PARAMS.into()));
+    }
+
+    #[test]
+    fn positions() {
+        let text = "a\n\u{1F600}é b\nc";
+        let b = text.find('b').unwrap();
+        assert_eq!(position(text, b).to_string(), r#"{"line":1,"character":4}"#);
+        assert_eq!(offset(text, &position(text, b)), b);
+        assert_eq!(offset(text, &position(text, text.len())), text.len());
+
+        let at = |line: usize, character: usize| {
+            offset(
+                text,
+                &JsonValue::object([("line", line.into()), ("character", character.into())]),
+            )
+        };
+        assert_eq!(at(0, 10), 1);
+        assert_eq!(at(5, 0), text.len());
+
+        let path = Path::new("/scenes/a b%.ababa");
+        assert_eq!(path_to_uri(path), "file:///scenes/a%20b%25.ababa");
+        assert_eq!(uri_to_path(&path_to_uri(path)).as_deref(), Some(path));
+        assert_eq!(uri_to_path("untitled:1"), None);
+    }
+}
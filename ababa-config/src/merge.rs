@@ -0,0 +1,187 @@
+//! merging of values, used to layer configs on top of each other (see [crate::AbabaLoader])
+
+use crate::{AbabaValue, AbabaValueKind, Span};
+
+impl AbabaValue {
+    /// merges `other` on top of this value:
+    ///
+    /// - objects are merged field by field, if `other` has no type or the same one as this value
+    /// - `..` in a list of `other` is replaced with the items of this list, so `[.., 4]` appends
+    ///   to it and `[0, ..]` prepends
+    /// - any other value of `other` replaces this one
+    ///
+    /// a `..` that isn't merged onto a list stands for no items at all.
+    ///
+    /// ```
+    /// use ababa_config::AbabaParser;
+    ///
+    /// let base = AbabaParser::new("Camera { position: (0, 0, 0), width: 64 }").parse().unwrap();
+    /// let shot = AbabaParser::new("{ width: 128, lights: [.., 1] }").parse().unwrap();
+    /// let merged = AbabaParser::new("Camera { position: (0, 0, 0), width: 128, lights: [1] }");
+    /// assert_eq!(base.merge(shot), merged.parse().unwrap());
+    /// ```
+    pub fn merge(self, other: AbabaValue) -> AbabaValue {
+        use AbabaValueKind::*;
+
+        let kind = match (self.kind, other.kind) {
+            (
+                Object {
+                    struct_type,
+                    mut fields,
+                },
+                Object {
+                    struct_type: other_type,
+                    fields: other_fields,
+                },
+            ) if other_type.is_none() || other_type == struct_type => {
+                for (name, value) in other_fields {
+                    let value = match fields.remove(&name) {
+                        Some(base) => base.merge(value),
+                        None => value.without_spreads(),
+                    };
+                    fields.insert(name, value);
+                }
+                Object {
+                    struct_type,
+                    fields,
+                }
+            }
+            (List(base), List(items)) => List(
+                items
+                    .into_iter()
+                    .flat_map(|item| match item.kind {
+                        Spread => base.clone(),
+                        _ => vec![item.without_spreads()],
+                    })
+                    .collect(),
+            ),
+            (_, kind) => {
+                let value = AbabaValue {
+                    kind,
+                    span: other.span,
+                    unit: other.unit,
+                };
+                return value.without_spreads();
+            }
+        };
+
+        AbabaValue::new(kind, other.span)
+    }
+
+    /// the value with all `..` removed, as if it was merged onto nothing
+    pub(crate) fn without_spreads(self) -> AbabaValue {
+        let kind = match self.kind {
+            AbabaValueKind::Object {
+                struct_type,
+                fields,
+            } => AbabaValueKind::Object {
+                struct_type,
+                fields: fields
+                    .into_iter()
+                    .map(|(name, value)| (name, value.without_spreads()))
+                    .collect(),
+            },
+            AbabaValueKind::List(items) => AbabaValueKind::List(
+                items
+                    .into_iter()
+                    .filter(|item| item.kind != AbabaValueKind::Spread)
+                    .map(AbabaValue::without_spreads)
+                    .collect(),
+            ),
+            AbabaValueKind::Tuple { struct_type, items } => AbabaValueKind::Tuple {
+                struct_type,
+                items: items.into_iter().map(AbabaValue::without_spreads).collect(),
+            },
+            kind => kind,
+        };
+
+        AbabaValue { kind, ..self }
+    }
+}
+
+/// `value` with `span` for itself and all nested values
+pub(crate) fn respan(mut value: AbabaValue, span: Span) -> AbabaValue {
+    for_each_span(&mut value, &mut |s| *s = span);
+    value
+}
+
+/// moves the spans of `value` and all nested values `offset` bytes further
+pub(crate) fn shift_spans(value: &mut AbabaValue, offset: usize) {
+    for_each_span(value, &mut |span| {
+        *span = Span::new(span.start + offset, span.end + offset)
+    });
+}
+
+fn for_each_span(value: &mut AbabaValue, f: &mut impl FnMut(&mut Span)) {
+    f(&mut value.span);
+
+    match &mut value.kind {
+        AbabaValueKind::Object { fields, .. } => fields
+            .values_mut()
+            .for_each(|value| for_each_span(value, f)),
+        AbabaValueKind::List(items) | AbabaValueKind::Tuple { items, .. } => {
+            items.iter_mut().for_each(|value| for_each_span(value, f))
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AbabaParser, AbabaValue};
+
+    fn parse(s: &str) -> AbabaValue {
+        AbabaParser::new(s).parse().unwrap()
+    }
+
+    fn merge(base: &str, other: &str) -> AbabaValue {
+        parse(base).merge(parse(other))
+    }
+
+    #[test]
+    fn objects() {
+        assert_eq!(
+            merge(
+                "Scene { camera: { position: (0, 0, 0), width: 64 }, name: \"a\" }",
+                "{ camera: { width: 128 }, name: \"b\", extra: true }"
+            ),
+            parse(
+                "Scene { camera: { position: (0, 0, 0), width: 128 }, name: \"b\", extra: true }"
+            )
+        );
+
+        // the type of the base is kept, other types replace the whole object
+        assert_eq!(
+            merge("Sphere { radius: 1, position: 0 }", "Sphere { radius: 2 }"),
+            parse("Sphere { radius: 2, position: 0 }")
+        );
+        assert_eq!(
+            merge("Sphere { radius: 1, position: 0 }", "Cube { size: 2 }"),
+            parse("Cube { size: 2 }")
+        );
+        assert_eq!(merge("{ a: { b: 1 } }", "{ a: 2 }"), parse("{ a: 2 }"));
+        assert_eq!(merge("{ a: 1 }", "[1]"), parse("[1]"));
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(merge("[1, 2]", "[3]"), parse("[3]"));
+        assert_eq!(merge("[1, 2]", "[.., 3]"), parse("[1, 2, 3]"));
+        assert_eq!(merge("[1, 2]", "[0, ..]"), parse("[0, 1, 2]"));
+        assert_eq!(merge("[1, 2]", "[..]"), parse("[1, 2]"));
+        assert_eq!(merge("[1]", "[.., ..]"), parse("[1, 1]"));
+
+        // lists are not merged item by item
+        assert_eq!(merge("[{ a: 1, b: 1 }]", "[{ a: 2 }]"), parse("[{ a: 2 }]"));
+        assert_eq!(
+            merge("{ a: [1], b: 2 }", "{ a: [.., 2] }"),
+            parse("{ a: [1, 2], b: 2 }")
+        );
+
+        // nothing to merge onto
+        assert_eq!(merge("{}", "{ a: [.., 1] }"), parse("{ a: [1] }"));
+        assert_eq!(merge("(1, 2)", "[.., 3]"), parse("[3]"));
+        assert_eq!(merge("1", "[[..], ..]"), parse("[[]]"));
+        assert_eq!(merge("[[1]]", "[.., [..]]"), parse("[[1], []]"));
+    }
+}
@@ -1,21 +1,2562 @@
 //! top-down parser using recursive descent
+//!
+//! every non-terminal of the grammar (see the crate docs) has its own `parse_*` method.
+//! since the grammar is LL(1), a single token of lookahead (peeking) is always enough to decide
+//! which rule to continue with.
 
-use crate::tokenizer::AbabaTokenizer;
-use crate::{AbabaParseError, AbabaValue};
+use crate::borrowed::respan;
+use crate::expr::{self, Operator};
+use crate::include::relative_to;
+use crate::tokenizer::{AbabaToken, AbabaTokenizer};
+use crate::unit::{self, split_suffix};
+use crate::{
+    AbabaFileResolver, AbabaParseError, AbabaResolver, AbabaValue, AbabaValueRef,
+    AbabaValueRefKind, Span,
+};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+
+/// how often the loops of a file can run in total
+const MAX_ITERATIONS: usize = 100_000;
+
+/// how many values the references of a file can copy in total, so that references to references
+/// can't grow the value exponentially
+const MAX_COPIES: usize = 1_000_000;
+
+/// how deep values can be nested in each other (including negations), deeper nesting is an error
+/// instead of a stack overflow
+pub(crate) const MAX_DEPTH: usize = 128;
+
+/// objects with more fields than this look up the names of their fields in a set
+const INDEXED_FIELDS: usize = 16;
+
+/// names that look up a value from outside of the input when followed by `(`
+pub(crate) const LOOKUPS: [&str; 2] = ["env", "param"];
 
 pub struct AbabaParser<'a> {
-    _tokenizer: Peekable<AbabaTokenizer<'a>>,
+    tokenizer: Peekable<AbabaTokenizer<'a>>,
+    len: usize,
+    /// values of all names defined so far
+    bindings: HashMap<String, AbabaValueRef<'a>>,
+    /// names whose value is currently being parsed
+    defining: Vec<String>,
+    resolver: &'a dyn AbabaResolver,
+    /// the files that are currently being parsed, the last one is the input itself
+    files: Vec<PathBuf>,
+    /// errors that were recovered from
+    errors: Vec<AbabaParseError>,
+    /// whether `inf` and `nan` are numbers
+    non_finite: bool,
+    /// how often loops have run so far
+    iterations: usize,
+    /// how many values references have copied so far
+    copies: usize,
+    /// how deep the value that is currently being parsed is nested, included files continue the
+    /// count of the including one
+    depth: usize,
+    /// values of `param("name")`
+    params: Option<&'a HashMap<String, AbabaValue>>,
 }
 
 impl<'a> AbabaParser<'a> {
     pub fn new(s: &'a str) -> Self {
         AbabaParser {
-            _tokenizer: AbabaTokenizer::new(s).peekable(),
+            tokenizer: AbabaTokenizer::new(s).peekable(),
+            len: s.len(),
+            bindings: HashMap::new(),
+            defining: Vec::new(),
+            resolver: &AbabaFileResolver,
+            files: Vec::new(),
+            errors: Vec::new(),
+            non_finite: false,
+            iterations: 0,
+            copies: 0,
+            depth: 0,
+            params: None,
         }
     }
 
+    /// path of the input, included files are relative to it.
+    /// without a path, they're relative to the current directory.
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.files = vec![path.into()];
+        self
+    }
+
+    /// reads included files with `resolver` instead of from the file system
+    pub fn with_resolver(mut self, resolver: &'a dyn AbabaResolver) -> Self {
+        self.resolver = resolver;
+        self
+    }
+
+    /// the values of `param("name")` in the input and all the files it includes. without them,
+    /// every `param` is its default.
+    pub fn with_params(mut self, params: &'a HashMap<String, AbabaValue>) -> Self {
+        self.params = Some(params);
+        self
+    }
+
+    /// parses `inf` and `nan` as numbers (infinity and not a number) instead of idents and
+    /// allows numbers and results that aren't finite (like `1e400` or `1e308 * 10`), in the input
+    /// and all the files it includes
+    pub fn allow_non_finite(mut self) -> Self {
+        self.non_finite = true;
+        self
+    }
+
+    /// parses the whole input into a single [AbabaValue], with all references and includes
+    /// resolved.
+    ///
+    /// the input has to consist of exactly one value (after the bindings), anything after it is
+    /// an error. only the first error is returned, see [AbabaParser::parse_recovering] for all of
+    /// them.
     pub fn parse(self) -> Result<AbabaValue, AbabaParseError> {
-        todo!()
+        self.parse_borrowed().map(AbabaValue::from)
+    }
+
+    /// parses the whole input like [AbabaParser::parse], into an [AbabaValueRef] that borrows its
+    /// strings from the input and keeps the order of the fields
+    pub fn parse_borrowed(self) -> Result<AbabaValueRef<'a>, AbabaParseError> {
+        match self.recover() {
+            (Some(value), errors) if errors.is_empty() => Ok(value),
+            (_, errors) => Err(errors
+                .into_iter()
+                .next()
+                .expect("there's a value if there are no errors")),
+        }
+    }
+
+    /// parses the whole input like [AbabaParser::parse], but doesn't stop at the first error.
+    ///
+    /// after an error the parser skips ahead to the next `,`, closing brace or `;` and continues
+    /// from there. values with errors are left out of the lists and objects containing them, so
+    /// the returned value is the part of the input that could be parsed. it's `None` if not even
+    /// the outermost value could be parsed. the errors are in the order they were found in, the
+    /// first one is the error [AbabaParser::parse] returns.
+    ///
+    /// ```
+    /// use ababa_config::AbabaParser;
+    ///
+    /// let (value, errors) = AbabaParser::new("{ a: 1 +, b: [2, 3 4], c: 5 }").parse_recovering();
+    /// assert_eq!(value, AbabaParser::new("{ b: [2, 3], c: 5 }").parse().ok());
+    /// assert_eq!(errors.len(), 2);
+    /// ```
+    pub fn parse_recovering(self) -> (Option<AbabaValue>, Vec<AbabaParseError>) {
+        let (value, errors) = self.recover();
+        (value.map(AbabaValue::from), errors)
+    }
+
+    // the value that could be parsed and all errors, see `parse_recovering`
+    fn recover(mut self) -> (Option<AbabaValueRef<'a>>, Vec<AbabaParseError>) {
+        if self.tokenizer.peek().is_none() {
+            return (None, vec![AbabaParseError::NoContent]);
+        }
+
+        self.parse_header();
+        let value = match self.parse_value() {
+            Ok(value) => {
+                if let Some(tok) = self.tokenizer.next() {
+                    let err = self.unexpected("end of input", Some(tok));
+                    self.errors.push(err);
+                }
+                Some(value)
+            }
+            Err(err) => {
+                self.errors.push(err);
+                None
+            }
+        };
+
+        (value, self.errors)
+    }
+
+    // the bindings of an imported file. the value is optional, it's only parsed for errors.
+    fn parse_imported(mut self) -> (HashMap<String, AbabaValueRef<'a>>, Vec<AbabaParseError>) {
+        self.parse_header();
+
+        if self.tokenizer.peek().is_some() {
+            let err = match self.parse_value() {
+                Ok(_) => self
+                    .tokenizer
+                    .next()
+                    .map(|tok| self.unexpected("end of input", Some(tok))),
+                Err(err) => Some(err),
+            };
+            self.errors.extend(err);
+        }
+
+        (self.bindings, self.errors)
+    }
+
+    // document ::= header* value
+    // header   ::= binding | import
+    //
+    // a broken header is skipped up to its `;`
+    fn parse_header(&mut self) {
+        loop {
+            let result = match self.tokenizer.peek() {
+                Some((AbabaToken::Let, _)) => {
+                    self.tokenizer.next();
+                    self.parse_binding()
+                }
+                Some((AbabaToken::Import, _)) => {
+                    self.tokenizer.next();
+                    self.parse_import()
+                }
+                _ => return,
+            };
+
+            if let Err(err) = result {
+                self.errors.push(err);
+                self.skip_header();
+            }
+        }
+    }
+
+    // binding ::= 'let' ident '=' value ';'
+    //
+    // `let` has already been consumed
+    fn parse_binding(&mut self) -> Result<(), AbabaParseError> {
+        let (name, span) = match self.tokenizer.peek().copied() {
+            Some((AbabaToken::Ident(name), span)) => {
+                self.tokenizer.next();
+                (name, span)
+            }
+            _ => return Err(self.unexpected_next("name")),
+        };
+
+        self.expect(AbabaToken::Equals, "'='")?;
+        self.parse_definition(name, span)?;
+        self.expect(AbabaToken::Semicolon, "';'")
+    }
+
+    // import ::= 'import' string ';'
+    //
+    // `import` has already been consumed. the bindings of the file become bindings of this one.
+    fn parse_import(&mut self) -> Result<(), AbabaParseError> {
+        let (file, source, span) = self.parse_include_path()?;
+        let (bindings, errors) = self.parser_for(&file, &source).parse_imported();
+        self.errors.extend(
+            errors
+                .into_iter()
+                .map(|err| included(file.clone(), source.clone(), span, err)),
+        );
+
+        // sorted, so that the error doesn't depend on the order of the map
+        let mut bindings: Vec<_> = bindings.into_iter().collect();
+        bindings.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (name, value) in bindings {
+            if self.bindings.contains_key(&name) {
+                return Err(AbabaParseError::DuplicateName { name, span });
+            }
+            // the spans of imported values point into the other file
+            self.bindings
+                .insert(name, respan(value.into_static(), span));
+        }
+
+        self.expect(AbabaToken::Semicolon, "';'")
+    }
+
+    // include ::= 'include' string
+    //
+    // `include` has already been consumed. the bindings of the file stay in the file.
+    fn parse_include(&mut self, start: Span) -> Result<AbabaValueRef<'a>, AbabaParseError> {
+        let (file, source, span) = self.parse_include_path()?;
+        let span = start.to(span);
+        let (value, errors) = self.parser_for(&file, &source).recover();
+        let mut errors: Vec<_> = errors
+            .into_iter()
+            .map(|err| included(file.clone(), source.clone(), span, err))
+            .collect();
+
+        match value {
+            // the spans of included values point into the other file
+            Some(value) => {
+                self.errors.append(&mut errors);
+                Ok(respan(value.into_static(), span))
+            }
+            // the last error is the one that stopped the value, it's reported by the caller
+            None => {
+                let last = errors
+                    .pop()
+                    .expect("there's a value if there are no errors");
+                self.errors.append(&mut errors);
+                Err(last)
+            }
+        }
+    }
+
+    // the path after `include` or `import`, relative to the current file, and the content of the
+    // file it refers to
+    fn parse_include_path(&mut self) -> Result<(PathBuf, String, Span), AbabaParseError> {
+        let (path, span) = match self.tokenizer.next() {
+            Some((AbabaToken::Str(path), span)) => (unescape(path, span)?, span),
+            tok => return Err(self.unexpected("path", tok)),
+        };
+
+        let file = relative_to(self.files.last().map(PathBuf::as_path), &path);
+        if let Some(i) = self.files.iter().position(|f| *f == file) {
+            let mut chain = self.files[i..].to_vec();
+            chain.push(file);
+            return Err(AbabaParseError::IncludeCycle { chain, span });
+        }
+
+        match self.resolver.read(&file) {
+            Ok(source) => Ok((file, source, span)),
+            Err(error) => Err(AbabaParseError::IncludeFailed { file, error, span }),
+        }
+    }
+
+    // parser for the included `file` with the content `source`
+    fn parser_for<'s>(&self, file: &Path, source: &'s str) -> AbabaParser<'s>
+    where
+        'a: 's,
+    {
+        let mut parser = self.nested(source);
+        parser.files.push(file.to_path_buf());
+        parser
+    }
+
+    // parser for `source` that is part of the current file, with the same resolver, params and
+    // limits
+    fn nested<'s>(&self, source: &'s str) -> AbabaParser<'s>
+    where
+        'a: 's,
+    {
+        let mut parser = AbabaParser::new(source).with_resolver(self.resolver);
+        parser.non_finite = self.non_finite;
+        parser.params = self.params;
+        parser.depth = self.depth;
+        parser.files = self.files.clone();
+        parser
+    }
+
+    // parses the value of the name `name` (defined at `span`) and remembers it
+    fn parse_definition(
+        &mut self,
+        name: &str,
+        span: Span,
+    ) -> Result<AbabaValueRef<'a>, AbabaParseError> {
+        if self.bindings.contains_key(name) || self.defining.iter().any(|n| n == name) {
+            return Err(AbabaParseError::DuplicateName {
+                name: name.to_string(),
+                span,
+            });
+        }
+
+        self.defining.push(name.to_string());
+        let value = self.parse_value();
+        self.defining.pop();
+
+        let value = value?;
+        self.bindings.insert(name.to_string(), value.clone());
+        Ok(value)
+    }
+
+    // a copy of the value of `name`, with the span of the reference
+    fn resolve(&mut self, name: &str, span: Span) -> Result<AbabaValueRef<'a>, AbabaParseError> {
+        if self.defining.iter().any(|n| n == name) {
+            return Err(AbabaParseError::CyclicReference {
+                name: name.to_string(),
+                span,
+            });
+        }
+
+        match self.bindings.get(name) {
+            Some(value) => {
+                let copies = count_values(value);
+                if copies > MAX_COPIES - self.copies {
+                    return Err(AbabaParseError::TooManyCopies {
+                        limit: MAX_COPIES,
+                        span,
+                    });
+                }
+                self.copies += copies;
+
+                Ok(AbabaValueRef {
+                    span,
+                    ..value.clone()
+                })
+            }
+            None => Err(AbabaParseError::UndefinedName {
+                name: name.to_string(),
+                span,
+            }),
+        }
+    }
+
+    // value    ::= product sum-cont
+    // sum-cont ::= ('+' | '-') product sum-cont | ε
+    //
+    // operators are evaluated right away, their operands have to be numbers
+    fn parse_value(&mut self) -> Result<AbabaValueRef<'a>, AbabaParseError> {
+        let mut value = self.parse_product()?;
+
+        loop {
+            let op = match self.tokenizer.peek() {
+                Some((AbabaToken::Plus, _)) => Operator::Add,
+                Some((AbabaToken::Minus, _)) => Operator::Sub,
+                _ => return Ok(value),
+            };
+            self.tokenizer.next();
+
+            let rhs = self.parse_product()?;
+            value = self.finite_value(apply(op, value, rhs)?)?;
+        }
+    }
+
+    // product      ::= unary product-cont
+    // product-cont ::= ('*' | '/') unary product-cont | ε
+    fn parse_product(&mut self) -> Result<AbabaValueRef<'a>, AbabaParseError> {
+        let mut value = self.parse_unary()?;
+
+        loop {
+            let op = match self.tokenizer.peek() {
+                Some((AbabaToken::Star, _)) => Operator::Mul,
+                Some((AbabaToken::Slash, _)) => Operator::Div,
+                _ => return Ok(value),
+            };
+            self.tokenizer.next();
+
+            let rhs = self.parse_unary()?;
+            value = self.finite_value(apply(op, value, rhs)?)?;
+        }
+    }
+
+    // unary ::= '-' unary | atom
+    //
+    // every nested value and negation goes through here, so this is where the nesting is limited
+    // before it could overflow the stack
+    fn parse_unary(&mut self) -> Result<AbabaValueRef<'a>, AbabaParseError> {
+        if self.depth == MAX_DEPTH {
+            return Err(AbabaParseError::TooDeep {
+                limit: MAX_DEPTH,
+                span: self.next_span(),
+            });
+        }
+
+        self.depth += 1;
+        let value = match self.tokenizer.peek() {
+            Some((AbabaToken::Minus, minus)) => {
+                let minus = *minus;
+                self.tokenizer.next();
+
+                self.parse_unary().and_then(|value| {
+                    let (span, unit) = (minus.to(value.span), value.unit);
+                    let x = number_operand(value)?;
+                    Ok(AbabaValueRef {
+                        unit,
+                        ..AbabaValueRef::new(AbabaValueRefKind::Number(-x), span)
+                    })
+                })
+            }
+            _ => self.parse_atom(),
+        };
+        self.depth -= 1;
+        value
+    }
+
+    // atom       ::= list | tuple | object | typed | call | constant | number | string | bool
+    //              | reference | anchored | include
+    // reference  ::= '$' ident
+    // anchored   ::= '&' ident value
+    // typed      ::= ident typed-cont
+    // typed-cont ::= tuple | object | ε
+    // call       ::= function '(' items ')'
+    //
+    // a lone ident that is the name of a constant is the constant, an ident followed by a tuple is
+    // a function call if it's the name of a function. otherwise it's a typed tuple, even if it was
+    // probably meant as a call, see `number_operand`.
+    fn parse_atom(&mut self) -> Result<AbabaValueRef<'a>, AbabaParseError> {
+        if self.tokenizer.peek().is_none_or(|(tok, _)| is_sync(*tok)) {
+            return Err(self.unexpected_next("value"));
+        }
+
+        match self.tokenizer.next() {
+            Some((AbabaToken::LeftBracket, start)) => {
+                let (items, end, _) = self.parse_items(AbabaToken::RightBracket, ']', start);
+                Ok(AbabaValueRef::new(
+                    AbabaValueRefKind::List(items),
+                    start.to(end),
+                ))
+            }
+            Some((AbabaToken::LeftParen, start)) => Ok(self.parse_tuple(None, start, start)),
+            Some((AbabaToken::LeftBrace, start)) => Ok(self.parse_fields(None, start, start)),
+            Some((AbabaToken::Ident(ident), start)) => match self.tokenizer.peek() {
+                Some((AbabaToken::LeftBrace, brace)) => {
+                    let brace = *brace;
+                    self.tokenizer.next();
+                    Ok(self.parse_fields(Some(Cow::Borrowed(ident)), start, brace))
+                }
+                Some((AbabaToken::LeftParen, paren)) => {
+                    let paren = *paren;
+                    self.tokenizer.next();
+                    if let Some(lookup) = LOOKUPS.iter().find(|l| **l == ident) {
+                        return self.parse_lookup(lookup, start, paren);
+                    }
+                    match expr::function(ident) {
+                        Some(function) => self.parse_call(function, start, paren),
+                        None => Ok(self.parse_tuple(Some(Cow::Borrowed(ident)), start, paren)),
+                    }
+                }
+                _ => match expr::constant(ident, self.non_finite) {
+                    Some(x) => Ok(AbabaValueRef::new(AbabaValueRefKind::Number(x), start)),
+                    None => Ok(AbabaValueRef::new(
+                        AbabaValueRefKind::Ident(Cow::Borrowed(ident)),
+                        start,
+                    )),
+                },
+            },
+            Some((AbabaToken::Number(s), span)) => self.finite_value(parse_number(s, span)?),
+            Some((AbabaToken::Color(s), span)) => Ok(parse_color(s, span)),
+            Some((AbabaToken::Str(s), span)) => Ok(AbabaValueRef::new(
+                AbabaValueRefKind::String(unescape(s, span)?),
+                span,
+            )),
+            Some((AbabaToken::Bool(b), span)) => {
+                Ok(AbabaValueRef::new(AbabaValueRefKind::Bool(b), span))
+            }
+            Some((AbabaToken::Reference(name), span)) => self.resolve(name, span),
+            Some((AbabaToken::Anchor(name), span)) => self.parse_definition(name, span),
+            Some((AbabaToken::Include, span)) => self.parse_include(span),
+            tok => Err(self.unexpected("value", tok)),
+        }
+    }
+
+    // tuple ::= '(' items ')'
+    //
+    // the opening paren has already been consumed.
+    // `start` is the span of the type (if present), `open` the one of the opening paren.
+    // a single value in untyped parens without a comma is just that value, like in `(1 + 2) * 3`.
+    fn parse_tuple(
+        &mut self,
+        struct_type: Option<Cow<'a, str>>,
+        start: Span,
+        open: Span,
+    ) -> AbabaValueRef<'a> {
+        let (mut items, end, trailing_comma) = self.parse_items(AbabaToken::RightParen, ')', open);
+
+        if struct_type.is_none() && items.len() == 1 && !trailing_comma {
+            let value = items.pop().expect("checked length");
+            return AbabaValueRef {
+                span: start.to(end),
+                ..value
+            };
+        }
+
+        AbabaValueRef::new(
+            AbabaValueRefKind::Tuple { struct_type, items },
+            start.to(end),
+        )
+    }
+
+    // call ::= function '(' items ')'
+    //
+    // the opening paren has already been consumed, `start` is the span of the function name
+    fn parse_call(
+        &mut self,
+        function: &'static expr::Function,
+        start: Span,
+        open: Span,
+    ) -> Result<AbabaValueRef<'a>, AbabaParseError> {
+        let (items, end, _) = self.parse_items(AbabaToken::RightParen, ')', open);
+        let span = start.to(end);
+
+        if items.len() != function.arity {
+            return Err(AbabaParseError::WrongArgumentCount {
+                function: function.name,
+                expected: function.arity,
+                got: items.len(),
+                span,
+            });
+        }
+
+        let args = items
+            .into_iter()
+            .map(number_operand)
+            .collect::<Result<Vec<_>, _>>()?;
+        let x = self.finite((function.eval)(&args), span)?;
+        Ok(AbabaValueRef::new(AbabaValueRefKind::Number(x), span))
+    }
+
+    // `x`, unless it isn't finite and that's not allowed
+    fn finite(&self, x: f64, span: Span) -> Result<f64, AbabaParseError> {
+        match x.is_finite() || self.non_finite {
+            true => Ok(x),
+            false => Err(AbabaParseError::NotFinite { span }),
+        }
+    }
+
+    // the number literal or result of an operator, unless it isn't finite and that's not allowed
+    fn finite_value(&self, value: AbabaValueRef<'a>) -> Result<AbabaValueRef<'a>, AbabaParseError> {
+        if let AbabaValueRefKind::Number(x) = value.kind {
+            self.finite(x, value.span)?;
+        }
+        Ok(value)
+    }
+
+    // lookup ::= ('env' | 'param') '(' items ')'
+    //
+    // `env("NAME", default)` or `param("name", default)`, the default is optional. the opening
+    // paren has already been consumed, `start` is the span of `env` or `param`. a value found
+    // outside of the input gets the span of the whole lookup.
+    fn parse_lookup(
+        &mut self,
+        function: &'static str,
+        start: Span,
+        open: Span,
+    ) -> Result<AbabaValueRef<'a>, AbabaParseError> {
+        let (items, end, _) = self.parse_items(AbabaToken::RightParen, ')', open);
+        let span = start.to(end);
+
+        if !(1..=2).contains(&items.len()) {
+            return Err(AbabaParseError::WrongArgumentCount {
+                function,
+                expected: 2,
+                got: items.len(),
+                span,
+            });
+        }
+
+        let mut items = items.into_iter();
+        let name = match items.next().expect("checked length") {
+            AbabaValueRef {
+                kind: AbabaValueRefKind::String(name),
+                ..
+            } => name.into_owned(),
+            got => {
+                return Err(AbabaParseError::ValueTypeDidNotMatch {
+                    expected: "String",
+                    got: got.into(),
+                })
+            }
+        };
+        let default = items.next();
+
+        let found = match function {
+            "env" => match self.resolver.env(&name) {
+                Some(text) => Some(self.parse_env(&name, text, default.as_ref(), span)?),
+                None => None,
+            },
+            _ => self
+                .params
+                .and_then(|params| params.get(&name))
+                .map(|value| AbabaValueRef::from(value.clone())),
+        };
+
+        match (found, default) {
+            (Some(value), _) => Ok(respan(value, span)),
+            (None, Some(default)) => Ok(default),
+            (None, None) => Err(AbabaParseError::UnsetParameter {
+                function,
+                name,
+                span,
+            }),
+        }
+    }
+
+    // the value of the environment variable `name`. it's taken as a string if the default is one,
+    // and parsed as a value otherwise, like it was written in place of the lookup.
+    fn parse_env(
+        &self,
+        name: &str,
+        text: String,
+        default: Option<&AbabaValueRef<'a>>,
+        span: Span,
+    ) -> Result<AbabaValueRef<'a>, AbabaParseError> {
+        if let Some(AbabaValueRef {
+            kind: AbabaValueRefKind::String(_),
+            ..
+        }) = default
+        {
+            return Ok(AbabaValueRef::new(
+                AbabaValueRefKind::String(Cow::Owned(text)),
+                span,
+            ));
+        }
+
+        self.nested(&text)
+            .parse_borrowed()
+            .map(AbabaValueRef::into_static)
+            .map_err(|err| AbabaParseError::InvalidEnvironment {
+                name: name.to_string(),
+                message: err.to_string(),
+                span,
+            })
+    }
+
+    // items      ::= item items-cont | ε
+    // items-cont ::= ',' items | ε
+    // item       ::= value | '..' | loop
+    //
+    // `..` is only an item of lists, loops are items of lists and loop bodies. the opening brace
+    // has already been consumed, the closing one is consumed here. returns the items, the span of
+    // the closing brace and whether there was a trailing comma.
+    fn parse_items(
+        &mut self,
+        end: AbabaToken,
+        brace: char,
+        open: Span,
+    ) -> (Vec<AbabaValueRef<'a>>, Span, bool) {
+        let mut items = Vec::new();
+
+        loop {
+            let failed = match self.tokenizer.peek() {
+                Some((tok, span)) if *tok == end => {
+                    let span = *span;
+                    self.tokenizer.next();
+                    // the closing brace right after a comma
+                    let trailing_comma = !items.is_empty();
+                    return (items, span, trailing_comma);
+                }
+                // only lists can be merged
+                Some((AbabaToken::DotDot, span)) if end == AbabaToken::RightBracket => {
+                    items.push(AbabaValueRef::new(AbabaValueRefKind::Spread, *span));
+                    self.tokenizer.next();
+                    false
+                }
+                Some((AbabaToken::For | AbabaToken::Repeat, _))
+                    if end != AbabaToken::RightParen =>
+                {
+                    match self.parse_loop() {
+                        Ok(mut expanded) => {
+                            items.append(&mut expanded);
+                            false
+                        }
+                        Err(err) => {
+                            self.errors.push(err);
+                            true
+                        }
+                    }
+                }
+                // the missing brace is reported by `parse_separator`
+                None => false,
+                Some(_) => match self.parse_value() {
+                    Ok(value) => {
+                        items.push(value);
+                        false
+                    }
+                    Err(err) => {
+                        self.errors.push(err);
+                        true
+                    }
+                },
+            };
+
+            let separator = self.parse_separator(end, brace, open, "',' or closing brace", failed);
+            if let Some(span) = separator {
+                return (items, span, false);
+            }
+        }
+    }
+
+    // loop ::= 'for' ident 'in' value '..' value body | 'repeat' value body
+    // body ::= '{' items '}'
+    //
+    // the tokens of the body are read again for every iteration, with the name bound to the
+    // number of the iteration. returns the items of all iterations.
+    fn parse_loop(&mut self) -> Result<Vec<AbabaValueRef<'a>>, AbabaParseError> {
+        let (keyword, start) = self.tokenizer.next().expect("checked by the caller");
+        let (name, range, end) = match keyword {
+            AbabaToken::For => {
+                let name = match self.tokenizer.peek().copied() {
+                    Some((AbabaToken::Ident(name), span)) => {
+                        self.tokenizer.next();
+                        (name, span)
+                    }
+                    _ => return Err(self.unexpected_next("name")),
+                };
+                self.expect(AbabaToken::In, "`in`")?;
+                let (from, _) = self.parse_bound()?;
+                self.expect(AbabaToken::DotDot, "'..'")?;
+                let (to, end) = self.parse_bound()?;
+                (Some(name), from..to, end)
+            }
+            _ => {
+                let (count, end) = self.parse_bound()?;
+                // unlike an empty range, this can't be intended
+                if count < 0 {
+                    return Err(AbabaParseError::NumberOutOfBounds {
+                        x: count as f64,
+                        target_type: "a count",
+                        span: end,
+                    });
+                }
+                (None, 0..count, end)
+            }
+        };
+        let span = start.to(end);
+
+        if let Some((name, span)) = name {
+            if self.bindings.contains_key(name) || self.defining.iter().any(|n| n == name) {
+                return Err(AbabaParseError::DuplicateName {
+                    name: name.to_string(),
+                    span,
+                });
+            }
+        }
+
+        let count = usize::try_from(range.end.saturating_sub(range.start)).unwrap_or(0);
+        if count > MAX_ITERATIONS - self.iterations {
+            return Err(AbabaParseError::TooManyIterations {
+                limit: MAX_ITERATIONS,
+                span,
+            });
+        }
+
+        let open = match self.tokenizer.peek().copied() {
+            Some((AbabaToken::LeftBrace, open)) => open,
+            _ => return Err(self.unexpected_next("'{'")),
+        };
+        if count == 0 {
+            self.skip_token();
+            return Ok(Vec::new());
+        }
+        self.tokenizer.next();
+
+        let body = self.tokenizer.clone();
+        let outer: HashSet<String> = self.bindings.keys().cloned().collect();
+        let mut items = Vec::new();
+
+        for (n, i) in range.enumerate() {
+            if n > 0 {
+                self.tokenizer = body.clone();
+            }
+            if let Some((name, span)) = name {
+                let value = AbabaValueRef::new(AbabaValueRefKind::Number(i as f64), span);
+                self.bindings.insert(name.to_string(), value);
+            }
+            self.iterations += 1;
+
+            let errors = self.errors.len();
+            let (mut expanded, _, _) = self.parse_items(AbabaToken::RightBrace, '}', open);
+            items.append(&mut expanded);
+            self.bindings.retain(|name, _| outer.contains(name));
+
+            // the next iterations would most likely report the same errors again
+            if self.errors.len() > errors {
+                break;
+            }
+        }
+
+        Ok(items)
+    }
+
+    // a bound of a loop, which has to be an integer
+    fn parse_bound(&mut self) -> Result<(i64, Span), AbabaParseError> {
+        let value = self.parse_value()?;
+        let span = value.span;
+        let x = number_operand(value)?;
+
+        match x.fract() == 0. && x.abs() <= (1u64 << 53) as f64 {
+            true => Ok((x as i64, span)),
+            false => Err(AbabaParseError::NumberOutOfBounds {
+                x,
+                target_type: "an integer",
+                span,
+            }),
+        }
+    }
+
+    // object      ::= '{' fields '}'
+    // fields      ::= ident ':' value fields-cont | ε
+    // fields-cont ::= ',' fields | ε
+    //
+    // the opening brace has already been consumed, the closing one is consumed here.
+    // `start` is the span of the type (if present), `open` the one of the opening brace.
+    fn parse_fields(
+        &mut self,
+        struct_type: Option<Cow<'a, str>>,
+        start: Span,
+        open: Span,
+    ) -> AbabaValueRef<'a> {
+        let mut fields = Vec::new();
+        let mut index = HashSet::new();
+
+        let end = loop {
+            let failed = match self.tokenizer.peek().copied() {
+                Some((AbabaToken::RightBrace, span)) => {
+                    self.tokenizer.next();
+                    break span;
+                }
+                Some((AbabaToken::Ident(field), field_span)) => {
+                    self.tokenizer.next();
+                    let value = self
+                        .expect(AbabaToken::FieldSeparator, "':'")
+                        .and_then(|()| self.parse_value());
+
+                    match value {
+                        Ok(_) if is_duplicate(&fields, &mut index, field) => {
+                            self.errors.push(AbabaParseError::DuplicateField {
+                                field: field.to_string(),
+                                span: field_span,
+                            });
+                            false
+                        }
+                        Ok(value) => {
+                            fields.push((Cow::Borrowed(field), value));
+                            false
+                        }
+                        Err(err) => {
+                            self.errors.push(err);
+                            true
+                        }
+                    }
+                }
+                None => false,
+                Some(_) => {
+                    let err = self.unexpected_next("field name");
+                    self.errors.push(err);
+                    true
+                }
+            };
+
+            let separator =
+                self.parse_separator(AbabaToken::RightBrace, '}', open, "',' or '}'", failed);
+            if let Some(span) = separator {
+                break span;
+            }
+        };
+
+        AbabaValueRef::new(
+            AbabaValueRefKind::Object {
+                struct_type,
+                fields,
+            },
+            start.to(end),
+        )
+    }
+
+    // the separator after an item or field: consumes the `,` before the next one (`None`) or the
+    // closing `end` (its span).
+    //
+    // anything else is an error, unless the item itself `failed` already. the tokens up to the
+    // next `,` or `end` are skipped then. the closing brace of an outer value, a `;` or the end of
+    // the input end the items as well, but aren't consumed (the span is empty).
+    fn parse_separator(
+        &mut self,
+        end: AbabaToken,
+        brace: char,
+        open: Span,
+        expected: &'static str,
+        mut failed: bool,
+    ) -> Option<Span> {
+        loop {
+            match self.tokenizer.peek().copied() {
+                Some((AbabaToken::ListSeparator, _)) => {
+                    self.tokenizer.next();
+                    return None;
+                }
+                Some((tok, span)) if tok == end => {
+                    self.tokenizer.next();
+                    return Some(span);
+                }
+                None => {
+                    self.errors
+                        .push(AbabaParseError::MissingEndBrace { brace, span: open });
+                    return Some(Span::new(self.len, self.len));
+                }
+                Some((tok, span)) => {
+                    if !failed {
+                        let err = self.unexpected(expected, Some((tok, span)));
+                        self.errors.push(err);
+                        failed = true;
+                    }
+                    if is_sync(tok) {
+                        return Some(Span::new(span.start, span.start));
+                    }
+                    self.skip_token();
+                }
+            }
+        }
+    }
+
+    // consumes `token`
+    fn expect(&mut self, token: AbabaToken, expected: &'static str) -> Result<(), AbabaParseError> {
+        match self.tokenizer.peek() {
+            Some((tok, _)) if *tok == token => {
+                self.tokenizer.next();
+                Ok(())
+            }
+            _ => Err(self.unexpected_next(expected)),
+        }
+    }
+
+    // skips the rest of a broken binding or import, up to its `;` or the next header
+    fn skip_header(&mut self) {
+        loop {
+            match self.tokenizer.peek() {
+                None | Some((AbabaToken::Let | AbabaToken::Import, _)) => return,
+                Some((AbabaToken::Semicolon, _)) => {
+                    self.tokenizer.next();
+                    return;
+                }
+                Some(_) => self.skip_token(),
+            }
+        }
+    }
+
+    // skips the next token, or the whole list, tuple or object it opens
+    fn skip_token(&mut self) {
+        let mut depth = 0;
+
+        for (tok, _) in self.tokenizer.by_ref() {
+            match tok {
+                AbabaToken::LeftBrace | AbabaToken::LeftBracket | AbabaToken::LeftParen => {
+                    depth += 1
+                }
+                AbabaToken::RightBrace | AbabaToken::RightBracket | AbabaToken::RightParen => {
+                    depth -= 1
+                }
+                _ => {}
+            }
+            if depth <= 0 {
+                return;
+            }
+        }
+    }
+
+    // the span of the next token, or the empty span at the end of the input
+    fn next_span(&mut self) -> Span {
+        match self.tokenizer.peek() {
+            Some((_, span)) => *span,
+            None => Span::new(self.len, self.len),
+        }
+    }
+
+    // the error for the next token. it's only consumed if parsing can't continue at it after
+    // recovering, see `is_sync`.
+    fn unexpected_next(&mut self, expected: &'static str) -> AbabaParseError {
+        let tok = self.tokenizer.peek().copied();
+        if tok.is_some_and(|(tok, _)| !is_sync(tok)) {
+            self.tokenizer.next();
+        }
+        self.unexpected(expected, tok)
+    }
+
+    fn unexpected(
+        &self,
+        expected: &'static str,
+        got: Option<(AbabaToken, Span)>,
+    ) -> AbabaParseError {
+        unexpected(expected, got, self.len)
+    }
+}
+
+// the error for an unexpected token, or the end of input at `len`. tokens that only exist for
+// errors, like an unterminated string, get their own.
+pub(crate) fn unexpected(
+    expected: &'static str,
+    got: Option<(AbabaToken, Span)>,
+    len: usize,
+) -> AbabaParseError {
+    match got {
+        Some((AbabaToken::UnterminatedString, span)) => AbabaParseError::Unterminated {
+            what: "string",
+            span,
+        },
+        Some((AbabaToken::UnterminatedComment, span)) => AbabaParseError::Unterminated {
+            what: "block comment",
+            span,
+        },
+        Some((AbabaToken::InvalidNumber(s), span)) => AbabaParseError::InvalidNumber {
+            s: s.to_string(),
+            span,
+        },
+        Some((AbabaToken::InvalidColor(s), span)) => AbabaParseError::InvalidColor {
+            s: s.to_string(),
+            span,
+        },
+        Some((tok, span)) => AbabaParseError::UnexpectedToken {
+            expected,
+            got: tok.to_string(),
+            span,
+        },
+        None => AbabaParseError::UnexpectedToken {
+            expected,
+            got: "end of input".to_string(),
+            span: Span::new(len, len),
+        },
+    }
+}
+
+// the number of values in `value`, including itself
+fn count_values(value: &AbabaValueRef) -> usize {
+    let items = match &value.kind {
+        AbabaValueRefKind::List(items) | AbabaValueRefKind::Tuple { items, .. } => {
+            items.iter().map(count_values).sum()
+        }
+        AbabaValueRefKind::Object { fields, .. } => {
+            fields.iter().map(|(_, value)| count_values(value)).sum()
+        }
+        _ => 0,
+    };
+    1 + items
+}
+
+// tokens that parsing continues at after an error: separators, closing braces and headers
+fn is_sync(tok: AbabaToken) -> bool {
+    use AbabaToken::*;
+    matches!(
+        tok,
+        ListSeparator | Semicolon | RightBrace | RightBracket | RightParen | Let | Import
+    )
+}
+
+// whether the field `name` is one of `fields` already, remembering it in `index` otherwise. the
+// names of small objects are searched instead, which is faster than hashing them.
+fn is_duplicate<'a>(
+    fields: &[(Cow<'a, str>, AbabaValueRef<'a>)],
+    index: &mut HashSet<Cow<'a, str>>,
+    name: &'a str,
+) -> bool {
+    if fields.len() < INDEXED_FIELDS {
+        return fields.iter().any(|(field, _)| field == name);
+    }
+    if index.is_empty() {
+        index.extend(fields.iter().map(|(field, _)| field.clone()));
+    }
+    !index.insert(Cow::Borrowed(name))
+}
+
+// wraps an error that happened in an included file
+fn included(file: PathBuf, source: String, span: Span, err: AbabaParseError) -> AbabaParseError {
+    AbabaParseError::Included {
+        file,
+        source,
+        span: Some(span),
+        inner: Box::new(err),
+    }
+}
+
+// the number of an operand of an operator or function
+fn number_operand(value: AbabaValueRef<'_>) -> Result<f64, AbabaParseError> {
+    match value.kind {
+        AbabaValueRefKind::Number(x) => Ok(x),
+        // types start with an uppercase letter, so this was most likely meant as a function call
+        AbabaValueRefKind::Tuple {
+            struct_type: Some(name),
+            ..
+        } if name.starts_with(|c: char| c.is_ascii_lowercase()) => {
+            Err(AbabaParseError::UnknownFunction {
+                name: name.into_owned(),
+                span: value.span,
+            })
+        }
+        kind => Err(AbabaParseError::ValueTypeDidNotMatch {
+            expected: "Number",
+            got: AbabaValueRef::new(kind, value.span).into(),
+        }),
+    }
+}
+
+// `lhs op rhs`, the division by zero and unit errors point at `rhs`
+fn apply<'a>(
+    op: Operator,
+    lhs: AbabaValueRef<'a>,
+    rhs: AbabaValueRef<'a>,
+) -> Result<AbabaValueRef<'a>, AbabaParseError> {
+    let span = lhs.span.to(rhs.span);
+    let (rhs_span, units) = (rhs.span, (lhs.unit, rhs.unit));
+    let (lhs, rhs) = (number_operand(lhs)?, number_operand(rhs)?);
+
+    let unit = unit::combine(op, units.0, units.1, rhs_span)?;
+    let x = op
+        .apply(lhs, rhs)
+        .ok_or(AbabaParseError::DivisionByZero { span: rhs_span })?;
+    Ok(AbabaValueRef {
+        unit,
+        ..AbabaValueRef::new(AbabaValueRefKind::Number(x), span)
+    })
+}
+
+// the tokenizer only returns valid numbers, integers too large for a f64 are rounded. numbers
+// with a unit are converted to radians or meters.
+fn parse_number(s: &str, span: Span) -> Result<AbabaValueRef<'static>, AbabaParseError> {
+    let digits = match s.contains('_') {
+        true => Cow::Owned(s.replace('_', "")),
+        false => Cow::Borrowed(s),
+    };
+    let (x, unit) = match digits.get(..2) {
+        Some("0x" | "0X") => (from_radix(&digits[2..], 16), None),
+        Some("0b" | "0B") => (from_radix(&digits[2..], 2), None),
+        _ => match split_suffix(&digits) {
+            (number, Some((unit, factor))) => {
+                (number.parse().ok().map(|x: f64| x * factor), Some(unit))
+            }
+            (number, None) => (number.parse().ok(), None),
+        },
+    };
+
+    match x {
+        Some(x) => Ok(AbabaValueRef {
+            unit,
+            ..AbabaValueRef::new(AbabaValueRefKind::Number(x), span)
+        }),
+        None => Err(AbabaParseError::InvalidNumber {
+            s: s.to_string(),
+            span,
+        }),
+    }
+}
+
+// the tuple of the bytes of `#rrggbb(aa)`, each with the span of its two digits
+fn parse_color(s: &str, span: Span) -> AbabaValueRef<'static> {
+    let items = (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            let byte = u8::from_str_radix(&s[i..i + 2], 16).expect("checked by the tokenizer");
+            let start = span.start + 1 + i;
+            AbabaValueRef::new(
+                AbabaValueRefKind::Number(byte as f64),
+                Span::new(start, start + 2),
+            )
+        })
+        .collect();
+
+    AbabaValueRef::new(
+        AbabaValueRefKind::Tuple {
+            struct_type: None,
+            items,
+        },
+        span,
+    )
+}
+
+fn from_radix(digits: &str, radix: u32) -> Option<f64> {
+    digits.chars().try_fold(0., |x, c| {
+        Some(x * radix as f64 + c.to_digit(radix)? as f64)
+    })
+}
+
+// resolves the escape sequences of the string token `s` (without quotes) at `span`. strings
+// without escapes are borrowed.
+pub(crate) fn unescape(s: &str, span: Span) -> Result<Cow<'_, str>, AbabaParseError> {
+    if !s.contains('\\') {
+        return Ok(Cow::Borrowed(s));
+    }
+
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        let escaped = match chars.next() {
+            Some((_, '\\')) => Some('\\'),
+            Some((_, '"')) => Some('"'),
+            Some((_, 'n')) => Some('\n'),
+            Some((_, 'r')) => Some('\r'),
+            Some((_, 't')) => Some('\t'),
+            Some((_, '0')) => Some('\0'),
+            Some((_, 'u')) if chars.next_if(|(_, c)| *c == '{').is_some() => {
+                let mut code = String::new();
+                while let Some((_, c)) = chars.next_if(|(_, c)| c.is_ascii_hexdigit()) {
+                    code.push(c);
+                }
+                chars
+                    .next_if(|(_, c)| *c == '}')
+                    .and_then(|_| u32::from_str_radix(&code, 16).ok())
+                    .and_then(char::from_u32)
+            }
+            _ => None,
+        };
+
+        match escaped {
+            Some(c) => unescaped.push(c),
+            None => {
+                let end = chars.peek().map(|(i, _)| *i).unwrap_or(s.len());
+                // the string starts after the opening quote
+                let offset = span.start + 1;
+                return Err(AbabaParseError::InvalidEscape {
+                    escape: s[i..end].to_string(),
+                    span: Span::new(offset + i, offset + end),
+                });
+            }
+        }
+    }
+
+    Ok(Cow::Owned(unescaped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AbabaValueKind;
+
+    fn parse(s: &str) -> Result<AbabaValue, AbabaParseError> {
+        AbabaParser::new(s).parse()
+    }
+
+    fn files(files: &[(&str, &str)]) -> HashMap<PathBuf, String> {
+        files
+            .iter()
+            .map(|(path, source)| (PathBuf::from(path), source.to_string()))
+            .collect()
+    }
+
+    fn parse_file(
+        files: &HashMap<PathBuf, String>,
+        path: &str,
+    ) -> Result<AbabaValue, AbabaParseError> {
+        AbabaParser::new(&files[Path::new(path)])
+            .with_file(path)
+            .with_resolver(files)
+            .parse()
+    }
+
+    fn parse_recovering(s: &str) -> (Option<AbabaValue>, Vec<String>) {
+        let (value, errors) = AbabaParser::new(s).parse_recovering();
+        (value, errors.iter().map(ToString::to_string).collect())
+    }
+
+    #[allow(non_snake_case)]
+    fn Number(x: f64) -> AbabaValue {
+        AbabaValueKind::Number(x).into()
+    }
+
+    #[allow(non_snake_case)]
+    fn List(items: Vec<AbabaValue>) -> AbabaValue {
+        AbabaValueKind::List(items).into()
+    }
+
+    #[allow(non_snake_case)]
+    fn Tuple(items: Vec<AbabaValue>) -> AbabaValue {
+        AbabaValueKind::Tuple {
+            struct_type: None,
+            items,
+        }
+        .into()
+    }
+
+    fn object(struct_type: Option<&str>, fields: Vec<(&str, AbabaValue)>) -> AbabaValue {
+        AbabaValueKind::Object {
+            struct_type: struct_type.map(str::to_string),
+            fields: fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(parse("5").unwrap(), Number(5.));
+        assert_eq!(parse("-12.5").unwrap(), Number(-12.5));
+        assert_eq!(parse("10.").unwrap(), Number(10.));
+        assert_eq!(parse("1_000_000").unwrap(), Number(1e6));
+        assert_eq!(parse("2.5e-3").unwrap(), Number(0.0025));
+        assert_eq!(parse("1E3").unwrap(), Number(1000.));
+        assert_eq!(parse("0xff").unwrap(), Number(255.));
+        assert_eq!(parse("-0x_10").unwrap(), Number(-16.));
+        assert_eq!(parse("0b1010").unwrap(), Number(10.));
+        assert_eq!(
+            parse("0xffff_ffff_ffff_ffff").unwrap(),
+            Number(u64::MAX as f64)
+        );
+        assert!(matches!(
+            parse("1.2.3"),
+            Err(AbabaParseError::InvalidNumber { s, .. }) if s == "1.2.3"
+        ));
+        assert!(matches!(
+            parse("[1, 2e, 3]"),
+            Err(AbabaParseError::InvalidNumber { s, span }) if s == "2e" && span == Span::new(4, 6)
+        ));
+        assert!(matches!(
+            parse("{ a: 0b12 }"),
+            Err(AbabaParseError::InvalidNumber { s, span }) if s == "0b12" && span == Span::new(5, 9)
+        ));
+        assert!(matches!(
+            parse("-"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "value",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn non_finite() {
+        assert_eq!(
+            parse("[inf, nan]").unwrap(),
+            List(vec![
+                AbabaValueKind::Ident("inf".to_string()).into(),
+                AbabaValueKind::Ident("nan".to_string()).into(),
+            ])
+        );
+
+        for (s, span) in [
+            ("1e400", Span::new(0, 5)),
+            ("[0x1_0000_0000, 1e308 * 10]", Span::new(16, 26)),
+            ("-1e308 - 1e308", Span::new(0, 14)),
+            ("1e200 * 1e200 / 1e200", Span::new(0, 13)),
+        ] {
+            assert!(
+                matches!(parse(s), Err(AbabaParseError::NotFinite { span: s }) if s == span),
+                "{s}"
+            );
+        }
+
+        let parse = |s| AbabaParser::new(s).allow_non_finite().parse().unwrap();
+        assert_eq!(parse("1e400"), Number(f64::INFINITY));
+        assert_eq!(parse("1e308 * 10"), Number(f64::INFINITY));
+        assert_eq!(parse("inf"), Number(f64::INFINITY));
+        assert_eq!(parse("-inf"), Number(f64::NEG_INFINITY));
+        assert!(matches!(parse("nan").kind, AbabaValueKind::Number(x) if x.is_nan()));
+        assert!(matches!(parse("inf - inf").kind, AbabaValueKind::Number(x) if x.is_nan()));
+        assert_eq!(parse("nan").to_string(), "nan");
+        assert_eq!(parse("-inf").to_string(), "-inf");
+
+        let files = files(&[("a.ababa", r#"[include "b.ababa"]"#), ("b.ababa", "inf")]);
+        let value = AbabaParser::new(&files[Path::new("a.ababa")])
+            .with_file("a.ababa")
+            .with_resolver(&files)
+            .allow_non_finite()
+            .parse()
+            .unwrap();
+        assert_eq!(value, List(vec![Number(f64::INFINITY)]));
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(
+            parse("[#ffcc00, #FFcc0080]").unwrap(),
+            List(vec![
+                Tuple(vec![Number(255.), Number(204.), Number(0.)]),
+                Tuple(vec![Number(255.), Number(204.), Number(0.), Number(128.)]),
+            ])
+        );
+
+        let value = parse(" #0a0b0c").unwrap();
+        assert_eq!(value.span, Span::new(1, 8));
+        let AbabaValueKind::Tuple { items, .. } = value.kind else {
+            panic!("not a tuple");
+        };
+        assert_eq!(items[1].span, Span::new(4, 6));
+
+        let err = parse("{ a: #fff }").unwrap_err();
+        assert!(matches!(
+            &err,
+            AbabaParseError::InvalidColor { s, span } if s == "fff" && *span == Span::new(5, 9)
+        ));
+        assert_eq!(
+            err.to_string(),
+            "invalid color `#fff`, expected `#rrggbb` or `#rrggbbaa`"
+        );
+    }
+
+    #[test]
+    fn lists_and_tuples() {
+        assert_eq!(
+            parse("[1, 2, 3]").unwrap(),
+            List(vec![Number(1.), Number(2.), Number(3.)])
+        );
+        assert_eq!(
+            parse("(1, [2,], (),)").unwrap(),
+            Tuple(vec![Number(1.), List(vec![Number(2.)]), Tuple(vec![])])
+        );
+        assert_eq!(parse("[]").unwrap(), List(vec![]));
+
+        // `..` is only an item of lists
+        let spread = AbabaValue::from(AbabaValueKind::Spread);
+        assert_eq!(
+            parse("[.., 1, ..]").unwrap(),
+            List(vec![spread.clone(), Number(1.), spread])
+        );
+        for s in ["(..)", "..", "{ a: .. }", "[.. + 1]", "[1 + ..]"] {
+            assert!(
+                matches!(parse(s), Err(AbabaParseError::UnexpectedToken { .. })),
+                "{s}"
+            );
+        }
+    }
+
+    #[test]
+    fn objects() {
+        assert_eq!(
+            parse("Ababa { a: 5.0, ba: (1, 2), }").unwrap(),
+            object(
+                Some("Ababa"),
+                vec![
+                    ("a", Number(5.)),
+                    ("ba", Tuple(vec![Number(1.), Number(2.)]))
+                ]
+            )
+        );
+        assert_eq!(
+            parse("{ inner: Inner {} }").unwrap(),
+            object(None, vec![("inner", object(Some("Inner"), vec![]))])
+        );
+    }
+
+    #[test]
+    fn typed_tuples_and_idents() {
+        assert_eq!(
+            parse("[Point(1, 2), Off, On {}]").unwrap(),
+            List(vec![
+                AbabaValueKind::Tuple {
+                    struct_type: Some("Point".to_string()),
+                    items: vec![Number(1.), Number(2.)],
+                }
+                .into(),
+                AbabaValueKind::Ident("Off".to_string()).into(),
+                object(Some("On"), vec![]),
+            ])
+        );
+
+        let value = parse("[Point(1, 2), Off]").unwrap();
+        let AbabaValueKind::List(items) = value.kind else {
+            panic!("not a list");
+        };
+        assert_eq!(items[0].span, Span::new(1, 12));
+        assert_eq!(items[0].struct_type(), Some("Point"));
+        assert_eq!(items[1].span, Span::new(14, 17));
+        assert_eq!(items[1].struct_type(), Some("Off"));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(parse(""), Err(AbabaParseError::NoContent)));
+        assert!(matches!(parse("   \n\t"), Err(AbabaParseError::NoContent)));
+        assert!(matches!(
+            parse("[1, 2"),
+            Err(AbabaParseError::MissingEndBrace { brace: ']', .. })
+        ));
+        assert!(matches!(
+            parse("(1, 2,"),
+            Err(AbabaParseError::MissingEndBrace { brace: ')', .. })
+        ));
+        assert!(matches!(
+            parse("{ a: 1"),
+            Err(AbabaParseError::MissingEndBrace { brace: '}', .. })
+        ));
+        assert!(matches!(
+            parse("[1 2]"),
+            Err(AbabaParseError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            parse("{ a 1 }"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "':'",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("Ababa ["),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "end of input",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("1 2"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "end of input",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("{ a: 1, a: 2 }"),
+            Err(AbabaParseError::DuplicateField { field, .. }) if field == "a"
+        ));
+    }
+
+    #[test]
+    fn scene_file() {
+        let scene = parse(include_str!("../../src/scene.ababa")).unwrap();
+
+        let AbabaValueKind::Object {
+            struct_type,
+            mut fields,
+        } = scene.kind
+        else {
+            panic!("scene is not an object");
+        };
+        assert_eq!(struct_type.as_deref(), Some("Scene"));
+
+        let camera = fields.remove("camera").unwrap();
+        assert_eq!(
+            camera,
+            object(
+                None,
+                vec![
+                    (
+                        "position",
+                        Tuple(vec![Number(0.), Number(0.), Number(-30.)])
+                    ),
+                    ("up", Tuple(vec![Number(0.), Number(1.), Number(0.)])),
+                    ("right", Tuple(vec![Number(1.), Number(0.), Number(0.)])),
+                    ("forward", Tuple(vec![Number(0.), Number(0.), Number(1.)])),
+                    ("focal_distance", Number(10.)),
+                    ("screen_width", Number(64.)),
+                    ("screen_height", Number(48.)),
+                ]
+            )
+        );
+
+        let AbabaValueKind::List(objects) = fields.remove("scene_objects").unwrap().kind else {
+            panic!("scene_objects is not a list");
+        };
+        let types: Vec<_> = objects
+            .iter()
+            .map(|o| match &o.kind {
+                AbabaValueKind::Object { struct_type, .. } => struct_type.as_deref(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            types,
+            vec![Some("Sphere"), Some("Sphere"), Some("InfinitePlane")]
+        );
+        assert_eq!(
+            objects[2],
+            object(
+                Some("InfinitePlane"),
+                vec![
+                    ("normal", Tuple(vec![Number(0.), Number(1.), Number(0.)])),
+                    ("offset", Number(10.)),
+                    (
+                        "material",
+                        Tuple(vec![Number(128.), Number(128.), Number(128.)])
+                    ),
+                ]
+            )
+        );
+
+        assert!(fields.is_empty());
+    }
+
+    #[test]
+    fn spans() {
+        let s = "Ababa { a: [1, 2.5], b: {} }";
+        let value = parse(s).unwrap();
+        assert_eq!(value.span, Span::new(0, s.len()));
+
+        let AbabaValueKind::Object { fields, .. } = value.kind else {
+            panic!("not an object");
+        };
+        assert_eq!(fields["a"].span, Span::new(11, 19));
+        assert_eq!(fields["b"].span, Span::new(24, 26));
+
+        let AbabaValueKind::List(items) = &fields["a"].kind else {
+            panic!("not a list");
+        };
+        assert_eq!(items[1].span, Span::new(15, 18));
+    }
+
+    #[test]
+    fn error_spans() {
+        let span = |s: &str| parse(s).unwrap_err().span();
+        assert_eq!(span("{ a: [1, 2 }"), Some(Span::new(11, 12)));
+        assert_eq!(span("{ a: [1, 2"), Some(Span::new(5, 6)));
+        assert_eq!(span("{ a: 1, a: 2 }"), Some(Span::new(8, 9)));
+        assert_eq!(span("{ a: 1.2.3 }"), Some(Span::new(5, 10)));
+        assert_eq!(span("{ a: "), Some(Span::new(5, 5)));
+    }
+
+    #[test]
+    fn strings_and_bools() {
+        let string = |s: &str| AbabaValue::from(AbabaValueKind::String(s.to_string()));
+        let bool = |b| AbabaValue::from(AbabaValueKind::Bool(b));
+
+        assert_eq!(
+            parse(r#"{ texture: "wood.ppm", visible: true, label: "" }"#).unwrap(),
+            object(
+                None,
+                vec![
+                    ("texture", string("wood.ppm")),
+                    ("visible", bool(true)),
+                    ("label", string("")),
+                ]
+            )
+        );
+        assert_eq!(
+            parse(r#"["a\"b\\c\n\t\r\0", "\u{e4}\u{1F600}"]"#).unwrap(),
+            List(vec![string("a\"b\\c\n\t\r\0"), string("ä😀")])
+        );
+    }
+
+    #[test]
+    fn string_errors() {
+        assert!(matches!(
+            parse(r#""ab\qc""#),
+            Err(AbabaParseError::InvalidEscape { escape, span }) if escape == r"\q" && span == Span::new(3, 5)
+        ));
+        assert!(matches!(
+            parse(r#""\u{110000}""#),
+            Err(AbabaParseError::InvalidEscape { escape, .. }) if escape == r"\u{110000}"
+        ));
+        assert!(matches!(
+            parse(r#""\u{12""#),
+            Err(AbabaParseError::InvalidEscape { .. })
+        ));
+        assert!(matches!(
+            parse(r#"["abc"#),
+            Err(AbabaParseError::Unterminated { what: "string", span }) if span == Span::new(1, 5)
+        ));
+        assert!(matches!(
+            parse("[1, /* 2"),
+            Err(AbabaParseError::Unterminated {
+                what: "block comment",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            parse("// the answer\n[ /* forty-two */ 42, // done\n]").unwrap(),
+            List(vec![Number(42.)])
+        );
+    }
+
+    #[test]
+    fn bindings() {
+        let s = "
+            let white = (255, 255, 255);
+            let offset = -12.5;
+            let big = { radius: 15, material: $white };
+
+            [$big, { radius: 5, position: (0, 0, $offset) }, $big]
+        ";
+        let big = object(
+            None,
+            vec![
+                ("radius", Number(15.)),
+                (
+                    "material",
+                    Tuple(vec![Number(255.), Number(255.), Number(255.)]),
+                ),
+            ],
+        );
+        let small = object(
+            None,
+            vec![
+                ("radius", Number(5.)),
+                (
+                    "position",
+                    Tuple(vec![Number(0.), Number(0.), Number(-12.5)]),
+                ),
+            ],
+        );
+        assert_eq!(parse(s).unwrap(), List(vec![big.clone(), small, big]));
+
+        // the reference has its own span
+        let value = parse("let a = 1; [$a]").unwrap();
+        let AbabaValueKind::List(items) = value.kind else {
+            panic!("expected list");
+        };
+        assert_eq!(items[0].span, Span::new(12, 14));
+    }
+
+    #[test]
+    fn anchors() {
+        assert_eq!(
+            parse("[&a (1, 2), $a, { b: &b [$a] }, $b]").unwrap(),
+            List(vec![
+                Tuple(vec![Number(1.), Number(2.)]),
+                Tuple(vec![Number(1.), Number(2.)]),
+                object(
+                    None,
+                    vec![("b", List(vec![Tuple(vec![Number(1.), Number(2.)])]))]
+                ),
+                List(vec![Tuple(vec![Number(1.), Number(2.)])]),
+            ])
+        );
+        assert_eq!(
+            parse("let a = &b 5; ($a, $b)").unwrap(),
+            Tuple(vec![Number(5.), Number(5.)])
+        );
+    }
+
+    #[test]
+    fn binding_errors() {
+        // every binding doubles the previous one, the references of `$an` copy about 2^(n + 1) values
+        let doubling = |n| {
+            let bindings: String = (1..=n)
+                .map(|i| format!("let a{i} = [$a{}, $a{}];", i - 1, i - 1))
+                .collect();
+            parse(&format!("let a0 = 1; {bindings} $a{n}"))
+        };
+        assert!(doubling(10).is_ok());
+        let err = doubling(40).unwrap_err();
+        assert!(matches!(
+            err,
+            AbabaParseError::TooManyCopies {
+                limit: 1_000_000,
+                ..
+            }
+        ));
+        assert_eq!(err.to_string(), "references copy more than 1000000 values");
+
+        let err = parse("let a = 1; [$a, $b]").unwrap_err();
+        assert!(matches!(
+            &err,
+            AbabaParseError::UndefinedName { name, span } if name == "b" && *span == Span::new(16, 18)
+        ));
+        assert_eq!(err.to_string(), "`$b` is not defined");
+
+        // names have to be defined before they're used
+        assert!(matches!(
+            parse("let a = $b; let b = 1; $a"),
+            Err(AbabaParseError::UndefinedName { name, .. }) if name == "b"
+        ));
+
+        let err = parse("let a = [1, $a]; $a").unwrap_err();
+        assert!(matches!(
+            &err,
+            AbabaParseError::CyclicReference { name, span } if name == "a" && *span == Span::new(12, 14)
+        ));
+        assert_eq!(err.to_string(), "`$a` is used inside of its own definition");
+        assert!(matches!(
+            parse("&a { b: &b [$a] }"),
+            Err(AbabaParseError::CyclicReference { name, .. }) if name == "a"
+        ));
+
+        assert!(matches!(
+            parse("let a = 1; let a = 2; $a"),
+            Err(AbabaParseError::DuplicateName { name, span }) if name == "a" && span == Span::new(15, 16)
+        ));
+        assert!(matches!(
+            parse("let a = &a 1; $a"),
+            Err(AbabaParseError::DuplicateName { .. })
+        ));
+
+        assert!(matches!(
+            parse("let a = 1 $a"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "';'",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("let 5 = 1; 5"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "name",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("let a = 1;"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "value",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("[let]"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "value",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn loops() {
+        assert_eq!(
+            parse("let n = 3; [0, for i in 1..$n { $i * 2, -$i }, repeat 2 { 9 }]").unwrap(),
+            List(vec![
+                Number(0.),
+                Number(2.),
+                Number(-1.),
+                Number(4.),
+                Number(-2.),
+                Number(9.),
+                Number(9.),
+            ])
+        );
+        assert_eq!(
+            parse("[for x in -1..1 { for y in 0..$x + 2 { ($x, $y) } }]").unwrap(),
+            List(vec![
+                Tuple(vec![Number(-1.), Number(0.)]),
+                Tuple(vec![Number(0.), Number(0.)]),
+                Tuple(vec![Number(0.), Number(1.)]),
+            ])
+        );
+        assert_eq!(
+            parse("[for i in 2..0 { $undefined }, repeat 0 {}, repeat 1 {}]").unwrap(),
+            List(vec![])
+        );
+
+        // every iteration has its own anchors, the name is gone after the loop
+        assert_eq!(
+            parse("[for i in 0..2 { &a { i: $i }, $a }, for i in 0..1 { $i }]").unwrap(),
+            List(vec![
+                object(None, vec![("i", Number(0.))]),
+                object(None, vec![("i", Number(0.))]),
+                object(None, vec![("i", Number(1.))]),
+                object(None, vec![("i", Number(1.))]),
+                Number(0.),
+            ])
+        );
+        assert!(matches!(
+            parse("[for i in 0..1 { &a 1 }, $a]"),
+            Err(AbabaParseError::UndefinedName { name, .. }) if name == "a"
+        ));
+
+        // the items keep the spans of the body
+        let value = parse("[repeat 2 { 1 }]").unwrap();
+        let AbabaValueKind::List(items) = value.kind else {
+            panic!("expected list");
+        };
+        assert_eq!(items[0].span, Span::new(12, 13));
+        assert_eq!(items[1].span, Span::new(12, 13));
+    }
+
+    #[test]
+    fn nesting() {
+        let nested = |open: &str, close: &str, depth| {
+            format!("{}1{}", open.repeat(depth), close.repeat(depth))
+        };
+
+        // the innermost value is one level deeper than the lists around it
+        assert!(parse(&nested("[", "]", MAX_DEPTH - 1)).is_ok());
+        assert!(parse(&nested("-", "", MAX_DEPTH - 1)).is_ok());
+        assert!(parse(&nested("{ a: ", " }", MAX_DEPTH - 1)).is_ok());
+
+        let err = parse(&nested("[", "]", MAX_DEPTH)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "values are nested more than 128 levels deep"
+        );
+        assert_eq!(err.span(), Some(Span::new(MAX_DEPTH, MAX_DEPTH + 1)));
+        assert!(matches!(
+            parse(&nested("(", ")", MAX_DEPTH + 5)),
+            Err(AbabaParseError::TooDeep {
+                limit: MAX_DEPTH,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&nested("-", "", 200_000)),
+            Err(AbabaParseError::TooDeep { .. })
+        ));
+
+        // unclosed lists deep enough to overflow the stack, the error is recovered from
+        let (value, errors) = AbabaParser::new(&"[".repeat(200_000)).parse_recovering();
+        assert!(value.is_some());
+        assert!(matches!(errors[0], AbabaParseError::TooDeep { .. }));
+    }
+
+    #[test]
+    fn loop_errors() {
+        let err = parse("[for i in 0..1000 { for j in 0..1000 { 1 } }]").unwrap_err();
+        assert!(matches!(
+            err,
+            AbabaParseError::TooManyIterations { limit: 100_000, span } if span == Span::new(20, 36)
+        ));
+        assert_eq!(err.to_string(), "loops run more than 100000 times");
+        assert!(matches!(
+            parse("[repeat 1e12 {}]"),
+            Err(AbabaParseError::TooManyIterations { .. })
+        ));
+
+        let err = parse("[repeat 1 - 2 { 1 }]").unwrap_err();
+        assert_eq!(err.to_string(), "number -1 does not fit into a count");
+        assert_eq!(err.span(), Some(Span::new(8, 13)));
+        assert_eq!(
+            parse("[for i in 0..1e300 { $i }]").unwrap_err().to_string(),
+            "number 1e300 does not fit into an integer"
+        );
+        assert_eq!(
+            parse("[repeat 1e-300 { 1 }]").unwrap_err().to_string(),
+            "number 1e-300 does not fit into an integer"
+        );
+        // ranges that end before they start are empty, like in rust
+        assert_eq!(
+            parse("[for i in 0..-1 { $i }, for i in -1..-3 { $i }]").unwrap(),
+            List(vec![])
+        );
+
+        let err = parse("[repeat 2.5 { 1 }]").unwrap_err();
+        assert_eq!(err.to_string(), "number 2.5 does not fit into an integer");
+        assert_eq!(err.span(), Some(Span::new(8, 11)));
+        assert!(matches!(
+            parse("[for i in 0..\"a\" {}]"),
+            Err(AbabaParseError::ValueTypeDidNotMatch { .. })
+        ));
+        assert!(matches!(
+            parse("let i = 1; [for i in 0..2 { $i }]"),
+            Err(AbabaParseError::DuplicateName { name, .. }) if name == "i"
+        ));
+
+        // loops are only items of lists
+        assert!(matches!(
+            parse("(repeat 2 { 1 })"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "value",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("[for 0..2 { 1 }]"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "name",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("[for i 0..2 { 1 }]"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "`in`",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("[repeat 2 [1]]"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "'{'",
+                ..
+            })
+        ));
+
+        // an error in the body is only reported once, parsing continues after the loop
+        assert_eq!(
+            parse_recovering("[repeat 3 { 1 / 0, 2 }, 3]"),
+            (
+                Some(List(vec![Number(2.), Number(3.)])),
+                vec!["division by zero".to_string()]
+            )
+        );
+        assert_eq!(
+            parse_recovering("[repeat 3.5 { 1 }, 3]"),
+            (
+                Some(List(vec![Number(3.)])),
+                vec!["number 3.5 does not fit into an integer".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn units() {
+        use crate::AbabaUnit;
+        let unit = |s| parse(s).map(|value| (value.kind, value.unit));
+        let number = |x| AbabaValueKind::Number(x);
+
+        assert_eq!(unit("2m").unwrap(), (number(2.), Some(AbabaUnit::Length)));
+        assert_eq!(
+            unit("1_0cm").unwrap(),
+            (number(0.1), Some(AbabaUnit::Length))
+        );
+        assert_eq!(
+            unit("5mm").unwrap(),
+            (number(0.005), Some(AbabaUnit::Length))
+        );
+        assert_eq!(
+            unit("180deg").unwrap(),
+            (number(std::f64::consts::PI), Some(AbabaUnit::Angle))
+        );
+        assert_eq!(
+            unit("0.5rad").unwrap(),
+            (number(0.5), Some(AbabaUnit::Angle))
+        );
+        assert_eq!(unit("0xff").unwrap(), (number(255.), None));
+
+        // references, anchors, negation, parens and expressions keep the unit
+        let unit = |s| unit(s).unwrap().1;
+        assert_eq!(unit("let a = 1m; $a"), Some(AbabaUnit::Length));
+        assert_eq!(unit("&a 1m"), Some(AbabaUnit::Length));
+        assert_eq!(unit("-(45deg)"), Some(AbabaUnit::Angle));
+        assert_eq!(unit("2 * 45deg - 1"), Some(AbabaUnit::Angle));
+        assert_eq!(unit("1m / 50cm"), None);
+        assert_eq!(unit("sin(45deg)"), None);
+
+        let err = parse("1m + 90deg").unwrap_err();
+        assert_eq!(err.to_string(), "expected a length, got an angle");
+        assert_eq!(err.span(), Some(Span::new(5, 10)));
+        assert!(matches!(
+            parse("[10px]"),
+            Err(AbabaParseError::InvalidNumber { s, .. }) if s == "10px"
+        ));
+
+        // merged values keep the unit of the value on top
+        let base = parse("{ a: 1, b: [1m] }").unwrap();
+        let merged = base.merge(parse("{ a: 2cm, b: [.., 1] }").unwrap());
+        let AbabaValueKind::Object { fields, .. } = merged.kind else {
+            panic!("expected object");
+        };
+        assert_eq!(fields["a"].unit, Some(AbabaUnit::Length));
+        let AbabaValueKind::List(items) = &fields["b"].kind else {
+            panic!("expected list");
+        };
+        assert_eq!(items[0].unit, Some(AbabaUnit::Length));
+    }
+
+    #[test]
+    fn lookups() {
+        struct Env;
+        impl AbabaResolver for Env {
+            fn read(&self, _: &Path) -> std::io::Result<String> {
+                Err(std::io::ErrorKind::NotFound.into())
+            }
+            fn env(&self, name: &str) -> Option<String> {
+                match name {
+                    "WIDTH" => Some("2 * 64".to_string()),
+                    "OUT" => Some("out.ppm".to_string()),
+                    "SECRET" => Some("include \"/etc/passwd\"".to_string()),
+                    "QUALITY" => Some("param(\"quality\")".to_string()),
+                    "DEEP" => Some(format!(
+                        "{}1{}",
+                        "[".repeat(MAX_DEPTH - 2),
+                        "]".repeat(MAX_DEPTH - 2)
+                    )),
+                    _ => None,
+                }
+            }
+        }
+
+        let params = HashMap::from([("quality".to_string(), parse("(2, 4m)").unwrap())]);
+        let lookup = |s| {
+            AbabaParser::new(s)
+                .with_resolver(&Env)
+                .with_params(&params)
+                .parse()
+        };
+        let value = |s| lookup(s).unwrap().kind;
+        let expected = |s| parse(s).unwrap().kind;
+
+        assert_eq!(value(r#"env("WIDTH", 64)"#), expected("128"));
+        assert_eq!(value(r#"env("HEIGHT", 48) / 2"#), expected("24"));
+        assert_eq!(value(r#"env("OUT", "a.ppm")"#), expected(r#""out.ppm""#));
+        assert_eq!(value(r#"param("quality")"#), expected("(2, 4)"));
+        assert_eq!(value(r#"param("samples", [1])"#), expected("[1]"));
+        assert_eq!(parse(r#"param("quality", 1)"#).unwrap().kind, expected("1"));
+
+        // found values get the span of the lookup, but keep their unit
+        let found = lookup(r#"[param("quality")]"#).unwrap();
+        let AbabaValueKind::List(items) = found.kind else {
+            panic!("expected list");
+        };
+        let AbabaValueKind::Tuple { items, .. } = &items[0].kind else {
+            panic!("expected tuple");
+        };
+        assert_eq!(items[1].span, Span::new(1, 17));
+        assert_eq!(items[1].unit, Some(crate::AbabaUnit::Length));
+
+        // included files see the same params
+        let files = files(&[
+            ("a.ababa", "include \"b.ababa\""),
+            ("b.ababa", "param(\"quality\")"),
+        ]);
+        let included = AbabaParser::new(&files[Path::new("a.ababa")])
+            .with_file("a.ababa")
+            .with_resolver(&files)
+            .with_params(&params)
+            .parse();
+        assert_eq!(included.unwrap().kind, expected("(2, 4)"));
+
+        let err = |s| lookup(s).unwrap_err().to_string();
+        assert_eq!(
+            err(r#"env("HEIGHT")"#),
+            r#"`env("HEIGHT")` is not set and has no default"#
+        );
+        assert_eq!(
+            err(r#"param("samples")"#),
+            r#"`param("samples")` is not set and has no default"#
+        );
+        assert_eq!(
+            err(r#"env("OUT", 1)"#),
+            "environment variable `OUT` is not a valid value: \
+             expected end of input, got unknown character '.'"
+        );
+        assert_eq!(err(r#"env("A", 1, 2)"#), "`env` takes 2 arguments, got 3");
+
+        // values of environment variables are parsed with the same resolver, params and depth
+        assert_eq!(value(r#"env("QUALITY", 1)"#), expected("(2, 4)"));
+        assert_eq!(
+            err(r#"env("SECRET", 1)"#),
+            "environment variable `SECRET` is not a valid value: \
+             could not read `/etc/passwd`: entity not found"
+        );
+        // the lookup itself is already nested one level deep
+        assert!(lookup(r#"env("DEEP", 1)"#).is_ok());
+        assert!(err(r#"[env("DEEP", 1)]"#).contains("nested more than"));
+        assert_eq!(err("param()"), "`param` takes 2 arguments, got 0");
+        assert_eq!(err("param(quality)"), "expected String, got Ident");
+        assert_eq!(
+            lookup(r#"env("HEIGHT")"#).unwrap_err().span(),
+            Some(Span::new(0, 13))
+        );
+    }
+
+    #[test]
+    fn expressions() {
+        let x = |s| match parse(s).unwrap().kind {
+            AbabaValueKind::Number(x) => x,
+            kind => panic!("expected number, got {kind:?}"),
+        };
+
+        assert_eq!(x("1 + 2 * 3"), 7.);
+        assert_eq!(x("(1 + 2) * 3"), 9.);
+        assert_eq!(x("10 - 4 - 3"), 3.);
+        assert_eq!(x("12 / 2 / 3"), 2.);
+        assert_eq!(x("-2 * -3"), 6.);
+        assert_eq!(x("--2"), 2.);
+        assert_eq!(x("-(1 + 2)"), -3.);
+        assert_eq!(x("2 * pi"), std::f64::consts::TAU);
+        assert_eq!(x("tau / 2"), std::f64::consts::PI);
+        assert_eq!(x("sqrt(16) + abs(-1)"), 5.);
+        assert_eq!(x("rad(180)"), std::f64::consts::PI);
+        assert_eq!(x("deg(pi / 2)"), 90.);
+        assert_eq!(x("min(1, 2) + max(1, 2,) * pow(2, 3)"), 17.);
+        assert!((x("sin(rad(30))") - 0.5).abs() < 1e-12);
+        assert!((x("cos(pi)") + 1.).abs() < 1e-12);
+        assert_eq!(x("let r = 5; $r * 2 + 1"), 11.);
+
+        // the span covers the whole expression
+        assert_eq!(parse("[ 1 + 2 * 3 ]").unwrap(), List(vec![Number(7.)]));
+        let value = parse("-(1 + 2) * 3").unwrap();
+        assert_eq!(value.span, Span::new(0, 12));
+
+        // parens around a single value without a comma are just parens
+        assert_eq!(parse("((1))").unwrap(), Number(1.));
+        assert_eq!(parse("(1,)").unwrap(), Tuple(vec![Number(1.)]));
+        assert_eq!(
+            parse("(1 + 1, 2 * 1)").unwrap(),
+            Tuple(vec![Number(2.), Number(2.)])
+        );
+        assert_eq!(parse("([1])").unwrap(), List(vec![Number(1.)]));
+        assert_eq!(
+            parse("Point(1)").unwrap(),
+            AbabaValueKind::Tuple {
+                struct_type: Some("Point".to_string()),
+                items: vec![Number(1.)]
+            }
+            .into()
+        );
+
+        // constants are only constants on their own
+        assert_eq!(
+            parse("[pi { }, pie]").unwrap(),
+            List(vec![
+                object(Some("pi"), vec![]),
+                AbabaValueKind::Ident("pie".to_string()).into()
+            ])
+        );
+    }
+
+    #[test]
+    fn expression_errors() {
+        let err = parse("[1, 2 / (3 - 3)]").unwrap_err();
+        assert!(
+            matches!(err, AbabaParseError::DivisionByZero { span } if span == Span::new(8, 15))
+        );
+        assert_eq!(err.to_string(), "division by zero");
+
+        let err = parse("{ a: 2 * sine(1) }").unwrap_err();
+        assert!(matches!(
+            &err,
+            AbabaParseError::UnknownFunction { name, span } if name == "sine" && *span == Span::new(9, 16)
+        ));
+        assert_eq!(err.to_string(), "unknown function `sine`");
+        assert!(matches!(
+            parse("-foo(1)"),
+            Err(AbabaParseError::UnknownFunction { name, .. }) if name == "foo"
+        ));
+        // on its own it could be a tuple of a lowercase type
+        assert_eq!(parse("foo(1)").unwrap().struct_type(), Some("foo"));
+
+        let err = parse("[sqrt(-1)]").unwrap_err();
+        assert!(matches!(err, AbabaParseError::NotFinite { span } if span == Span::new(1, 9)));
+        assert_eq!(err.to_string(), "the number is not finite");
+        assert!(matches!(
+            parse("pow(0, -1)"),
+            Err(AbabaParseError::NotFinite { .. })
+        ));
+        let value = AbabaParser::new("sqrt(-1)")
+            .allow_non_finite()
+            .parse()
+            .unwrap();
+        assert!(matches!(value.kind, AbabaValueKind::Number(x) if x.is_nan()));
+
+        let err = parse("min(1)").unwrap_err();
+        assert!(matches!(
+            err,
+            AbabaParseError::WrongArgumentCount { function: "min", expected: 2, got: 1, span }
+                if span == Span::new(0, 6)
+        ));
+        assert_eq!(err.to_string(), "`min` takes 2 arguments, got 1");
+
+        let err = parse("1 + \"2\"").unwrap_err();
+        assert!(matches!(
+            err,
+            AbabaParseError::ValueTypeDidNotMatch { expected: "Number", got }
+                if got.span == Span::new(4, 7)
+        ));
+        assert!(matches!(
+            parse("sqrt((1, 2))"),
+            Err(AbabaParseError::ValueTypeDidNotMatch {
+                expected: "Number",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("Point(1) * 2"),
+            Err(AbabaParseError::ValueTypeDidNotMatch {
+                expected: "Number",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("1 +"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "value",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("1 2"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "end of input",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn includes() {
+        let files = files(&[
+            (
+                "scenes/scene.ababa",
+                r#"import "../lib/materials.ababa"; { lights: include "lights.ababa", a: $red }"#,
+            ),
+            (
+                "scenes/lights.ababa",
+                "let l = 1; [$l, include \"./light.ababa\"]",
+            ),
+            ("scenes/light.ababa", "2"),
+            (
+                "lib/materials.ababa",
+                r#"import "colors.ababa"; let red = $r; let green = (0, 1, 0);"#,
+            ),
+            ("lib/colors.ababa", "let r = (1, 0, 0); $r"),
+        ]);
+
+        let value = parse_file(&files, "scenes/scene.ababa").unwrap();
+        assert_eq!(
+            value,
+            object(
+                None,
+                vec![
+                    ("lights", List(vec![Number(1.), Number(2.)])),
+                    ("a", Tuple(vec![Number(1.), Number(0.), Number(0.)])),
+                ]
+            )
+        );
+
+        // included values get the span of the include
+        let AbabaValueKind::Object { fields, .. } = value.kind else {
+            unreachable!()
+        };
+        let AbabaValueKind::List(lights) = &fields["lights"].kind else {
+            unreachable!()
+        };
+        assert_eq!(fields["lights"].span, Span::new(43, 65));
+        assert_eq!(lights[1].span, Span::new(43, 65));
+
+        // the bindings of included files stay there, imported ones are all available. only
+        // imported files may consist of bindings only.
+        let files = self::files(&[
+            ("a.ababa", r#"[include "b.ababa", $l]"#),
+            ("b.ababa", "let l = 1; $l"),
+            ("c.ababa", r#"import "d.ababa"; [$r, $g]"#),
+            ("d.ababa", r#"import "e.ababa"; let g = 2;"#),
+            ("e.ababa", "let r = 1;"),
+            ("f.ababa", r#"[include "e.ababa"]"#),
+        ]);
+        assert!(matches!(
+            parse_file(&files, "a.ababa"),
+            Err(AbabaParseError::UndefinedName { name, .. }) if name == "l"
+        ));
+        assert_eq!(
+            parse_file(&files, "c.ababa").unwrap(),
+            List(vec![Number(1.), Number(2.)])
+        );
+        assert!(matches!(
+            parse_file(&files, "f.ababa").unwrap_err().root(),
+            AbabaParseError::UnexpectedToken {
+                expected: "value",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn include_errors() {
+        let files = files(&[
+            ("a.ababa", r#"{ b: include "b.ababa" }"#),
+            ("b.ababa", r#"[1, include "dir/c.ababa"]"#),
+            ("dir/c.ababa", r#"include "../a.ababa""#),
+            ("d.ababa", r#"include "missing.ababa""#),
+            ("e.ababa", r#"import "f.ababa"; let x = 1; $x"#),
+            ("f.ababa", "let x = 2;"),
+            ("g.ababa", r#"[include "h.ababa"]"#),
+            ("h.ababa", "(1, @)"),
+            ("i.ababa", "include 1"),
+            ("j.ababa", r#"import "f.ababa" 1"#),
+        ]);
+
+        let err = parse_file(&files, "a.ababa").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "file includes itself: a.ababa -> b.ababa -> dir/c.ababa -> a.ababa \
+             (in `dir/c.ababa`, included from `b.ababa`)"
+        );
+        assert_eq!(
+            err.include_chain(),
+            [Path::new("b.ababa"), Path::new("dir/c.ababa")]
+        );
+        assert_eq!(err.span(), Some(Span::new(5, 22)));
+        assert!(matches!(
+            err.root(),
+            AbabaParseError::IncludeCycle { span, .. } if *span == Span::new(8, 20)
+        ));
+
+        let err = parse_file(&files, "d.ababa").unwrap_err();
+        assert!(matches!(
+            &err,
+            AbabaParseError::IncludeFailed { file, span, .. }
+                if file == Path::new("missing.ababa") && *span == Span::new(8, 23)
+        ));
+        assert_eq!(
+            err.to_string(),
+            "could not read `missing.ababa`: file not found"
+        );
+
+        // imported names can't be defined again
+        assert!(matches!(
+            parse_file(&files, "e.ababa"),
+            Err(AbabaParseError::DuplicateName { name, .. }) if name == "x"
+        ));
+        assert!(matches!(
+            parse_file(&files, "g.ababa").unwrap_err().root(),
+            AbabaParseError::UnexpectedToken { span, .. } if *span == Span::new(4, 5)
+        ));
+        assert!(matches!(
+            parse_file(&files, "i.ababa"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "path",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse_file(&files, "j.ababa"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "';'",
+                ..
+            })
+        ));
+
+        // without a resolver, files are read from the disk
+        assert!(matches!(
+            parse(r#"include "does/not/exist.ababa""#),
+            Err(AbabaParseError::IncludeFailed { error, .. })
+                if error.kind() == std::io::ErrorKind::NotFound
+        ));
+    }
+
+    #[test]
+    fn recovery() {
+        let (value, errors) = parse_recovering("[1 2, 3, 4 + , 5]");
+        assert_eq!(value, Some(List(vec![Number(1.), Number(3.), Number(5.)])));
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert!(errors[0].starts_with("expected ',' or closing brace"));
+        assert!(errors[1].starts_with("expected value"));
+
+        let (value, errors) = parse_recovering("{ a: 1,, b: , c 3, d: 4, a: 5 }");
+        assert_eq!(
+            value,
+            Some(object(None, vec![("a", Number(1.)), ("d", Number(4.))]))
+        );
+        assert_eq!(errors.len(), 4, "{errors:?}");
+        assert!(errors[0].starts_with("expected field name"));
+        assert!(errors[1].starts_with("expected value"));
+        assert!(errors[2].starts_with("expected ':'"));
+        assert!(errors[3].starts_with("field `a` is defined more than once"));
+
+        // a group that isn't closed ends at the closing brace of the one around it
+        let (value, errors) = parse_recovering("Ababa { a: [1, 2 }");
+        assert_eq!(
+            value,
+            Some(object(
+                Some("Ababa"),
+                vec![("a", List(vec![Number(1.), Number(2.)]))]
+            ))
+        );
+        assert_eq!(errors.len(), 1, "{errors:?}");
+
+        // skipped groups don't end the items, even though they contain closing braces
+        let (value, errors) = parse_recovering("[1 (2, [3]) {}, 4]");
+        assert_eq!(value, Some(List(vec![Number(1.), Number(4.)])));
+        assert_eq!(errors.len(), 1, "{errors:?}");
+
+        let (value, errors) = parse_recovering("{ a: [(1, 2");
+        assert_eq!(
+            value,
+            Some(object(
+                None,
+                vec![("a", List(vec![Tuple(vec![Number(1.), Number(2.)])]))]
+            ))
+        );
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(errors.iter().all(|e| e.starts_with("missing closing")));
+
+        // broken bindings are skipped up to their `;`
+        let (value, errors) = parse_recovering("let a = [1 2; let = 3; let b = 4; [$a, $b]");
+        assert_eq!(value, Some(List(vec![List(vec![Number(1.)]), Number(4.)])));
+        assert_eq!(errors.len(), 2, "{errors:?}");
+
+        let (value, errors) = parse_recovering("[1] 2");
+        assert_eq!(value, Some(List(vec![Number(1.)])));
+        assert_eq!(errors.len(), 1);
+
+        let (value, errors) = parse_recovering("let a = ; }");
+        assert_eq!(value, None);
+        assert_eq!(errors.len(), 2, "{errors:?}");
+
+        // `parse` returns the first error
+        assert!(matches!(
+            parse("{ a 1, b: }"),
+            Err(AbabaParseError::UnexpectedToken {
+                expected: "':'",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn include_recovery() {
+        let files = files(&[
+            (
+                "a.ababa",
+                r#"import "b.ababa"; [$x, include "c.ababa", $y]"#,
+            ),
+            ("b.ababa", "let x = 1; let y = [2 3];"),
+            ("c.ababa", "{ a: 4, b: }"),
+        ]);
+
+        let (value, errors) = AbabaParser::new(&files[Path::new("a.ababa")])
+            .with_file("a.ababa")
+            .with_resolver(&files)
+            .parse_recovering();
+        assert_eq!(
+            value,
+            Some(List(vec![
+                Number(1.),
+                object(None, vec![("a", Number(4.))]),
+                List(vec![Number(2.)]),
+            ]))
+        );
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert_eq!(errors[0].include_chain(), [Path::new("b.ababa")]);
+        assert_eq!(errors[1].include_chain(), [Path::new("c.ababa")]);
     }
 }
@@ -0,0 +1,506 @@
+//! printing of [AbabaValue] back into ababa text.
+//!
+//! the output always parses back into an equal value (the only exception are numbers that aren't
+//! finite, which don't have a literal). object fields are printed in alphabetical order, since
+//! [AbabaValue] doesn't remember the order they were written in. field names, idents and struct
+//! types that wouldn't be read back as such (like `for` or `a b`) can't be printed, they are an
+//! [AbabaParseError::InvalidName].
+//!
+//! the [Display] impl of [AbabaValue] prints everything on a single line, the alternate form
+//! (`{:#}`) uses the default [AbabaPrinter]. it never fails, names that the printer refuses are
+//! written as they are.
+//!
+//! ```
+//! use ababa_config::{AbabaParser, AbabaPrinter};
+//!
+//! let value = AbabaParser::new("Ababa { b: [1, 2], a: { ba: (1.5, \"ba\") } }").parse().unwrap();
+//!
+//! assert_eq!(
+//!     value.to_string(),
+//!     "Ababa { a: { ba: (1.5, \"ba\") }, b: [1, 2] }"
+//! );
+//! assert_eq!(
+//!     AbabaPrinter::new().with_indent("  ").with_trailing_commas(false).print(&value).unwrap(),
+//!     "\
+//! Ababa {
+//!   a: {
+//!     ba: (1.5, \"ba\")
+//!   },
+//!   b: [1, 2]
+//! }"
+//! );
+//! ```
+
+use crate::expr;
+use crate::parser::LOOKUPS;
+use crate::tokenizer::is_ident;
+use crate::{AbabaParseError, AbabaValue, AbabaValueKind};
+use std::fmt::{Display, Formatter, Write};
+
+/// pretty printer for [AbabaValue].
+///
+/// objects are always spread over multiple lines, lists and tuples only if they contain anything
+/// else than numbers, strings, bools, idents and tuples of those.
+pub struct AbabaPrinter {
+    /// `None` prints everything on a single line
+    indent: Option<String>,
+    trailing_commas: bool,
+}
+
+impl AbabaPrinter {
+    /// indents with tabs and adds trailing commas, like the scene files
+    pub fn new() -> Self {
+        AbabaPrinter {
+            indent: Some("\t".to_string()),
+            trailing_commas: true,
+        }
+    }
+
+    /// prints everything on a single line
+    pub fn compact() -> Self {
+        AbabaPrinter {
+            indent: None,
+            trailing_commas: false,
+        }
+    }
+
+    /// string used for a single level of indentation
+    pub fn with_indent(mut self, indent: impl Into<String>) -> Self {
+        self.indent = Some(indent.into());
+        self
+    }
+
+    /// whether the last item of values spread over multiple lines gets a comma as well
+    pub fn with_trailing_commas(mut self, trailing_commas: bool) -> Self {
+        self.trailing_commas = trailing_commas;
+        self
+    }
+
+    pub fn print(&self, value: &AbabaValue) -> Result<String, AbabaParseError> {
+        check_names(value)?;
+
+        let mut s = String::new();
+        self.write_value(&mut s, value, 0)
+            .expect("writing to a String never fails");
+        Ok(s)
+    }
+
+    fn write_value(
+        &self,
+        w: &mut impl Write,
+        value: &AbabaValue,
+        depth: usize,
+    ) -> std::fmt::Result {
+        match &value.kind {
+            AbabaValueKind::Number(x) => write!(w, "{}", Number(*x)),
+            AbabaValueKind::String(s) => write_string(w, s),
+            AbabaValueKind::Bool(b) => write!(w, "{b}"),
+            AbabaValueKind::Ident(ident) => write!(w, "{ident}"),
+            AbabaValueKind::Spread => write!(w, ".."),
+            AbabaValueKind::List(items) => {
+                let items: Vec<_> = items.iter().map(|item| (None, item)).collect();
+                self.write_items(w, &items, ('[', ']'), depth)
+            }
+            AbabaValueKind::Tuple { struct_type, items } => {
+                if let Some(struct_type) = struct_type {
+                    write!(w, "{struct_type}")?;
+                }
+
+                let items: Vec<_> = items.iter().map(|item| (None, item)).collect();
+                self.write_items(w, &items, ('(', ')'), depth)
+            }
+            AbabaValueKind::Object {
+                struct_type,
+                fields,
+            } => {
+                if let Some(struct_type) = struct_type {
+                    write!(w, "{struct_type} ")?;
+                }
+
+                let mut fields: Vec<_> =
+                    fields.iter().map(|(k, v)| (Some(k.as_str()), v)).collect();
+                fields.sort_by_key(|(k, _)| *k);
+                self.write_items(w, &fields, ('{', '}'), depth)
+            }
+        }
+    }
+
+    // writes the items (with an optional field name) between the braces
+    fn write_items(
+        &self,
+        w: &mut impl Write,
+        items: &[(Option<&str>, &AbabaValue)],
+        (open, close): (char, char),
+        depth: usize,
+    ) -> std::fmt::Result {
+        if items.is_empty() {
+            return write!(w, "{open}{close}");
+        }
+
+        let is_object = open == '{';
+        // a single value in parens needs a comma to be read as a tuple
+        let needs_comma = open == '(' && items.len() == 1;
+        let multiline = match &self.indent {
+            Some(_) => is_object || !items.iter().all(|(_, item)| self.is_inline(item)),
+            None => false,
+        };
+
+        write!(w, "{open}")?;
+        if is_object && !multiline {
+            write!(w, " ")?;
+        }
+
+        for (i, (field, item)) in items.iter().enumerate() {
+            if multiline {
+                writeln!(w)?;
+                self.write_indent(w, depth + 1)?;
+            } else if i > 0 {
+                write!(w, " ")?;
+            }
+
+            if let Some(field) = field {
+                write!(w, "{field}: ")?;
+            }
+            self.write_value(w, item, depth + 1)?;
+
+            let is_last = i == items.len() - 1;
+            if !is_last || (multiline && self.trailing_commas) || needs_comma {
+                write!(w, ",")?;
+            }
+        }
+
+        if multiline {
+            writeln!(w)?;
+            self.write_indent(w, depth)?;
+        } else if is_object {
+            write!(w, " ")?;
+        }
+
+        write!(w, "{close}")
+    }
+
+    fn write_indent(&self, w: &mut impl Write, depth: usize) -> std::fmt::Result {
+        if let Some(indent) = &self.indent {
+            for _ in 0..depth {
+                w.write_str(indent)?;
+            }
+        }
+        Ok(())
+    }
+
+    // whether a value is simple enough to be printed inside of an inline list or tuple
+    fn is_inline(&self, value: &AbabaValue) -> bool {
+        match &value.kind {
+            AbabaValueKind::Number(_)
+            | AbabaValueKind::String(_)
+            | AbabaValueKind::Bool(_)
+            | AbabaValueKind::Ident(_)
+            | AbabaValueKind::Spread => true,
+            AbabaValueKind::Tuple { items, .. } => items.iter().all(|item| {
+                !matches!(
+                    item.kind,
+                    AbabaValueKind::Tuple { .. }
+                        | AbabaValueKind::List(_)
+                        | AbabaValueKind::Object { .. }
+                )
+            }),
+            AbabaValueKind::List(_) | AbabaValueKind::Object { .. } => false,
+        }
+    }
+}
+
+impl Default for AbabaPrinter {
+    fn default() -> Self {
+        AbabaPrinter::new()
+    }
+}
+
+/// whether `name` is read back as a lone ident, `pi` would be the number. `inf` and `nan` are
+/// idents, since they're only numbers with [crate::AbabaParser::allow_non_finite].
+pub(crate) fn is_ident_value(name: &str) -> bool {
+    is_ident(name) && expr::constant(name, false).is_none()
+}
+
+/// whether `name` followed by a tuple is read back as a typed tuple, `sqrt(1)` would be a call
+pub(crate) fn is_tuple_type(name: &str) -> bool {
+    is_ident(name) && expr::function(name).is_none() && !LOOKUPS.contains(&name)
+}
+
+// checks that every field name, ident and struct type in the value is read back as one
+fn check_names(value: &AbabaValue) -> Result<(), AbabaParseError> {
+    let invalid = |name: &str| AbabaParseError::InvalidName {
+        name: name.to_string(),
+        span: value.span,
+    };
+    let check_items = |items: &[AbabaValue]| {
+        items
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, item)| check_names(item).map_err(|e| e.at_index(i)))
+    };
+
+    match &value.kind {
+        AbabaValueKind::Ident(ident) if !is_ident_value(ident) => Err(invalid(ident)),
+        AbabaValueKind::List(items) => check_items(items),
+        AbabaValueKind::Tuple { struct_type, items } => match struct_type {
+            Some(name) if !is_tuple_type(name) => Err(invalid(name)),
+            _ => check_items(items),
+        },
+        AbabaValueKind::Object {
+            struct_type,
+            fields,
+        } => {
+            if let Some(name) = struct_type.as_deref().filter(|name| !is_ident(name)) {
+                return Err(invalid(name));
+            }
+            for (field, item) in fields {
+                if !is_ident(field) {
+                    return Err(invalid(field));
+                }
+                check_names(item).map_err(|e| e.in_field(field))?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// displays a number in the shortest form that is read back as the same number, with an exponent
+/// if it's very large or small. `nan` is written like `inf`, so that
+/// [crate::AbabaParser::allow_non_finite] can read it back.
+pub(crate) struct Number(pub f64);
+
+impl Display for Number {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let x = self.0;
+        match x.abs() {
+            _ if x.is_nan() => write!(f, "nan"),
+            a if a.is_finite() && a != 0. && !(1e-5..1e16).contains(&a) => write!(f, "{x:e}"),
+            _ => write!(f, "{x}"),
+        }
+    }
+}
+
+/// writes the string in quotes, escaping everything that needs to be escaped
+fn write_string(w: &mut impl Write, s: &str) -> std::fmt::Result {
+    w.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '\\' => w.write_str("\\\\")?,
+            '"' => w.write_str("\\\"")?,
+            '\n' => w.write_str("\\n")?,
+            '\r' => w.write_str("\\r")?,
+            '\t' => w.write_str("\\t")?,
+            '\0' => w.write_str("\\0")?,
+            c if c.is_control() => write!(w, "\\u{{{:x}}}", c as u32)?,
+            c => w.write_char(c)?,
+        }
+    }
+    w.write_char('"')
+}
+
+impl Display for AbabaValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let printer = match f.alternate() {
+            true => AbabaPrinter::new(),
+            false => AbabaPrinter::compact(),
+        };
+        printer.write_value(f, self, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AbabaParser;
+
+    fn parse(s: &str) -> AbabaValue {
+        AbabaParser::new(s).parse().unwrap()
+    }
+
+    fn assert_round_trip(value: &AbabaValue) {
+        let printers = [
+            AbabaPrinter::new(),
+            AbabaPrinter::compact(),
+            AbabaPrinter::new()
+                .with_indent("  ")
+                .with_trailing_commas(false),
+        ];
+
+        for printer in printers {
+            let printed = printer.print(value).unwrap();
+            assert_eq!(&parse(&printed), value, "printed as:\n{printed}");
+        }
+    }
+
+    #[test]
+    fn scene_file() {
+        let value = parse(include_str!("../../src/scene.ababa"));
+        assert_round_trip(&value);
+
+        assert_eq!(
+            format!("{value:#}"),
+            "\
+Scene {
+	camera: {
+		focal_distance: 10,
+		forward: (0, 0, 1),
+		position: (0, 0, -30),
+		right: (1, 0, 0),
+		screen_height: 48,
+		screen_width: 64,
+		up: (0, 1, 0),
+	},
+	scene_objects: [
+		Sphere {
+			material: (255, 255, 255),
+			position: (0, 0, 0),
+			radius: 15,
+		},
+		Sphere {
+			material: (0, 255, 0),
+			position: (-10, 0, -12.5),
+			radius: 5,
+		},
+		InfinitePlane {
+			material: (128, 128, 128),
+			normal: (0, 1, 0),
+			offset: 10,
+		},
+	],
+}"
+        );
+    }
+
+    #[test]
+    fn values() {
+        let cases = [
+            "0.1",
+            "-0",
+            r#""""#,
+            r#""a\"b\\c\n\r\t\0\u{7f}ä😀""#,
+            "true",
+            "Ababa",
+            "[]",
+            "()",
+            "{}",
+            "Ababa {}",
+            "(1,)",
+            "((1,),)",
+            "([1],)",
+            "Point(1, 2)",
+            "[[1, 2], (3, [4]), { a: (5,) }]",
+            "[Off, On { level: 5 }, Level(5,)]",
+            "[.., 1, ..]",
+        ];
+
+        for case in cases {
+            assert_round_trip(&parse(case));
+        }
+    }
+
+    #[test]
+    fn numbers() {
+        let numbers = [
+            0.1,
+            -0.,
+            1e300,
+            f64::MAX,
+            f64::MIN,
+            f64::MIN_POSITIVE,
+            5e-324,
+            1. / 3.,
+        ];
+        for x in numbers {
+            assert_round_trip(&AbabaValueKind::Number(x).into());
+        }
+
+        let print = |x: f64| AbabaValue::from(AbabaValueKind::Number(x)).to_string();
+        assert_eq!(print(1e300), "1e300");
+        assert_eq!(print(-5e-324), "-5e-324");
+        assert_eq!(print(1.5e16), "1.5e16");
+        assert_eq!(print(123456.5), "123456.5");
+        assert_eq!(print(0.0001), "0.0001");
+        assert_eq!(print(-0.), "-0");
+    }
+
+    #[test]
+    fn compact() {
+        assert_eq!(
+            parse("{ a: [1, { b: \"c\" }], d: Ababa(), e: (true,) }").to_string(),
+            "{ a: [1, { b: \"c\" }], d: Ababa(), e: (true,) }"
+        );
+    }
+
+    #[test]
+    fn names() {
+        use crate::AbabaSerialize;
+        use std::collections::BTreeMap;
+
+        // names that only look like keywords, functions or constants are fine
+        for key in ["for_each", "formula", "inf", "pi", "sqrt", "env", "_", "a1"] {
+            let map = BTreeMap::from([(key.to_string(), 1.)]);
+            assert_round_trip(&map.to_ababa());
+        }
+        assert_round_trip(&parse("[sqrt, env, Pi, pi { a: 1 }, Inf(1, 2)]"));
+        assert_round_trip(&parse("[inf, nan, pi]"));
+        assert_eq!(
+            parse("[inf, nan, pi]").to_string(),
+            "[inf, nan, 3.141592653589793]"
+        );
+
+        for key in ["", "for", "true", "1x", "a b"] {
+            let map = BTreeMap::from([(key.to_string(), 1.)]);
+            let err = AbabaPrinter::new().print(&map.to_ababa()).unwrap_err();
+            assert_eq!(err.to_string(), format!("{key:?} is not a valid name"));
+            assert_eq!(map.to_ababa().to_string(), format!("{{ {key}: 1 }}"));
+        }
+
+        let tuple = AbabaValueKind::Tuple {
+            struct_type: Some("sqrt".to_string()),
+            items: vec![AbabaValueKind::Number(1.).into()],
+        };
+        let object = AbabaValueKind::Object {
+            struct_type: None,
+            fields: [("a".to_string(), tuple.into())].into(),
+        };
+        assert_eq!(
+            AbabaPrinter::new()
+                .print(&object.into())
+                .unwrap_err()
+                .to_string(),
+            "\"sqrt\" is not a valid name (at `a`)"
+        );
+
+        let value = AbabaValue::from(AbabaValueKind::List(vec![AbabaValueKind::Ident(
+            "pi".to_string(),
+        )
+        .into()]));
+        assert_eq!(
+            AbabaPrinter::new().print(&value).unwrap_err().to_string(),
+            "\"pi\" is not a valid name (at `[0]`)"
+        );
+    }
+
+    #[test]
+    fn layout() {
+        let value = parse("[[1, 2], (3, (4, 5)), [(6, 7)], {}, ([8],)]");
+        assert_eq!(
+            AbabaPrinter::new()
+                .with_indent("  ")
+                .with_trailing_commas(false)
+                .print(&value)
+                .unwrap(),
+            "\
+[
+  [1, 2],
+  (3, (4, 5)),
+  [(6, 7)],
+  {},
+  (
+    [8],
+  )
+]"
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! registry of constructors for trait objects.
+//!
+//! enums can derive their [TryFrom]<AbabaValue> impl, but trait objects like
+//! `Box<dyn SceneObject>` can't, since the set of implementing types is open. an [AbabaRegistry]
+//! maps the type of an object (`Sphere { .. }`) to a function that builds the boxed trait object.
+//!
+//! ```
+//! use ababa_config::{AbabaParseError, AbabaParser, AbabaRegistry, AbabaValue};
+//! use ababa_config_proc::AbabaDeserialize;
+//!
+//! trait Shape {
+//!     fn area(&self) -> f64;
+//! }
+//!
+//! #[derive(AbabaDeserialize)]
+//! struct Square {
+//!     side: f64,
+//! }
+//!
+//! impl Shape for Square {
+//!     fn area(&self) -> f64 {
+//!         self.side * self.side
+//!     }
+//! }
+//!
+//! let mut registry = AbabaRegistry::<dyn Shape>::new();
+//! registry.register("Square", |v| Ok(Box::new(Square::try_from(v)?)));
+//!
+//! let value = AbabaParser::new("Square { side: 2.0 }").parse().unwrap();
+//! let shape = registry.construct(value).unwrap();
+//! assert_eq!(shape.area(), 4.0);
+//! ```
+
+use crate::{AbabaParseError, AbabaSchema, AbabaStructSchema, AbabaValue, AbabaVariantSchema};
+use std::collections::HashMap;
+
+/// builds a boxed `T` from a value with the registered type
+pub type AbabaConstructor<T> = fn(AbabaValue) -> Result<Box<T>, AbabaParseError>;
+
+pub struct AbabaRegistry<T: ?Sized> {
+    constructors: HashMap<&'static str, AbabaConstructor<T>>,
+    schemas: HashMap<&'static str, fn() -> AbabaSchema>,
+}
+
+impl<T: ?Sized> AbabaRegistry<T> {
+    pub fn new() -> Self {
+        AbabaRegistry {
+            constructors: HashMap::new(),
+            schemas: HashMap::new(),
+        }
+    }
+
+    /// registers the constructor for objects of type `name`, replacing any previous one
+    pub fn register(&mut self, name: &'static str, constructor: AbabaConstructor<T>) -> &mut Self {
+        self.constructors.insert(name, constructor);
+        self
+    }
+
+    /// sets the schema of objects of type `name`, usually `Type::schema` of [crate::AbabaDescribe]
+    pub fn describe(&mut self, name: &'static str, schema: fn() -> AbabaSchema) -> &mut Self {
+        self.schemas.insert(name, schema);
+        self
+    }
+
+    /// schema of the values [AbabaRegistry::construct] accepts, an enum called `name` with a
+    /// variant for every registered type. types without a schema accept any value.
+    pub fn schema(&self, name: &'static str) -> AbabaSchema {
+        let variants = self
+            .names()
+            .into_iter()
+            .map(|name| match self.schemas.get(name).map(|schema| schema()) {
+                // the constructor gets the whole object, including its type
+                Some(AbabaSchema::Struct(schema)) => {
+                    AbabaVariantSchema::Struct(AbabaStructSchema { name, ..schema })
+                }
+                Some(AbabaSchema::Tuple(items)) => AbabaVariantSchema::Tuple { name, items },
+                schema => AbabaVariantSchema::Newtype {
+                    name,
+                    inner: Box::new(schema.unwrap_or(AbabaSchema::Any)),
+                },
+            })
+            .collect();
+
+        AbabaSchema::Enum { name, variants }
+    }
+
+    /// names of all registered types, sorted
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names: Vec<_> = self.constructors.keys().copied().collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// calls the constructor registered for the type of `value`
+    pub fn construct(&self, value: AbabaValue) -> Result<Box<T>, AbabaParseError> {
+        match value.struct_type().and_then(|t| self.constructors.get(t)) {
+            Some(constructor) => constructor(value),
+            None => Err(AbabaParseError::UnknownVariant {
+                expected: self.names(),
+                got: value.struct_type().map(str::to_string),
+                span: value.span,
+            }),
+        }
+    }
+}
+
+impl<T: ?Sized> Default for AbabaRegistry<T> {
+    fn default() -> Self {
+        AbabaRegistry::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AbabaDescribe, AbabaParser};
+
+    trait Named {
+        fn name(&self) -> String;
+    }
+
+    struct Ababa(f64, f64);
+
+    impl Named for Ababa {
+        fn name(&self) -> String {
+            format!("ababa {} {}", self.0, self.1)
+        }
+    }
+
+    fn registry() -> AbabaRegistry<dyn Named> {
+        let mut registry = AbabaRegistry::<dyn Named>::new();
+        registry
+            .register("Ababa", |v| {
+                let (a, b) = v.try_into()?;
+                Ok(Box::new(Ababa(a, b)))
+            })
+            .register("Baba", |_| Ok(Box::new(Ababa(0., 0.))))
+            .describe("Ababa", <(f64, f64)>::schema);
+        registry
+    }
+
+    #[test]
+    fn construct() {
+        let value = AbabaParser::new("Ababa(5, 6)").parse().unwrap();
+        assert_eq!(registry().construct(value).unwrap().name(), "ababa 5 6");
+
+        let value = AbabaParser::new("Baba").parse().unwrap();
+        assert_eq!(registry().construct(value).unwrap().name(), "ababa 0 0");
+    }
+
+    #[test]
+    fn unknown_type() {
+        let value = AbabaParser::new("Abab { }").parse().unwrap();
+        let err = registry().construct(value).err().unwrap();
+        assert!(matches!(
+            &err,
+            AbabaParseError::UnknownVariant { expected, got: Some(got), .. }
+                if expected == &["Ababa", "Baba"] && got == "Abab"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "unknown type `Abab`, expected one of `Ababa`, `Baba`"
+        );
+
+        let value = AbabaParser::new("{ }").parse().unwrap();
+        let err = registry().construct(value).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "missing type, expected one of `Ababa`, `Baba`"
+        );
+    }
+
+    #[test]
+    fn schema() {
+        let schema = AbabaSchema::List(Box::new(registry().schema("Named")));
+        let validate = |s| {
+            let value = AbabaParser::new(s).parse().unwrap();
+            schema
+                .validate(&value)
+                .map_err(|errors| errors.iter().map(|e| e.to_string()).collect::<Vec<_>>())
+        };
+
+        assert!(validate("[Ababa(5, 6), Baba { a: 1 }]").is_ok());
+        assert_eq!(
+            validate("[Ababa(5), Abab]").unwrap_err(),
+            [
+                "expected 2 elements, got 1 (at `[0]`)",
+                "unknown type `Abab`, expected one of `Ababa`, `Baba` (at `[1]`)",
+            ]
+        );
+    }
+}
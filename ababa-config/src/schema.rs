@@ -0,0 +1,730 @@
+//! descriptions of the values a type accepts, and validation of values against them.
+//!
+//! `#[derive(AbabaDeserialize)]` also derives [AbabaDescribe], so the fields of a struct (with
+//! their types, aliases and defaults) and the variants of an enum can be looked up without reading
+//! the rust source. [AbabaSchema::validate] checks a value the same way the derived [TryFrom] impls
+//! do, but reports all errors at once instead of only the first one.
+//!
+//! ```
+//! use ababa_config::{AbabaDescribe, AbabaParser};
+//! use ababa_config_proc::AbabaDeserialize;
+//!
+//! #[derive(AbabaDeserialize)]
+//! struct Camera {
+//!     position: (f64, f64, f64),
+//!     width: u32,
+//!     #[ababa(default)]
+//!     samples: u8,
+//! }
+//!
+//! let value = AbabaParser::new("Camera { position: (0, 0), samples: 1000 }").parse().unwrap();
+//! let errors = Camera::schema().validate(&value).unwrap_err();
+//! let errors: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+//! assert_eq!(
+//!     errors,
+//!     [
+//!         "missing field `width`",
+//!         "expected 3 elements, got 2 (at `position`)",
+//!         "number 1000 does not fit into u8 (at `samples`)",
+//!     ]
+//! );
+//! ```
+//!
+//! schemas of recursive types would be infinitely large, so deriving it for them overflows the
+//! stack when [AbabaDescribe::schema] is called.
+
+use crate::fields::suggest;
+use crate::impls::check_len;
+use crate::{AbabaConstraint, AbabaParseError, AbabaValue, AbabaValueKind, Span};
+use ababa_config_proc::AbabaSerialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+/// types that can describe the values they are read from
+pub trait AbabaDescribe {
+    fn schema() -> AbabaSchema;
+}
+
+/// the values a type accepts. it implements [crate::AbabaSerialize], so it can be printed as
+/// ababa as well.
+#[derive(Debug, Clone, PartialEq, AbabaSerialize)]
+pub enum AbabaSchema {
+    /// a number of the rust type `ty`, `min` and `max` are inclusive
+    Number {
+        ty: &'static str,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    String,
+    Bool,
+    List(Box<AbabaSchema>),
+    /// a list with exactly `len` items
+    Array {
+        item: Box<AbabaSchema>,
+        len: usize,
+    },
+    /// a tuple with exactly these items
+    Tuple(Vec<AbabaSchema>),
+    /// the ident `None` or the inner value
+    Option(Box<AbabaSchema>),
+    /// an object with any fields
+    Map(Box<AbabaSchema>),
+    Struct(AbabaStructSchema),
+    /// one of the variants, picked by the type of the value
+    Enum {
+        name: &'static str,
+        variants: Vec<AbabaVariantSchema>,
+    },
+    /// any value, for types that can't describe themselves
+    Any,
+}
+
+/// an object with named fields
+#[derive(Debug, Clone, PartialEq, AbabaSerialize)]
+pub struct AbabaStructSchema {
+    /// the type of the object, which is optional in the value
+    pub name: &'static str,
+    pub fields: Vec<AbabaFieldSchema>,
+    /// the schema of all fields that aren't in `fields`, e.g. because of a flattened map
+    pub other_fields: Option<Box<AbabaSchema>>,
+    /// whether fields that aren't in `fields` are an error, unless there are `other_fields`
+    pub deny_unknown_fields: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, AbabaSerialize)]
+pub struct AbabaFieldSchema {
+    pub name: &'static str,
+    pub aliases: Vec<&'static str>,
+    /// the rust type of the field as it's written in the struct, like `Option<f64>`
+    pub ty: &'static str,
+    pub schema: AbabaSchema,
+    /// whether the field has to be present
+    pub required: bool,
+    /// the function called for a missing field, like `Default::default`
+    pub default: Option<&'static str>,
+    pub constraints: Vec<AbabaConstraint>,
+}
+
+#[derive(Debug, Clone, PartialEq, AbabaSerialize)]
+pub enum AbabaVariantSchema {
+    /// `Name { .. }`, the name of the struct is the name of the variant
+    Struct(AbabaStructSchema),
+    /// `Name(a, b)`
+    Tuple {
+        name: &'static str,
+        items: Vec<AbabaSchema>,
+    },
+    /// `Name(value)`, or anything `inner` accepts with the type `Name`
+    Newtype {
+        name: &'static str,
+        inner: Box<AbabaSchema>,
+    },
+    /// `Name`
+    Unit { name: &'static str },
+}
+
+impl AbabaSchema {
+    /// checks whether `value` is accepted. all errors are collected, sorted by their position.
+    ///
+    /// the constraints of fields are checked as well, except for [AbabaConstraint::Validate]
+    /// which needs the converted value.
+    pub fn validate(&self, value: &AbabaValue) -> Result<(), Vec<AbabaParseError>> {
+        let mut errors = Vec::new();
+        self.check(value, &mut errors);
+        errors.sort_by_key(|e| e.span().map_or(0, |span| span.start));
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    fn check(&self, value: &AbabaValue, errors: &mut Vec<AbabaParseError>) {
+        let span = value.span;
+
+        match (self, &value.kind) {
+            (AbabaSchema::Number { ty, min, max }, AbabaValueKind::Number(x)) => {
+                if min.is_some_and(|min| *x < min) || max.is_some_and(|max| *x > max) {
+                    errors.push(AbabaParseError::NumberOutOfBounds {
+                        x: *x,
+                        target_type: ty,
+                        span,
+                    });
+                }
+            }
+            (AbabaSchema::String, AbabaValueKind::String(_))
+            | (AbabaSchema::Bool, AbabaValueKind::Bool(_))
+            | (AbabaSchema::Any, _) => {}
+            (AbabaSchema::List(schema), AbabaValueKind::List(items)) => {
+                for (i, item) in items.iter().enumerate() {
+                    schema.check_nested(item, errors, |e| e.at_index(i));
+                }
+            }
+            (AbabaSchema::Array { item, len }, AbabaValueKind::List(items)) => {
+                match check_len(items.len(), *len, span) {
+                    Ok(()) => {
+                        for (i, value) in items.iter().enumerate() {
+                            item.check_nested(value, errors, |e| e.at_index(i));
+                        }
+                    }
+                    Err(e) => errors.push(e),
+                }
+            }
+            (AbabaSchema::Option(_), AbabaValueKind::Ident(ident)) if ident == "None" => {}
+            (AbabaSchema::Option(schema), _) => schema.check(value, errors),
+            (AbabaSchema::Tuple(schemas), AbabaValueKind::Tuple { items, .. }) => {
+                check_items(schemas, items, span, errors)
+            }
+            (AbabaSchema::Map(schema), AbabaValueKind::Object { fields, .. }) => {
+                for (name, value) in by_position(fields.iter()) {
+                    schema.check_nested(value, errors, |e| e.in_field(name));
+                }
+            }
+            (
+                AbabaSchema::Struct(schema),
+                AbabaValueKind::Object {
+                    struct_type,
+                    fields,
+                },
+            ) => match struct_type {
+                Some(t) if t != schema.name => {
+                    errors.push(AbabaParseError::StructTypeDidNotMatch {
+                        expected: schema.name,
+                        got: struct_type.clone(),
+                        span,
+                    })
+                }
+                _ => schema.check_fields(fields, span, errors),
+            },
+            (AbabaSchema::Enum { variants, .. }, _) => {
+                let variant = value
+                    .struct_type()
+                    .and_then(|t| variants.iter().find(|v| v.name() == t));
+
+                match variant {
+                    Some(variant) => variant.check(value, errors),
+                    None => errors.push(AbabaParseError::UnknownVariant {
+                        expected: variants.iter().map(AbabaVariantSchema::name).collect(),
+                        got: value.struct_type().map(str::to_string),
+                        span,
+                    }),
+                }
+            }
+            (schema, _) => errors.push(AbabaParseError::ValueTypeDidNotMatch {
+                expected: schema.expected(),
+                got: value.clone(),
+            }),
+        }
+    }
+
+    // checks a nested value, `wrap` adds the path to it to the errors
+    fn check_nested(
+        &self,
+        value: &AbabaValue,
+        errors: &mut Vec<AbabaParseError>,
+        wrap: impl Fn(AbabaParseError) -> AbabaParseError,
+    ) {
+        let mut nested = Vec::new();
+        self.check(value, &mut nested);
+        errors.extend(nested.into_iter().map(wrap));
+    }
+
+    // the kind of value in type errors
+    fn expected(&self) -> &'static str {
+        match self {
+            AbabaSchema::Number { .. } => "Number",
+            AbabaSchema::String => "String",
+            AbabaSchema::Bool => "Bool",
+            AbabaSchema::List(_) | AbabaSchema::Array { .. } => "List",
+            AbabaSchema::Tuple(_) => "Tuple",
+            AbabaSchema::Map(_) | AbabaSchema::Struct(_) => "Object",
+            AbabaSchema::Enum { .. } | AbabaSchema::Option(_) | AbabaSchema::Any => {
+                unreachable!("accept any kind")
+            }
+        }
+    }
+}
+
+impl AbabaStructSchema {
+    pub fn new(name: &'static str) -> Self {
+        AbabaStructSchema {
+            name,
+            fields: Vec::new(),
+            other_fields: None,
+            deny_unknown_fields: false,
+        }
+    }
+
+    /// adds the fields of a `#[ababa(flatten)]` field with the schema `schema`
+    pub fn flatten(&mut self, schema: AbabaSchema) {
+        match schema {
+            AbabaSchema::Struct(schema) => {
+                self.fields.extend(schema.fields);
+                if schema.other_fields.is_some() {
+                    self.other_fields = schema.other_fields;
+                }
+            }
+            AbabaSchema::Map(schema) => self.other_fields = Some(schema),
+            _ => self.other_fields = Some(Box::new(AbabaSchema::Any)),
+        }
+    }
+
+    /// names of all fields, including the aliases
+    pub fn field_names(&self) -> Vec<&'static str> {
+        self.fields
+            .iter()
+            .flat_map(|f| std::iter::once(f.name).chain(f.aliases.iter().copied()))
+            .collect()
+    }
+
+    fn check_fields(
+        &self,
+        fields: &HashMap<String, AbabaValue>,
+        span: Span,
+        errors: &mut Vec<AbabaParseError>,
+    ) {
+        for field in &self.fields {
+            let mut present = std::iter::once(field.name)
+                .chain(field.aliases.iter().copied())
+                .filter_map(|name| fields.get_key_value(name));
+
+            match present.next() {
+                Some((name, value)) => {
+                    for (duplicate, value) in present {
+                        errors.push(AbabaParseError::DuplicateField {
+                            field: duplicate.clone(),
+                            span: value.span,
+                        });
+                    }
+                    field
+                        .schema
+                        .check_nested(value, errors, |e| e.in_field(name));
+                    errors.extend(
+                        field
+                            .constraints
+                            .iter()
+                            .filter_map(|c| c.check(value).err())
+                            .map(|e| e.in_field(name)),
+                    );
+                }
+                None if field.required => errors.push(AbabaParseError::StructFieldNotPresent {
+                    field: field.name,
+                    span,
+                }),
+                None => {}
+            }
+        }
+
+        let names = self.field_names();
+        let others = fields
+            .iter()
+            .filter(|(name, _)| !names.contains(&name.as_str()));
+
+        for (name, value) in by_position(others) {
+            match &self.other_fields {
+                Some(schema) => schema.check_nested(value, errors, |e| e.in_field(name)),
+                None if self.deny_unknown_fields => errors.push(AbabaParseError::UnknownField {
+                    field: name.clone(),
+                    suggestion: suggest(name, &names),
+                    span: value.span,
+                }),
+                None => {}
+            }
+        }
+    }
+}
+
+impl AbabaVariantSchema {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AbabaVariantSchema::Struct(schema) => schema.name,
+            AbabaVariantSchema::Tuple { name, .. }
+            | AbabaVariantSchema::Newtype { name, .. }
+            | AbabaVariantSchema::Unit { name } => name,
+        }
+    }
+
+    // `value` has the type of this variant
+    fn check(&self, value: &AbabaValue, errors: &mut Vec<AbabaParseError>) {
+        let span = value.span;
+        let expected = match (self, &value.kind) {
+            (AbabaVariantSchema::Struct(schema), AbabaValueKind::Object { fields, .. }) => {
+                return schema.check_fields(fields, span, errors);
+            }
+            (
+                AbabaVariantSchema::Tuple { items: schemas, .. },
+                AbabaValueKind::Tuple { items, .. },
+            ) => {
+                return check_items(schemas, items, span, errors);
+            }
+            (AbabaVariantSchema::Newtype { inner, .. }, AbabaValueKind::Tuple { items, .. })
+                if items.len() == 1 =>
+            {
+                return inner.check(&items[0], errors);
+            }
+            (
+                AbabaVariantSchema::Newtype { inner, .. },
+                AbabaValueKind::Tuple { .. } | AbabaValueKind::Object { .. },
+            ) => {
+                return inner.check(&untyped(value), errors);
+            }
+            (AbabaVariantSchema::Unit { .. }, AbabaValueKind::Ident(_)) => return,
+            (AbabaVariantSchema::Unit { .. }, AbabaValueKind::Object { fields, .. })
+                if fields.is_empty() =>
+            {
+                return
+            }
+            (AbabaVariantSchema::Unit { .. }, AbabaValueKind::Tuple { items, .. })
+                if items.is_empty() =>
+            {
+                return
+            }
+            (AbabaVariantSchema::Struct(_), _) => "Object",
+            (AbabaVariantSchema::Tuple { .. } | AbabaVariantSchema::Newtype { .. }, _) => "Tuple",
+            (AbabaVariantSchema::Unit { .. }, _) => "Ident",
+        };
+
+        errors.push(AbabaParseError::ValueTypeDidNotMatch {
+            expected,
+            got: value.clone(),
+        });
+    }
+}
+
+fn check_items(
+    schemas: &[AbabaSchema],
+    items: &[AbabaValue],
+    span: Span,
+    errors: &mut Vec<AbabaParseError>,
+) {
+    if let Err(e) = check_len(items.len(), schemas.len(), span) {
+        errors.push(e);
+        return;
+    }
+
+    for (i, (schema, item)) in schemas.iter().zip(items).enumerate() {
+        schema.check_nested(item, errors, |e| e.at_index(i));
+    }
+}
+
+// fields in the order they appear in the input, hash maps have none
+fn by_position<'a>(
+    fields: impl Iterator<Item = (&'a String, &'a AbabaValue)>,
+) -> Vec<(&'a String, &'a AbabaValue)> {
+    let mut fields: Vec<_> = fields.collect();
+    fields.sort_by_key(|(name, value)| (value.span.start, *name));
+    fields
+}
+
+// the tuple or object without its type, like it's passed on by a single field variant
+fn untyped(value: &AbabaValue) -> AbabaValue {
+    let mut value = value.clone();
+    if let AbabaValueKind::Object { struct_type, .. } | AbabaValueKind::Tuple { struct_type, .. } =
+        &mut value.kind
+    {
+        *struct_type = None;
+    }
+    value
+}
+
+impl AbabaDescribe for f64 {
+    fn schema() -> AbabaSchema {
+        AbabaSchema::Number {
+            ty: "f64",
+            min: None,
+            max: None,
+        }
+    }
+}
+
+macro_rules! impl_describe_number {
+    ($t:ty) => {
+        impl AbabaDescribe for $t {
+            fn schema() -> AbabaSchema {
+                AbabaSchema::Number {
+                    ty: stringify!($t),
+                    min: Some(<$t>::MIN as f64),
+                    max: Some(<$t>::MAX as f64),
+                }
+            }
+        }
+    };
+}
+
+impl_describe_number!(i64);
+impl_describe_number!(u64);
+impl_describe_number!(i32);
+impl_describe_number!(u32);
+impl_describe_number!(i16);
+impl_describe_number!(u16);
+impl_describe_number!(i8);
+impl_describe_number!(u8);
+impl_describe_number!(usize);
+impl_describe_number!(isize);
+
+impl AbabaDescribe for String {
+    fn schema() -> AbabaSchema {
+        AbabaSchema::String
+    }
+}
+
+impl AbabaDescribe for bool {
+    fn schema() -> AbabaSchema {
+        AbabaSchema::Bool
+    }
+}
+
+impl AbabaDescribe for PathBuf {
+    fn schema() -> AbabaSchema {
+        AbabaSchema::String
+    }
+}
+
+impl<T: AbabaDescribe> AbabaDescribe for Vec<T> {
+    fn schema() -> AbabaSchema {
+        AbabaSchema::List(Box::new(T::schema()))
+    }
+}
+
+/// the schema of the boxed value, `Box<dyn Trait>` needs its own impl
+impl<T: AbabaDescribe> AbabaDescribe for Box<T> {
+    fn schema() -> AbabaSchema {
+        T::schema()
+    }
+}
+
+impl<T: AbabaDescribe> AbabaDescribe for Option<T> {
+    fn schema() -> AbabaSchema {
+        AbabaSchema::Option(Box::new(T::schema()))
+    }
+}
+
+impl<T: AbabaDescribe, const N: usize> AbabaDescribe for [T; N] {
+    fn schema() -> AbabaSchema {
+        AbabaSchema::Array {
+            item: Box::new(T::schema()),
+            len: N,
+        }
+    }
+}
+
+macro_rules! impl_describe_tuple {
+    ($($t:ident),+) => {
+        impl<$($t: AbabaDescribe),+> AbabaDescribe for ($($t,)+) {
+            fn schema() -> AbabaSchema {
+                AbabaSchema::Tuple(vec![$($t::schema()),+])
+            }
+        }
+    };
+}
+
+impl_describe_tuple!(A);
+impl_describe_tuple!(A, B);
+impl_describe_tuple!(A, B, C);
+impl_describe_tuple!(A, B, C, D);
+impl_describe_tuple!(A, B, C, D, E);
+impl_describe_tuple!(A, B, C, D, E, F);
+impl_describe_tuple!(A, B, C, D, E, F, G);
+impl_describe_tuple!(A, B, C, D, E, F, G, H);
+impl_describe_tuple!(A, B, C, D, E, F, G, H, I);
+impl_describe_tuple!(A, B, C, D, E, F, G, H, I, J);
+impl_describe_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+impl_describe_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+/// an object with any fields, or flattened into a struct, see [AbabaStructSchema::flatten]
+impl<T: AbabaDescribe> AbabaDescribe for HashMap<String, T> {
+    fn schema() -> AbabaSchema {
+        AbabaSchema::Map(Box::new(T::schema()))
+    }
+}
+
+impl<T: AbabaDescribe> AbabaDescribe for BTreeMap<String, T> {
+    fn schema() -> AbabaSchema {
+        AbabaSchema::Map(Box::new(T::schema()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AbabaParser, AbabaSerialize};
+
+    fn validate(schema: &AbabaSchema, s: &str) -> Vec<String> {
+        let value = AbabaParser::new(s).parse().unwrap();
+        match schema.validate(&value) {
+            Ok(()) => vec![],
+            Err(errors) => errors.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    fn field(name: &'static str, schema: AbabaSchema) -> AbabaFieldSchema {
+        AbabaFieldSchema {
+            name,
+            aliases: vec![],
+            ty: "",
+            schema,
+            required: true,
+            default: None,
+            constraints: vec![],
+        }
+    }
+
+    fn sphere() -> AbabaStructSchema {
+        let mut sphere = AbabaStructSchema::new("Sphere");
+        sphere.fields = vec![
+            field("position", <(f64, f64, f64)>::schema()),
+            AbabaFieldSchema {
+                aliases: vec!["r"],
+                constraints: vec![AbabaConstraint::NonZero],
+                ..field("radius", f64::schema())
+            },
+            AbabaFieldSchema {
+                required: false,
+                default: Some("Default::default"),
+                ..field("material", <(u8, u8, u8)>::schema())
+            },
+        ];
+        sphere
+    }
+
+    #[test]
+    fn values() {
+        let list = Vec::<u8>::schema();
+        assert!(validate(&list, "[1, 2, 255]").is_empty());
+        assert_eq!(
+            validate(&list, "[1, -1, \"a\", 256]"),
+            [
+                "number -1 does not fit into u8 (at `[1]`)",
+                "expected Number, got String (at `[2]`)",
+                "number 256 does not fit into u8 (at `[3]`)",
+            ]
+        );
+        assert_eq!(validate(&list, "(1, 2)"), ["expected List, got Tuple"]);
+        assert_eq!(
+            validate(
+                &AbabaSchema::Map(Box::new(bool::schema())),
+                "{ b: 1, a: true, c: 2 }"
+            ),
+            [
+                "expected Bool, got Number (at `b`)",
+                "expected Bool, got Number (at `c`)"
+            ]
+        );
+        assert!(validate(&AbabaSchema::Any, "[1, (2,), {}]").is_empty());
+
+        let options = Vec::<(Option<u8>, [bool; 2])>::schema();
+        assert!(validate(&options, "[(None, [true, false]), (1, [false, false])]").is_empty());
+        assert_eq!(
+            validate(&options, "[(Some, [true]), (256, [1, true], 3)]"),
+            [
+                "expected Number, got Ident (at `[0][0]`)",
+                "expected 2 elements, got 1 (at `[0][1]`)",
+                "expected 2 elements, got 3 (at `[1]`)",
+            ]
+        );
+    }
+
+    #[test]
+    fn structs() {
+        let mut schema = sphere();
+        let sphere = AbabaSchema::Struct(schema.clone());
+
+        assert!(validate(&sphere, "Sphere { position: (0, 0, 0), r: 1, extra: 1 }").is_empty());
+        assert_eq!(
+            validate(&sphere, "{ position: (0, 0), material: (0, 0, 256) }"),
+            [
+                "missing field `radius`",
+                "expected 3 elements, got 2 (at `position`)",
+                "number 256 does not fit into u8 (at `material[2]`)",
+            ]
+        );
+        assert_eq!(
+            validate(&sphere, "{ position: (0, 0, 0), r: 0 }"),
+            ["must not be zero (at `r`)"]
+        );
+        assert_eq!(
+            validate(&sphere, "Cube { position: (0, 0) }"),
+            ["expected object of type Sphere, got Cube"]
+        );
+        assert_eq!(
+            validate(&sphere, "{ position: (0, 0, 0), radius: 1, r: 2 }"),
+            ["field `r` is defined more than once"]
+        );
+
+        schema.deny_unknown_fields = true;
+        assert_eq!(
+            validate(
+                &AbabaSchema::Struct(schema.clone()),
+                "{ position: (0, 0, 0), radus: 1, colour: 2 }"
+            ),
+            [
+                "missing field `radius`",
+                "unknown field `radus`, did you mean `radius`?",
+                "unknown field `colour`",
+            ]
+        );
+
+        schema.flatten(HashMap::<String, String>::schema());
+        assert_eq!(
+            validate(
+                &AbabaSchema::Struct(schema),
+                "{ position: (0, 0, 0), radius: 1, name: \"a\", tag: 2 }"
+            ),
+            ["expected String, got Number (at `tag`)"]
+        );
+    }
+
+    #[test]
+    fn enums() {
+        let shape = AbabaSchema::Enum {
+            name: "Shape",
+            variants: vec![
+                AbabaVariantSchema::Newtype {
+                    name: "Sphere",
+                    inner: Box::new(AbabaSchema::Struct(sphere())),
+                },
+                AbabaVariantSchema::Struct(AbabaStructSchema {
+                    fields: vec![field("size", f64::schema())],
+                    ..AbabaStructSchema::new("Cube")
+                }),
+                AbabaVariantSchema::Tuple {
+                    name: "Point",
+                    items: vec![f64::schema(), f64::schema()],
+                },
+                AbabaVariantSchema::Unit { name: "Empty" },
+            ],
+        };
+
+        for valid in [
+            "Sphere { position: (0, 0, 0), radius: 1 }",
+            "Sphere({ position: (0, 0, 0), radius: 1 })",
+            "Cube { size: 1 }",
+            "Point(1, 2)",
+            "Empty",
+            "Empty {}",
+            "Empty()",
+        ] {
+            assert!(validate(&shape, valid).is_empty(), "{valid}");
+        }
+
+        let list = AbabaSchema::List(Box::new(shape));
+        assert_eq!(
+            validate(&list, "[Cube { size: true }, Point(1), Empty(1), Circle, Sphere(1, 2)]"),
+            [
+                "expected Number, got Bool (at `[0].size`)",
+                "expected 2 elements, got 1 (at `[1]`)",
+                "expected Ident, got Tuple (at `[2]`)",
+                "unknown type `Circle`, expected one of `Sphere`, `Cube`, `Point`, `Empty` (at `[3]`)",
+                "expected Object, got Tuple (at `[4]`)",
+            ]
+        );
+    }
+
+    #[test]
+    fn printed() {
+        assert_eq!(
+            <(u8, u8)>::schema().to_ababa().to_string(),
+            "Tuple([Number { max: 255, min: 0, ty: \"u8\" }, Number { max: 255, min: 0, ty: \"u8\" }],)"
+        );
+    }
+}
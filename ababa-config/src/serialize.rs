@@ -0,0 +1,217 @@
+//! conversion of rust values into [AbabaValue], the counterpart of the [TryFrom]<AbabaValue> impls.
+//!
+//! the values can then be printed with [crate::AbabaPrinter] or the [std::fmt::Display] impl of
+//! [AbabaValue]. the impls here are written so that converting the printed value back gives the
+//! same rust value again.
+
+use crate::{AbabaValue, AbabaValueKind};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+/// types that can be converted into an [AbabaValue].
+///
+/// can be derived with `#[derive(AbabaSerialize)]` (implemented in [ababa_config_proc]).
+pub trait AbabaSerialize {
+    fn to_ababa(&self) -> AbabaValue;
+}
+
+impl AbabaSerialize for f64 {
+    fn to_ababa(&self) -> AbabaValue {
+        AbabaValueKind::Number(*self).into()
+    }
+}
+
+impl AbabaSerialize for str {
+    fn to_ababa(&self) -> AbabaValue {
+        AbabaValueKind::String(self.to_string()).into()
+    }
+}
+
+impl AbabaSerialize for String {
+    fn to_ababa(&self) -> AbabaValue {
+        self.as_str().to_ababa()
+    }
+}
+
+impl AbabaSerialize for bool {
+    fn to_ababa(&self) -> AbabaValue {
+        AbabaValueKind::Bool(*self).into()
+    }
+}
+
+/// paths that aren't valid unicode are converted lossily
+impl AbabaSerialize for PathBuf {
+    fn to_ababa(&self) -> AbabaValue {
+        self.to_string_lossy().to_ababa()
+    }
+}
+
+macro_rules! impl_to_number {
+    ($t:ty) => {
+        impl AbabaSerialize for $t {
+            fn to_ababa(&self) -> AbabaValue {
+                AbabaValueKind::Number(*self as f64).into()
+            }
+        }
+    };
+}
+
+impl_to_number!(i64);
+impl_to_number!(u64);
+impl_to_number!(i32);
+impl_to_number!(u32);
+impl_to_number!(i16);
+impl_to_number!(u16);
+impl_to_number!(i8);
+impl_to_number!(u8);
+impl_to_number!(usize);
+impl_to_number!(isize);
+
+impl<T: AbabaSerialize + ?Sized> AbabaSerialize for &T {
+    fn to_ababa(&self) -> AbabaValue {
+        (**self).to_ababa()
+    }
+}
+
+impl<T: AbabaSerialize + ?Sized> AbabaSerialize for Box<T> {
+    fn to_ababa(&self) -> AbabaValue {
+        (**self).to_ababa()
+    }
+}
+
+impl<T: AbabaSerialize> AbabaSerialize for [T] {
+    fn to_ababa(&self) -> AbabaValue {
+        AbabaValueKind::List(self.iter().map(T::to_ababa).collect()).into()
+    }
+}
+
+impl<T: AbabaSerialize> AbabaSerialize for Vec<T> {
+    fn to_ababa(&self) -> AbabaValue {
+        self.as_slice().to_ababa()
+    }
+}
+
+impl<T: AbabaSerialize> AbabaSerialize for HashMap<String, T> {
+    fn to_ababa(&self) -> AbabaValue {
+        AbabaValueKind::Object {
+            struct_type: None,
+            fields: self
+                .iter()
+                .map(|(k, v)| (k.clone(), v.to_ababa()))
+                .collect(),
+        }
+        .into()
+    }
+}
+
+impl<T: AbabaSerialize> AbabaSerialize for BTreeMap<String, T> {
+    fn to_ababa(&self) -> AbabaValue {
+        AbabaValueKind::Object {
+            struct_type: None,
+            fields: self
+                .iter()
+                .map(|(k, v)| (k.clone(), v.to_ababa()))
+                .collect(),
+        }
+        .into()
+    }
+}
+
+impl<T: AbabaSerialize, const N: usize> AbabaSerialize for [T; N] {
+    fn to_ababa(&self) -> AbabaValue {
+        self.as_slice().to_ababa()
+    }
+}
+
+/// `None` is the ident `None`
+impl<T: AbabaSerialize> AbabaSerialize for Option<T> {
+    fn to_ababa(&self) -> AbabaValue {
+        match self {
+            Some(value) => value.to_ababa(),
+            None => AbabaValueKind::Ident("None".to_string()).into(),
+        }
+    }
+}
+
+macro_rules! impl_to_tuple {
+    ($($t:ident $i:tt),+) => {
+        impl<$($t: AbabaSerialize),+> AbabaSerialize for ($($t,)+) {
+            fn to_ababa(&self) -> AbabaValue {
+                AbabaValueKind::Tuple {
+                    struct_type: None,
+                    items: vec![$(self.$i.to_ababa()),+],
+                }
+                .into()
+            }
+        }
+    };
+}
+
+impl_to_tuple!(A 0);
+impl_to_tuple!(A 0, B 1);
+impl_to_tuple!(A 0, B 1, C 2);
+impl_to_tuple!(A 0, B 1, C 2, D 3);
+impl_to_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_to_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_to_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_to_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+impl_to_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+impl_to_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+impl_to_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+impl_to_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AbabaParser;
+
+    fn round_trip<T>(x: T) -> T
+    where
+        T: AbabaSerialize + TryFrom<AbabaValue, Error = crate::AbabaParseError>,
+    {
+        let printed = x.to_ababa().to_string();
+        AbabaParser::new(&printed)
+            .parse()
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
+    #[test]
+    fn round_trips() {
+        assert_eq!(round_trip(0.1), 0.1);
+        assert_eq!(round_trip(-12.5), -12.5);
+        assert_eq!(round_trip(u64::MAX), u64::MAX);
+        assert_eq!(round_trip(i8::MIN), i8::MIN);
+        assert_eq!(round_trip("a \"b\"\n".to_string()), "a \"b\"\n");
+        assert!(round_trip(true));
+        assert_eq!(
+            round_trip(PathBuf::from("textures/wood.ppm")),
+            PathBuf::from("textures/wood.ppm")
+        );
+        assert_eq!(
+            round_trip(vec![(1., 2.), (3., 4.)]),
+            vec![(1., 2.), (3., 4.)]
+        );
+        assert_eq!(round_trip((1u8, 2u8, 3u8)), (1, 2, 3));
+        assert_eq!(round_trip(Vec::<Vec<i32>>::new()), Vec::<Vec<i32>>::new());
+        assert_eq!(
+            round_trip((1u8, "a".to_string(), false)),
+            (1, "a".into(), false)
+        );
+        assert_eq!(round_trip((0.5,)), (0.5,));
+        assert_eq!(round_trip([[1u8, 2], [3, 4]]), [[1, 2], [3, 4]]);
+        assert_eq!(round_trip(vec![Some(1u8), None]), [Some(1), None]);
+        assert_eq!(round_trip(Option::<u8>::None), None);
+
+        let map: BTreeMap<String, Option<bool>> =
+            [("a".to_string(), None), ("b".to_string(), Some(true))].into();
+        assert_eq!(round_trip(map.clone()), map);
+
+        let map: HashMap<String, Vec<f64>> =
+            [("a".to_string(), vec![1.]), ("b".to_string(), vec![])].into();
+        let value = map.to_ababa();
+        let parsed = AbabaParser::new(&value.to_string()).parse().unwrap();
+        assert_eq!(parsed, value);
+    }
+}
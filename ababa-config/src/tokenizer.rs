@@ -1,12 +1,36 @@
 //! tokenizer for the ababa config language. parses a string into [AbabaToken].
 //!
-//! tokens of note are [AbabaToken::Ident] and [AbabaToken::Number], both of which represent
-//! more than a single character. they specifically contain a reference to the passed in &str,
-//! which is why a lifetime annotation is necessary.
+//! tokens of note are [AbabaToken::Ident], [AbabaToken::Number], [AbabaToken::Str],
+//! [AbabaToken::Reference] and [AbabaToken::Anchor], all of which represent more than a single
+//! character. they specifically contain a reference to the
+//! passed in &str, which is why a lifetime annotation is necessary. strings are returned as they
+//! appear in the input (without the quotes), escape sequences are resolved by the parser.
+//!
+//! whitespace, line comments (`// ...`) and block comments (`/* ... */`) are skipped. the
+//! formatter needs to keep the comments, [AbabaTokenizer::with_comments] returns them as
+//! [AbabaToken::Comment] instead.
+//!
+//! numbers are decimal (`1_000.5e-3`), hexadecimal (`0xff`) or binary (`0b101`) without a sign,
+//! `-` is always an operator of its own. a number has to be followed by something other than a
+//! digit, letter, `_` or `.`, `1.2.3` or `10px` are a single [AbabaToken::InvalidNumber]. the
+//! exception are the units of decimal numbers like `45deg` or `10cm`, which are part of the
+//! number (see [crate::AbabaUnit]).
+//!
+//! colors are `#` followed by 6 or 8 hex digits (`#rrggbb` or `#rrggbbaa`), any other letters or
+//! digits after a `#` are an [AbabaToken::InvalidColor].
+//!
+//! the tokenizer itself never fails, invalid input is returned as [AbabaToken::UnknownChar],
+//! [AbabaToken::InvalidNumber], [AbabaToken::InvalidColor], [AbabaToken::UnterminatedString] or
+//! [AbabaToken::UnterminatedComment] and reported by the parser.
+//!
+//! every token is returned together with its [Span] in the input.
+use crate::unit::is_suffix;
+use crate::Span;
+use std::fmt::{Display, Formatter};
 use std::iter::Peekable;
 use std::str::CharIndices;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub(crate) enum AbabaToken<'s> {
     LeftBrace,
     RightBrace,
@@ -16,14 +40,89 @@ pub(crate) enum AbabaToken<'s> {
     RightParen,
     ListSeparator,  // comma
     FieldSeparator, // colon
+    Equals,
+    Semicolon,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    /// `..`
+    DotDot,
+    Let,
+    Include,
+    Import,
+    For,
+    In,
+    Repeat,
+    /// `$name`, without the `$`
+    Reference(&'s str),
+    /// `&name`, without the `&`
+    Anchor(&'s str),
     Ident(&'s str),
     Number(&'s str),
+    Str(&'s str),
+    Bool(bool),
+    /// `#rrggbb` or `#rrggbbaa`, without the `#`
+    Color(&'s str),
+    /// the whole comment including `//` or `/* */`, only returned by [AbabaTokenizer::with_comments]
+    Comment(&'s str),
     UnknownChar(char),
+    /// something starting with a digit that isn't a valid number
+    InvalidNumber(&'s str),
+    /// a `#` followed by something other than 6 or 8 hex digits, without the `#`
+    InvalidColor(&'s str),
+    UnterminatedString,
+    UnterminatedComment,
 }
 
+impl Display for AbabaToken<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use AbabaToken::*;
+        match self {
+            LeftBrace => write!(f, "'{{'"),
+            RightBrace => write!(f, "'}}'"),
+            LeftBracket => write!(f, "'['"),
+            RightBracket => write!(f, "']'"),
+            LeftParen => write!(f, "'('"),
+            RightParen => write!(f, "')'"),
+            ListSeparator => write!(f, "','"),
+            FieldSeparator => write!(f, "':'"),
+            Equals => write!(f, "'='"),
+            Semicolon => write!(f, "';'"),
+            Plus => write!(f, "'+'"),
+            Minus => write!(f, "'-'"),
+            Star => write!(f, "'*'"),
+            Slash => write!(f, "'/'"),
+            DotDot => write!(f, "'..'"),
+            Let => write!(f, "`let`"),
+            Include => write!(f, "`include`"),
+            Import => write!(f, "`import`"),
+            For => write!(f, "`for`"),
+            In => write!(f, "`in`"),
+            Repeat => write!(f, "`repeat`"),
+            Reference(s) => write!(f, "reference `${s}`"),
+            Anchor(s) => write!(f, "anchor `&{s}`"),
+            Ident(s) => write!(f, "ident `{s}`"),
+            Number(s) => write!(f, "number `{s}`"),
+            Str(s) => write!(f, "string \"{s}\""),
+            Bool(b) => write!(f, "`{b}`"),
+            Color(s) => write!(f, "color `#{s}`"),
+            Comment(_) => write!(f, "comment"),
+            UnknownChar(c) => write!(f, "unknown character {c:?}"),
+            InvalidNumber(s) => write!(f, "invalid number `{s}`"),
+            InvalidColor(s) => write!(f, "invalid color `#{s}`"),
+            UnterminatedString => write!(f, "unterminated string"),
+            UnterminatedComment => write!(f, "unterminated block comment"),
+        }
+    }
+}
+
+// cloned by the parser to read the body of a loop again
+#[derive(Clone)]
 pub(crate) struct AbabaTokenizer<'s> {
     s: &'s str,
     inner: Peekable<CharIndices<'s>>,
+    comments: bool,
 }
 
 impl<'a> AbabaTokenizer<'a> {
@@ -31,20 +130,85 @@ impl<'a> AbabaTokenizer<'a> {
         AbabaTokenizer {
             s,
             inner: s.char_indices().peekable(),
+            comments: false,
+        }
+    }
+
+    /// returns comments as tokens instead of skipping them
+    pub fn with_comments(s: &'a str) -> Self {
+        AbabaTokenizer {
+            comments: true,
+            ..AbabaTokenizer::new(s)
+        }
+    }
+
+    // advances until the next char is at or after `offset`
+    fn skip_to(&mut self, offset: usize) {
+        while self.inner.next_if(|(i, _)| *i < offset).is_some() {}
+    }
+
+    // advances over the chars of an ident, returns the end of it
+    fn ident_end(&mut self) -> usize {
+        while self
+            .inner
+            .next_if(|(_, c)| matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-'))
+            .is_some()
+        {}
+
+        self.inner.peek().map(|(i, _)| *i).unwrap_or(self.s.len())
+    }
+
+    // skips whitespace and comments. an unterminated block comment is returned as a token,
+    // as well as any comment if they're kept.
+    fn skip_ignored(&mut self) -> Option<(AbabaToken<'a>, Span)> {
+        loop {
+            // basically just an awkward take_while() that uses peeking
+            while self.inner.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+
+            let &(start, '/') = self.inner.peek()? else {
+                return None;
+            };
+
+            let rest = &self.s[start..];
+            let end = if rest.starts_with("//") {
+                while self.inner.next_if(|(_, c)| *c != '\n').is_some() {}
+                self.inner.peek().map(|(i, _)| *i).unwrap_or(self.s.len())
+            } else if rest.starts_with("/*") {
+                match rest.find("*/") {
+                    Some(end) => {
+                        self.skip_to(start + end + 2);
+                        start + end + 2
+                    }
+                    None => {
+                        self.skip_to(self.s.len());
+                        let span = Span::new(start, self.s.len());
+                        return Some((AbabaToken::UnterminatedComment, span));
+                    }
+                }
+            } else {
+                return None;
+            };
+
+            if self.comments {
+                let comment = AbabaToken::Comment(&self.s[start..end]);
+                return Some((comment, Span::new(start, end)));
+            }
         }
     }
 }
 
 impl<'a> Iterator for AbabaTokenizer<'a> {
-    type Item = AbabaToken<'a>;
+    type Item = (AbabaToken<'a>, Span);
 
     fn next(&mut self) -> Option<Self::Item> {
-        // ignore whitespace!
-        // basically just an awkward take_while() that uses peeking
-        while self.inner.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        // ignore whitespace and comments!
+        if let Some(comment) = self.skip_ignored() {
+            return Some(comment);
+        }
 
         use AbabaToken::*;
-        let tok = match self.inner.next()? {
+        let (start, c) = self.inner.next()?;
+        let tok = match (start, c) {
             (_, '{') => LeftBrace,
             (_, '}') => RightBrace,
             (_, '[') => LeftBracket,
@@ -53,37 +217,142 @@ impl<'a> Iterator for AbabaTokenizer<'a> {
             (_, ')') => RightParen,
             (_, ',') => ListSeparator,
             (_, ':') => FieldSeparator,
-            (i, '-' | '0'..='9') => {
-                let start = i;
-
+            (_, '=') => Equals,
+            (_, ';') => Semicolon,
+            (_, '+') => Plus,
+            (_, '-') => Minus,
+            (_, '*') => Star,
+            // comments have already been skipped
+            (_, '/') => Slash,
+            (_, '.') if self.inner.next_if(|(_, c)| *c == '.').is_some() => DotDot,
+            // `$` and `&` have to be followed by the name right away
+            (i, '$' | '&') if self.inner.peek().is_some_and(|(_, c)| is_ident_start(*c)) => {
+                let name = &self.s[i + 1..self.ident_end()];
+                match c {
+                    '$' => Reference(name),
+                    _ => Anchor(name),
+                }
+            }
+            // the sign is a unary minus, not part of the number
+            (i, '0'..='9') => {
+                let (len, valid) = number_len(&self.s[i..]);
+                self.skip_to(i + len);
+                match valid {
+                    true => Number(&self.s[i..i + len]),
+                    false => InvalidNumber(&self.s[i..i + len]),
+                }
+            }
+            (i, '#')
+                if self
+                    .inner
+                    .peek()
+                    .is_some_and(|(_, c)| c.is_ascii_alphanumeric()) =>
+            {
                 while self
                     .inner
-                    .next_if(|(_, c)| matches!(c, '0'..='9' | '.' | '-' | '_'))
+                    .next_if(|(_, c)| c.is_ascii_alphanumeric())
                     .is_some()
                 {}
-
-                let end = *self.inner.peek().map(|(i, _)| i).unwrap_or(&self.s.len());
-
-                Number(&self.s[start..end])
+                let end = self.inner.peek().map(|(i, _)| *i).unwrap_or(self.s.len());
+                let digits = &self.s[i + 1..end];
+                match digits.len() {
+                    6 | 8 if digits.chars().all(|c| c.is_ascii_hexdigit()) => Color(digits),
+                    _ => InvalidColor(digits),
+                }
             }
-            (i, 'a'..='z' | 'A'..='Z' | '_') => {
-                let start = i;
+            (i, c) if is_ident_start(c) => match &self.s[i..self.ident_end()] {
+                "true" => Bool(true),
+                "false" => Bool(false),
+                "let" => Let,
+                "include" => Include,
+                "import" => Import,
+                "for" => For,
+                "in" => In,
+                "repeat" => Repeat,
+                ident => Ident(ident),
+            },
+            (i, '"') => loop {
+                match self.inner.next() {
+                    // skip the escaped char, so an escaped quote doesn't end the string
+                    Some((_, '\\')) => {
+                        self.inner.next();
+                    }
+                    Some((end, '"')) => break Str(&self.s[i + 1..end]),
+                    Some(_) => {}
+                    None => break UnterminatedString,
+                }
+            },
+            (_, c) => UnknownChar(c),
+        };
 
-                while self
-                    .inner
-                    .next_if(|(_, c)| matches!(c, 'a'..='z' | 'A'..='Z' | '_' | '-'))
-                    .is_some()
-                {}
+        let end = self.inner.peek().map(|(i, _)| *i).unwrap_or(self.s.len());
+        Some((tok, Span::new(start, end)))
+    }
+}
 
-                let end = *self.inner.peek().map(|(i, _)| i).unwrap_or(&self.s.len());
+// the length of the number at the start of `s`, and whether it's valid. anything directly
+// following the number that could be mistaken for a part of it makes it invalid and is included,
+// unless it's the suffix of a unit after a decimal number.
+fn number_len(s: &str) -> (usize, bool) {
+    let bytes = s.as_bytes();
+    // the end of the digits (and `_`) starting at `i`, and whether there was any digit
+    let digits = |mut i: usize, radix: u32| {
+        let mut any = false;
+        while let Some(&b) = bytes.get(i) {
+            match b {
+                b'_' => {}
+                b if (b as char).is_digit(radix) => any = true,
+                _ => break,
+            }
+            i += 1;
+        }
+        (i, any)
+    };
 
-                Ident(&self.s[start..end])
+    let decimal = !matches!(bytes, [b'0', b'x' | b'X' | b'b' | b'B', ..]);
+    let (end, valid) = match bytes {
+        [b'0', b'x' | b'X', ..] => digits(2, 16),
+        [b'0', b'b' | b'B', ..] => digits(2, 2),
+        _ => {
+            let (mut end, _) = digits(0, 10);
+            // `1..` is a number followed by `..`
+            if bytes.get(end) == Some(&b'.') && bytes.get(end + 1) != Some(&b'.') {
+                end = digits(end + 1, 10).0;
             }
-            (_, c) => UnknownChar(c),
-        };
+            match bytes.get(end) {
+                Some(b'e' | b'E') => {
+                    let sign = usize::from(matches!(bytes.get(end + 1), Some(b'+' | b'-')));
+                    digits(end + 1 + sign, 10)
+                }
+                _ => (end, true),
+            }
+        }
+    };
 
-        Some(tok)
+    let mut rest = end;
+    while let Some(&b) = bytes.get(rest) {
+        match b {
+            b'.' if bytes.get(rest + 1) == Some(&b'.') => break,
+            b'.' | b'_' => {}
+            b if b.is_ascii_alphanumeric() => {}
+            _ => break,
+        }
+        rest += 1;
     }
+
+    let suffix = decimal && is_suffix(&s[end..rest]);
+    (rest, valid && (rest == end || suffix))
+}
+
+fn is_ident_start(c: char) -> bool {
+    matches!(c, 'a'..='z' | 'A'..='Z' | '_')
+}
+
+/// whether `s` is a single ident, e.g. a valid field name
+pub(crate) fn is_ident(s: &str) -> bool {
+    let mut tokens = AbabaTokenizer::new(s);
+    matches!(tokens.next(), Some((AbabaToken::Ident(ident), _)) if ident == s)
+        && tokens.next().is_none()
 }
 
 #[cfg(test)]
@@ -93,7 +362,7 @@ mod tests {
     #[test]
     fn basic() {
         use crate::tokenizer::AbabaToken::*;
-        let mut tok = AbabaTokenizer::new("{}[](),:   -123.456ababa");
+        let mut tok = AbabaTokenizer::new("{}[](),:   -123.456 ababa").map(|(t, _)| t);
         assert_eq!(tok.next(), Some(LeftBrace));
         assert_eq!(tok.next(), Some(RightBrace));
         assert_eq!(tok.next(), Some(LeftBracket));
@@ -102,7 +371,8 @@ mod tests {
         assert_eq!(tok.next(), Some(RightParen));
         assert_eq!(tok.next(), Some(ListSeparator));
         assert_eq!(tok.next(), Some(FieldSeparator));
-        assert_eq!(tok.next(), Some(Number("-123.456")));
+        assert_eq!(tok.next(), Some(Minus));
+        assert_eq!(tok.next(), Some(Number("123.456")));
         assert_eq!(tok.next(), Some(Ident("ababa")));
         assert_eq!(tok.next(), None);
     }
@@ -110,7 +380,7 @@ mod tests {
     #[test]
     fn list() {
         use crate::tokenizer::AbabaToken::*;
-        let mut tok = AbabaTokenizer::new("[1.0, 2, 3,4,5]");
+        let mut tok = AbabaTokenizer::new("[1.0, 2, 3,4,5]").map(|(t, _)| t);
         assert_eq!(tok.next(), Some(LeftBracket));
         assert_eq!(tok.next(), Some(Number("1.0")));
         assert_eq!(tok.next(), Some(ListSeparator));
@@ -125,14 +395,240 @@ mod tests {
         assert_eq!(tok.next(), None);
     }
 
+    #[test]
+    fn operators() {
+        use crate::tokenizer::AbabaToken::*;
+        let mut tok = AbabaTokenizer::new("1+-2*3/4-5 -pi").map(|(t, _)| t);
+        assert_eq!(tok.next(), Some(Number("1")));
+        assert_eq!(tok.next(), Some(Plus));
+        assert_eq!(tok.next(), Some(Minus));
+        assert_eq!(tok.next(), Some(Number("2")));
+        assert_eq!(tok.next(), Some(Star));
+        assert_eq!(tok.next(), Some(Number("3")));
+        assert_eq!(tok.next(), Some(Slash));
+        assert_eq!(tok.next(), Some(Number("4")));
+        assert_eq!(tok.next(), Some(Minus));
+        assert_eq!(tok.next(), Some(Number("5")));
+        assert_eq!(tok.next(), Some(Minus));
+        assert_eq!(tok.next(), Some(Ident("pi")));
+        assert_eq!(tok.next(), None);
+    }
+
     #[test]
     fn idents() {
         use crate::tokenizer::AbabaToken::*;
-        let mut tok = AbabaTokenizer::new("-aba-ba ababa _aba_ba");
-        assert_eq!(tok.next(), Some(Number("-")));
+        let mut tok = AbabaTokenizer::new("-aba-ba ababa _aba_ba a1-2b v2").map(|(t, _)| t);
+        assert_eq!(tok.next(), Some(Minus));
         assert_eq!(tok.next(), Some(Ident("aba-ba")));
         assert_eq!(tok.next(), Some(Ident("ababa")));
         assert_eq!(tok.next(), Some(Ident("_aba_ba")));
+        assert_eq!(tok.next(), Some(Ident("a1-2b")));
+        assert_eq!(tok.next(), Some(Ident("v2")));
+        assert_eq!(tok.next(), None);
+    }
+
+    #[test]
+    fn numbers() {
+        use crate::tokenizer::AbabaToken::*;
+        let valid = [
+            "0",
+            "1_000",
+            "1.",
+            "1.5",
+            "1e3",
+            "2.5E-3",
+            "1e+3",
+            "1_0.0_1e1_0",
+            "0xfF",
+            "0X_10",
+            "0b101",
+            "0B1_0",
+            "45deg",
+            "0.5rad",
+            "1.5e2mm",
+            "10cm",
+            "2m",
+        ];
+        for s in valid {
+            let mut tok = AbabaTokenizer::new(s).map(|(t, _)| t);
+            assert_eq!(tok.next(), Some(Number(s)));
+            assert_eq!(tok.next(), None);
+        }
+
+        let invalid = [
+            "1.2.3", "1e", "1e-", "1.5.", "10px", "2pi", "1_a", "0x", "0xg", "0x1.5", "0b", "0b12",
+            "1__e_", "1degs", "0x1m", "0b1cm", "1e5cm.5",
+        ];
+        for s in invalid {
+            let mut tok = AbabaTokenizer::new(s).map(|(t, _)| t);
+            assert_eq!(tok.next(), Some(InvalidNumber(s)));
+            assert_eq!(tok.next(), None);
+        }
+
+        // a number ends before `..`, a sign or anything else that can't be part of it
+        let mut tok = AbabaTokenizer::new("0..1e-2-3 1.5e2..x 4ä");
+        assert_eq!(tok.next(), Some((Number("0"), Span::new(0, 1))));
+        assert_eq!(tok.next(), Some((DotDot, Span::new(1, 3))));
+        assert_eq!(tok.next(), Some((Number("1e-2"), Span::new(3, 7))));
+        assert_eq!(tok.next(), Some((Minus, Span::new(7, 8))));
+        assert_eq!(tok.next(), Some((Number("3"), Span::new(8, 9))));
+        assert_eq!(tok.next(), Some((Number("1.5e2"), Span::new(10, 15))));
+        assert_eq!(tok.next(), Some((DotDot, Span::new(15, 17))));
+        assert_eq!(tok.next(), Some((Ident("x"), Span::new(17, 18))));
+        assert_eq!(tok.next(), Some((Number("4"), Span::new(19, 20))));
+        assert_eq!(tok.next(), Some((UnknownChar('ä'), Span::new(20, 22))));
+        assert_eq!(tok.next(), None);
+    }
+
+    #[test]
+    fn spans() {
+        use crate::tokenizer::AbabaToken::*;
+        let mut tok = AbabaTokenizer::new(" ababa:\n\t-1.5 ,ä}");
+        assert_eq!(tok.next(), Some((Ident("ababa"), Span::new(1, 6))));
+        assert_eq!(tok.next(), Some((FieldSeparator, Span::new(6, 7))));
+        assert_eq!(tok.next(), Some((Minus, Span::new(9, 10))));
+        assert_eq!(tok.next(), Some((Number("1.5"), Span::new(10, 13))));
+        assert_eq!(tok.next(), Some((ListSeparator, Span::new(14, 15))));
+        assert_eq!(tok.next(), Some((UnknownChar('ä'), Span::new(15, 17))));
+        assert_eq!(tok.next(), Some((RightBrace, Span::new(17, 18))));
+        assert_eq!(tok.next(), None);
+    }
+
+    #[test]
+    fn comments() {
+        use crate::tokenizer::AbabaToken::*;
+        let mut tok = AbabaTokenizer::new(
+            "// line comment\n[1, // another one\n/* block\n comment */ 2 /**/]/",
+        )
+        .map(|(t, _)| t);
+        assert_eq!(tok.next(), Some(LeftBracket));
+        assert_eq!(tok.next(), Some(Number("1")));
+        assert_eq!(tok.next(), Some(ListSeparator));
+        assert_eq!(tok.next(), Some(Number("2")));
+        assert_eq!(tok.next(), Some(RightBracket));
+        assert_eq!(tok.next(), Some(Slash));
+        assert_eq!(tok.next(), None);
+
+        let mut tok = AbabaTokenizer::new("1 /* never closed");
+        assert_eq!(tok.next(), Some((Number("1"), Span::new(0, 1))));
+        assert_eq!(tok.next(), Some((UnterminatedComment, Span::new(2, 17))));
+        assert_eq!(tok.next(), None);
+    }
+
+    #[test]
+    fn kept_comments() {
+        use crate::tokenizer::AbabaToken::*;
+        let mut tok = AbabaTokenizer::with_comments("// a\n[1, /* b */ 2] // c");
+        assert_eq!(tok.next(), Some((Comment("// a"), Span::new(0, 4))));
+        assert_eq!(tok.next(), Some((LeftBracket, Span::new(5, 6))));
+        assert_eq!(tok.next(), Some((Number("1"), Span::new(6, 7))));
+        assert_eq!(tok.next(), Some((ListSeparator, Span::new(7, 8))));
+        assert_eq!(tok.next(), Some((Comment("/* b */"), Span::new(9, 16))));
+        assert_eq!(tok.next(), Some((Number("2"), Span::new(17, 18))));
+        assert_eq!(tok.next(), Some((RightBracket, Span::new(18, 19))));
+        assert_eq!(tok.next(), Some((Comment("// c"), Span::new(20, 24))));
+        assert_eq!(tok.next(), None);
+
+        let mut tok = AbabaTokenizer::with_comments("/* never closed");
+        assert_eq!(tok.next(), Some((UnterminatedComment, Span::new(0, 15))));
+        assert_eq!(tok.next(), None);
+    }
+
+    #[test]
+    fn bindings() {
+        use crate::tokenizer::AbabaToken::*;
+        let mut tok = AbabaTokenizer::new("let a-b = &c $d; letter $ & $1").map(|(t, _)| t);
+        assert_eq!(tok.next(), Some(Let));
+        assert_eq!(tok.next(), Some(Ident("a-b")));
+        assert_eq!(tok.next(), Some(Equals));
+        assert_eq!(tok.next(), Some(Anchor("c")));
+        assert_eq!(tok.next(), Some(Reference("d")));
+        assert_eq!(tok.next(), Some(Semicolon));
+        assert_eq!(tok.next(), Some(Ident("letter")));
+        assert_eq!(tok.next(), Some(UnknownChar('$')));
+        assert_eq!(tok.next(), Some(UnknownChar('&')));
+        assert_eq!(tok.next(), Some(UnknownChar('$')));
+        assert_eq!(tok.next(), Some(Number("1")));
+        assert_eq!(tok.next(), None);
+    }
+
+    #[test]
+    fn spread() {
+        use crate::tokenizer::AbabaToken::*;
+        let mut tok = AbabaTokenizer::new("[.., 1] ... .").map(|(t, _)| t);
+        assert_eq!(tok.next(), Some(LeftBracket));
+        assert_eq!(tok.next(), Some(DotDot));
+        assert_eq!(tok.next(), Some(ListSeparator));
+        assert_eq!(tok.next(), Some(Number("1")));
+        assert_eq!(tok.next(), Some(RightBracket));
+        assert_eq!(tok.next(), Some(DotDot));
+        assert_eq!(tok.next(), Some(UnknownChar('.')));
+        assert_eq!(tok.next(), Some(UnknownChar('.')));
+        assert_eq!(tok.next(), None);
+    }
+
+    #[test]
+    fn includes() {
+        use crate::tokenizer::AbabaToken::*;
+        let mut tok = AbabaTokenizer::new(r#"import "a"; include "b" importer"#).map(|(t, _)| t);
+        assert_eq!(tok.next(), Some(Import));
+        assert_eq!(tok.next(), Some(Str("a")));
+        assert_eq!(tok.next(), Some(Semicolon));
+        assert_eq!(tok.next(), Some(Include));
+        assert_eq!(tok.next(), Some(Str("b")));
+        assert_eq!(tok.next(), Some(Ident("importer")));
+        assert_eq!(tok.next(), None);
+    }
+
+    #[test]
+    fn loops() {
+        use crate::tokenizer::AbabaToken::*;
+        let mut tok =
+            AbabaTokenizer::new("for i in 0..$n { repeat 2 {} } fork inside").map(|(t, _)| t);
+        assert_eq!(tok.next(), Some(For));
+        assert_eq!(tok.next(), Some(Ident("i")));
+        assert_eq!(tok.next(), Some(In));
+        assert_eq!(tok.next(), Some(Number("0")));
+        assert_eq!(tok.next(), Some(DotDot));
+        assert_eq!(tok.next(), Some(Reference("n")));
+        assert_eq!(tok.next(), Some(LeftBrace));
+        assert_eq!(tok.next(), Some(Repeat));
+        assert_eq!(tok.next(), Some(Number("2")));
+        assert_eq!(tok.next(), Some(LeftBrace));
+        assert_eq!(tok.next(), Some(RightBrace));
+        assert_eq!(tok.next(), Some(RightBrace));
+        assert_eq!(tok.next(), Some(Ident("fork")));
+        assert_eq!(tok.next(), Some(Ident("inside")));
+        assert_eq!(tok.next(), None);
+    }
+
+    #[test]
+    fn strings_and_bools() {
+        use crate::tokenizer::AbabaToken::*;
+        let mut tok = AbabaTokenizer::new(r#""ababa" "a\"b\\" true false truely "open"#);
+        assert_eq!(tok.next(), Some((Str("ababa"), Span::new(0, 7))));
+        assert_eq!(tok.next(), Some((Str(r#"a\"b\\"#), Span::new(8, 16))));
+        assert_eq!(tok.next(), Some((Bool(true), Span::new(17, 21))));
+        assert_eq!(tok.next(), Some((Bool(false), Span::new(22, 27))));
+        assert_eq!(tok.next(), Some((Ident("truely"), Span::new(28, 34))));
+        assert_eq!(tok.next(), Some((UnterminatedString, Span::new(35, 40))));
+        assert_eq!(tok.next(), None);
+    }
+
+    #[test]
+    fn colors() {
+        use crate::tokenizer::AbabaToken::*;
+        let mut tok = AbabaTokenizer::new("#ffCC00 #12345678,#12345 #ffcc0g # #");
+        assert_eq!(tok.next(), Some((Color("ffCC00"), Span::new(0, 7))));
+        assert_eq!(tok.next(), Some((Color("12345678"), Span::new(8, 17))));
+        assert_eq!(tok.next(), Some((ListSeparator, Span::new(17, 18))));
+        assert_eq!(tok.next(), Some((InvalidColor("12345"), Span::new(18, 24))));
+        assert_eq!(
+            tok.next(),
+            Some((InvalidColor("ffcc0g"), Span::new(25, 32)))
+        );
+        assert_eq!(tok.next(), Some((UnknownChar('#'), Span::new(33, 34))));
+        assert_eq!(tok.next(), Some((UnknownChar('#'), Span::new(35, 36))));
         assert_eq!(tok.next(), None);
     }
 }
@@ -0,0 +1,140 @@
+//! units of numbers, written as a suffix like `45deg`, `0.5rad`, `10cm` or `2m`.
+//!
+//! numbers with a unit are converted to radians or meters while parsing. they remember which kind
+//! of quantity they are in [crate::AbabaValue::unit], so a field can require one with
+//! [crate::AbabaConstraint::Unit]. numbers without a suffix are already taken to be in radians or
+//! meters.
+
+use crate::expr::Operator;
+use crate::{AbabaParseError, Span};
+use ababa_config_proc::AbabaSerialize;
+use std::f64::consts::PI;
+use std::fmt::{Display, Formatter};
+
+/// the kind of quantity a number with a unit suffix is
+#[derive(Debug, Copy, Clone, Eq, PartialEq, AbabaSerialize)]
+pub enum AbabaUnit {
+    /// `deg` or `rad`, in radians
+    Angle,
+    /// `mm`, `cm` or `m`, in meters
+    Length,
+}
+
+impl AbabaUnit {
+    // with an article, for error messages. `None` is a number without a unit.
+    pub(crate) fn describe(unit: Option<AbabaUnit>) -> &'static str {
+        match unit {
+            Some(AbabaUnit::Angle) => "an angle",
+            Some(AbabaUnit::Length) => "a length",
+            None => "a number without a unit",
+        }
+    }
+}
+
+impl Display for AbabaUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AbabaUnit::Angle => write!(f, "angle"),
+            AbabaUnit::Length => write!(f, "length"),
+        }
+    }
+}
+
+// the suffixes with their unit and the factor to radians or meters. `m` comes after the longer
+// suffixes ending in it.
+const SUFFIXES: [(&str, AbabaUnit, f64); 5] = [
+    ("deg", AbabaUnit::Angle, PI / 180.),
+    ("rad", AbabaUnit::Angle, 1.),
+    ("mm", AbabaUnit::Length, 0.001),
+    ("cm", AbabaUnit::Length, 0.01),
+    ("m", AbabaUnit::Length, 1.),
+];
+
+/// whether `s` is the suffix of a unit
+pub(crate) fn is_suffix(s: &str) -> bool {
+    SUFFIXES.iter().any(|(suffix, ..)| *suffix == s)
+}
+
+/// splits a decimal number into the number and the unit and factor of its suffix, if it has one
+pub(crate) fn split_suffix(s: &str) -> (&str, Option<(AbabaUnit, f64)>) {
+    for (suffix, unit, factor) in SUFFIXES {
+        if let Some(number) = s.strip_suffix(suffix) {
+            return (number, Some((unit, factor)));
+        }
+    }
+    (s, None)
+}
+
+/// the unit of `lhs op rhs`. numbers without a unit can be combined with any unit, two units only
+/// if it's the same one and they're added, subtracted or divided. `span` is the span of `rhs`,
+/// which is blamed for a mismatch.
+pub(crate) fn combine(
+    op: Operator,
+    lhs: Option<AbabaUnit>,
+    rhs: Option<AbabaUnit>,
+    span: Span,
+) -> Result<Option<AbabaUnit>, AbabaParseError> {
+    use Operator::*;
+    match (op, lhs, rhs) {
+        (_, lhs, None) => Ok(lhs),
+        (Add | Sub | Mul, None, rhs) => Ok(rhs),
+        (Add | Sub, Some(lhs), Some(rhs)) if lhs == rhs => Ok(Some(lhs)),
+        // a ratio
+        (Div, Some(lhs), Some(rhs)) if lhs == rhs => Ok(None),
+        (Add | Sub | Div, Some(lhs), Some(got)) => Err(AbabaParseError::UnitMismatch {
+            expected: Some(lhs),
+            got,
+            span,
+        }),
+        (Mul, Some(_), Some(got)) | (Div, None, Some(got)) => Err(AbabaParseError::UnitMismatch {
+            expected: None,
+            got,
+            span,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suffixes() {
+        assert_eq!(
+            split_suffix("90deg"),
+            ("90", Some((AbabaUnit::Angle, PI / 180.)))
+        );
+        assert_eq!(
+            split_suffix("1.5e2mm"),
+            ("1.5e2", Some((AbabaUnit::Length, 0.001)))
+        );
+        assert_eq!(split_suffix("2m"), ("2", Some((AbabaUnit::Length, 1.))));
+        assert_eq!(split_suffix("2"), ("2", None));
+        assert!(is_suffix("cm"));
+        assert!(!is_suffix("px"));
+    }
+
+    #[test]
+    fn combining() {
+        use AbabaUnit::*;
+        let span = Span::new(0, 0);
+        let combine = |op, lhs, rhs| combine(op, lhs, rhs, span).map_err(|e| e.to_string());
+
+        assert_eq!(combine(Operator::Add, Some(Length), None), Ok(Some(Length)));
+        assert_eq!(combine(Operator::Sub, None, Some(Angle)), Ok(Some(Angle)));
+        assert_eq!(combine(Operator::Mul, None, Some(Angle)), Ok(Some(Angle)));
+        assert_eq!(combine(Operator::Div, Some(Length), Some(Length)), Ok(None));
+        assert_eq!(
+            combine(Operator::Add, Some(Length), Some(Angle)),
+            Err("expected a length, got an angle".to_string())
+        );
+        assert_eq!(
+            combine(Operator::Mul, Some(Length), Some(Length)),
+            Err("expected a number without a unit, got a length".to_string())
+        );
+        assert_eq!(
+            combine(Operator::Div, None, Some(Angle)),
+            Err("expected a number without a unit, got an angle".to_string())
+        );
+    }
+}
@@ -0,0 +1,833 @@
+use ababa_config::{
+    AbabaConstraint, AbabaDescribe, AbabaFieldSchema, AbabaParseError, AbabaParser, AbabaPrinter,
+    AbabaSchema, AbabaSerialize, AbabaUnit, AbabaValue, AbabaVariantSchema,
+};
+use ababa_config_proc::{AbabaDeserialize, AbabaSerialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+fn parse<T: TryFrom<AbabaValue, Error = AbabaParseError>>(s: &str) -> Result<T, AbabaParseError> {
+    AbabaParser::new(s).parse()?.try_into()
+}
+
+// prints the value in all styles and checks that it's read back the same
+fn assert_round_trip<T>(x: &T)
+where
+    T: AbabaSerialize + TryFrom<AbabaValue, Error = AbabaParseError> + PartialEq + std::fmt::Debug,
+{
+    let value = x.to_ababa();
+    for printed in [
+        value.to_string(),
+        format!("{value:#}"),
+        AbabaPrinter::new()
+            .with_indent(" ")
+            .with_trailing_commas(false)
+            .print(&value)
+            .unwrap(),
+    ] {
+        assert_eq!(&parse::<T>(&printed).unwrap(), x, "printed as:\n{printed}");
+    }
+}
+
+#[derive(Debug, PartialEq, AbabaDeserialize, AbabaSerialize)]
+struct Sphere {
+    radius: f64,
+}
+
+#[derive(Debug, PartialEq, AbabaDeserialize, AbabaSerialize)]
+enum Shape {
+    Sphere(Sphere),
+    Cube { size: f64 },
+    Point(f64, f64, f64),
+    Empty,
+}
+
+#[test]
+fn enum_variants() {
+    assert_eq!(
+        parse::<Shape>("Cube { size: 2 }").unwrap(),
+        Shape::Cube { size: 2. }
+    );
+    assert_eq!(
+        parse::<Shape>("Point(1, 2, 3)").unwrap(),
+        Shape::Point(1., 2., 3.)
+    );
+    assert_eq!(parse::<Shape>("Empty").unwrap(), Shape::Empty);
+    assert_eq!(parse::<Shape>("Empty {}").unwrap(), Shape::Empty);
+    assert_eq!(parse::<Shape>("Empty()").unwrap(), Shape::Empty);
+}
+
+#[test]
+fn newtype_variants() {
+    let sphere = Shape::Sphere(Sphere { radius: 5. });
+    assert_eq!(parse::<Shape>("Sphere { radius: 5 }").unwrap(), sphere);
+    assert_eq!(parse::<Shape>("Sphere({ radius: 5 })").unwrap(), sphere);
+    assert_eq!(
+        parse::<Shape>("Sphere(Sphere { radius: 5 })").unwrap(),
+        sphere
+    );
+}
+
+#[test]
+fn enum_in_list() {
+    let shapes = parse::<Vec<Shape>>("[Empty, Point(0, 0, 0), Sphere { radius: 1 }]").unwrap();
+    assert_eq!(
+        shapes,
+        vec![
+            Shape::Empty,
+            Shape::Point(0., 0., 0.),
+            Shape::Sphere(Sphere { radius: 1. })
+        ]
+    );
+}
+
+#[test]
+fn enum_errors() {
+    let err = parse::<Shape>("Cone { height: 1 }").unwrap_err();
+    assert!(matches!(
+        &err,
+        AbabaParseError::UnknownVariant { got: Some(got), .. } if got == "Cone"
+    ));
+    assert_eq!(
+        err.to_string(),
+        "unknown type `Cone`, expected one of `Sphere`, `Cube`, `Point`, `Empty`"
+    );
+
+    assert!(matches!(
+        parse::<Shape>("{ size: 1 }"),
+        Err(AbabaParseError::UnknownVariant { got: None, .. })
+    ));
+    assert!(matches!(
+        parse::<Shape>("Point(1, 2)"),
+        Err(AbabaParseError::NotEnoughElements {
+            expected: 3,
+            got: 2,
+            ..
+        })
+    ));
+    assert!(matches!(
+        parse::<Shape>("Cube(1)"),
+        Err(AbabaParseError::ValueTypeDidNotMatch {
+            expected: "Object",
+            ..
+        })
+    ));
+
+    assert!(matches!(
+        parse::<Shape>("Point(1, 2, 3, 4)"),
+        Err(AbabaParseError::TooManyElements {
+            expected: 3,
+            got: 4,
+            ..
+        })
+    ));
+
+    let err = parse::<Shape>("Point(1, (2,), 3)").unwrap_err();
+    assert_eq!(err.path(), "[1]");
+
+    let err = parse::<Shape>("Sphere { radius: [] }").unwrap_err();
+    assert_eq!(err.path(), "radius");
+}
+
+#[derive(Debug, PartialEq, AbabaDeserialize, AbabaSerialize)]
+struct Group {
+    transform: Box<[[f64; 3]; 3]>,
+    parent: Option<Box<Group>>,
+    tags: Option<HashMap<String, (u8, String, bool)>>,
+}
+
+#[derive(Debug, PartialEq, AbabaDeserialize, AbabaSerialize)]
+enum Node {
+    Leaf(Box<Sphere>),
+    Pair(Box<Node>, Option<Box<Node>>),
+}
+
+#[test]
+fn generic_fields() {
+    let identity = [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+    let group = parse::<Group>(
+        "Group {
+            transform: [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+            parent: { transform: [[2, 0, 0], [0, 2, 0], [0, 0, 2]] },
+            tags: { a: (1, \"b\", true) },
+        }",
+    )
+    .unwrap();
+    assert_eq!(group.transform, Box::new(identity));
+    assert_eq!(group.parent.as_ref().unwrap().parent, None);
+    assert_eq!(
+        group.tags.as_ref().unwrap()["a"],
+        (1, "b".to_string(), true)
+    );
+    assert_round_trip(&group);
+
+    let group = parse::<Group>(
+        "{ transform: [[1, 0, 0], [0, 1, 0], [0, 0, 1]], parent: None, tags: None }",
+    )
+    .unwrap();
+    assert_eq!((group.parent, group.tags), (None, None));
+
+    let node = parse::<Node>("Pair(Leaf { radius: 1 }, Pair(Leaf { radius: 2 }, None))").unwrap();
+    assert_eq!(
+        node,
+        Node::Pair(
+            Box::new(Node::Leaf(Box::new(Sphere { radius: 1. }))),
+            Some(Box::new(Node::Pair(
+                Box::new(Node::Leaf(Box::new(Sphere { radius: 2. }))),
+                None
+            )))
+        )
+    );
+    assert_round_trip(&node);
+
+    let err = parse::<Group>("{ transform: [[1, 0, 0], [0, 1, 0], [0, 0]] }").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected 3 elements, got 2 (at `transform[2]`)"
+    );
+    let err = parse::<Group>("{ transform: [], tags: { a: (1, \"b\", true, 2) } }").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected 3 elements, got 0 (at `transform`)"
+    );
+}
+
+#[derive(Debug, PartialEq, AbabaDeserialize, AbabaSerialize)]
+struct Radius(f64);
+
+#[derive(Debug, PartialEq, AbabaDeserialize, AbabaSerialize)]
+struct Rgb(u8, u8, u8);
+
+#[derive(Debug, PartialEq, AbabaDeserialize, AbabaSerialize)]
+struct Marker;
+
+#[derive(Debug, PartialEq, AbabaDeserialize, AbabaSerialize)]
+struct Labeled<T, U>
+where
+    U: Clone,
+{
+    value: T,
+    tags: Vec<U>,
+}
+
+#[derive(Debug, PartialEq, AbabaDeserialize, AbabaSerialize)]
+struct Wrapper<T>(Box<T>);
+
+#[derive(Debug, PartialEq, AbabaDeserialize, AbabaSerialize)]
+struct Pair<T>(T, T);
+
+#[test]
+fn tuple_and_unit_structs() {
+    assert_eq!(parse::<Radius>("2.5").unwrap(), Radius(2.5));
+    assert_eq!(parse::<Rgb>("(1, 2, 3)").unwrap(), Rgb(1, 2, 3));
+    assert_eq!(parse::<Rgb>("Rgb(1, 2, 3)").unwrap(), Rgb(1, 2, 3));
+    assert_eq!(parse::<Marker>("Marker").unwrap(), Marker);
+    assert_eq!(parse::<Marker>("Marker {}").unwrap(), Marker);
+    assert_round_trip(&Radius(0.5));
+    assert_round_trip(&Rgb(255, 0, 128));
+    assert_round_trip(&Marker);
+
+    let errors = [
+        parse::<Radius>("[1]").unwrap_err(),
+        parse::<Rgb>("Hsv(1, 2, 3)").unwrap_err(),
+        parse::<Rgb>("(1, 2)").unwrap_err(),
+        parse::<Rgb>("(1, 2, 256)").unwrap_err(),
+        parse::<Marker>("Other").unwrap_err(),
+        parse::<Marker>("Marker(1)").unwrap_err(),
+    ];
+    assert_eq!(
+        errors.map(|e| e.to_string()),
+        [
+            "expected Number, got List",
+            "expected object of type Rgb, got Hsv",
+            "expected 3 elements, got 2",
+            "number 256 does not fit into u8 (at `[2]`)",
+            "unknown type `Other`, expected one of `Marker`",
+            "expected Ident, got Tuple",
+        ]
+    );
+
+    assert!(validate::<Vec<Radius>>("[1, 2]").is_empty());
+    assert_eq!(
+        validate::<(Rgb, Marker)>("((1, 2, 3, 4), Other)"),
+        [
+            "expected 3 elements, got 4 (at `[0]`)",
+            "unknown type `Other`, expected one of `Marker` (at `[1]`)",
+        ]
+    );
+}
+
+#[test]
+fn generics() {
+    let tagged = parse::<Labeled<Rgb, String>>("Labeled { value: (1, 2, 3), tags: [\"a\"] }");
+    assert_eq!(
+        tagged.unwrap(),
+        Labeled {
+            value: Rgb(1, 2, 3),
+            tags: vec!["a".to_string()],
+        }
+    );
+    assert_round_trip(&Labeled {
+        value: Some(1u8),
+        tags: vec!["a".to_string(), "b".to_string()],
+    });
+
+    let AbabaSchema::Struct(schema) = Labeled::<Option<[u8; 2]>, bool>::schema() else {
+        panic!("structs are described as structs");
+    };
+    assert_eq!(
+        schema.fields.iter().map(|f| f.ty).collect::<Vec<_>>(),
+        ["T", "Vec<U>"]
+    );
+
+    let wrapper = parse::<Wrapper<Pair<f64>>>("Pair(1, 2)").unwrap();
+    assert_eq!(wrapper, Wrapper(Box::new(Pair(1., 2.))));
+    assert_round_trip(&wrapper);
+    assert_eq!(
+        validate::<Labeled<Pair<u8>, bool>>("{ value: (1, -1), tags: [1] }"),
+        [
+            "number -1 does not fit into u8 (at `value[1]`)",
+            "expected Bool, got Number (at `tags[0]`)",
+        ]
+    );
+}
+
+fn default_focal_distance() -> f64 {
+    10.
+}
+
+#[derive(Debug, PartialEq, AbabaDeserialize, AbabaSerialize)]
+struct Lens {
+    #[ababa(default = "default_focal_distance")]
+    focal_distance: f64,
+    #[ababa(default)]
+    aperture: f64,
+}
+
+#[derive(Debug, PartialEq, AbabaDeserialize, AbabaSerialize)]
+struct Camera {
+    #[ababa(rename = "pos", alias = "position", alias = "origin")]
+    position: (f64, f64, f64),
+    #[ababa(flatten)]
+    lens: Lens,
+    label: Option<String>,
+    #[ababa(skip)]
+    frame_count: usize,
+}
+
+fn default_output() -> Option<String> {
+    Some("out.ppm".to_string())
+}
+
+#[derive(Debug, PartialEq, AbabaDeserialize, AbabaSerialize)]
+struct Output {
+    #[ababa(default = "default_output")]
+    path: Option<String>,
+    #[ababa(default)]
+    format: Option<String>,
+}
+
+#[test]
+fn field_attributes() {
+    assert_eq!(
+        parse::<Camera>("Camera { pos: (0, 0, -30), focal_distance: 5, label: \"main\" }").unwrap(),
+        Camera {
+            position: (0., 0., -30.),
+            lens: Lens {
+                focal_distance: 5.,
+                aperture: 0.
+            },
+            label: Some("main".to_string()),
+            frame_count: 0,
+        }
+    );
+    assert_eq!(
+        parse::<Camera>("{ origin: (1, 2, 3), aperture: 2 }").unwrap(),
+        Camera {
+            position: (1., 2., 3.),
+            lens: Lens {
+                focal_distance: 10.,
+                aperture: 2.
+            },
+            label: None,
+            frame_count: 0,
+        }
+    );
+}
+
+#[test]
+fn field_attribute_errors() {
+    // the rust name of a renamed field isn't accepted, only the rename and its aliases
+    assert!(matches!(
+        parse::<Camera>("{ positon: (1, 2, 3) }"),
+        Err(AbabaParseError::StructFieldNotPresent { field: "pos", .. })
+    ));
+    assert!(matches!(
+        parse::<Camera>("{ pos: (1, 2, 3), origin: (1, 2, 3) }"),
+        Err(AbabaParseError::DuplicateField { field, .. }) if field == "origin"
+    ));
+
+    // errors inside of aliased and flattened fields use the names from the input
+    let err = parse::<Camera>("{ origin: (1, 2) }").unwrap_err();
+    assert_eq!(err.path(), "origin");
+    let err = parse::<Camera>("{ pos: (1, 2, 3), aperture: [] }").unwrap_err();
+    assert_eq!(err.path(), "aperture");
+    let err = parse::<Camera>("{ pos: (1, 2, 3), label: 5 }").unwrap_err();
+    assert_eq!(err.path(), "label");
+}
+
+#[derive(Debug, PartialEq, AbabaDeserialize, AbabaSerialize)]
+struct Tagged {
+    name: String,
+    #[ababa(flatten)]
+    rest: std::collections::HashMap<String, f64>,
+}
+
+#[test]
+fn flatten_map() {
+    let tagged = parse::<Tagged>("{ name: \"ababa\", a: 1, b: 2 }").unwrap();
+    assert_eq!(tagged.name, "ababa");
+    assert_eq!(
+        tagged.rest,
+        [("a".to_string(), 1.), ("b".to_string(), 2.)].into()
+    );
+
+    let err = parse::<Tagged>("{ name: \"ababa\", a: \"1\" }").unwrap_err();
+    assert_eq!(err.path(), "a");
+}
+
+#[derive(Debug, PartialEq, AbabaDeserialize, AbabaSerialize)]
+#[ababa(deny_unknown_fields)]
+struct StrictSphere {
+    radius: f64,
+    #[ababa(alias = "colour")]
+    color: Option<(u8, u8, u8)>,
+    #[ababa(flatten)]
+    lens: Lens,
+}
+
+#[derive(Debug, PartialEq, AbabaDeserialize)]
+#[ababa(deny_unknown_fields)]
+enum StrictShape {
+    Cube { size: f64 },
+}
+
+#[test]
+fn unknown_fields() {
+    // unknown fields are ignored by default
+    assert_eq!(
+        parse::<Sphere>("{ radius: 1, radus: 2 }").unwrap(),
+        Sphere { radius: 1. }
+    );
+
+    assert!(parse::<StrictSphere>("{ radius: 1, colour: (1, 2, 3), aperture: 2 }").is_ok());
+
+    let s = "{ radus: 1 }";
+    let err = parse::<StrictSphere>(s).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown field `radus`, did you mean `radius`?"
+    );
+    assert_eq!(
+        err.with_source(s).to_string().lines().nth(1),
+        Some(" --> 1:10")
+    );
+
+    // fields of flattened types are suggested as well
+    let err = parse::<StrictSphere>("{ radius: 1, aprture: 2 }").unwrap_err();
+    assert!(matches!(
+        err,
+        AbabaParseError::UnknownField {
+            suggestion: Some("aperture"),
+            ..
+        }
+    ));
+
+    let err = parse::<StrictSphere>("{ radius: 1, texture: \"wood.ppm\" }").unwrap_err();
+    assert_eq!(err.to_string(), "unknown field `texture`");
+
+    let err = parse::<Vec<StrictShape>>("[Cube { size: 1 }, Cube { sise: 1 }]").unwrap_err();
+    assert_eq!(err.path(), "[1]");
+    assert_eq!(
+        err.to_string(),
+        "unknown field `sise`, did you mean `size`? (at `[1]`)"
+    );
+}
+
+#[test]
+fn included_files() {
+    let files: HashMap<PathBuf, String> = [
+        (
+            "shapes/sphere.ababa",
+            "let big = Sphere { radius: 10 }; $big",
+        ),
+        ("shapes/library.ababa", "let unit = Cube { size: 1 };"),
+        ("shapes/broken.ababa", "Cube { size: \"big\" }"),
+    ]
+    .into_iter()
+    .map(|(path, source)| (path.into(), source.to_string()))
+    .collect();
+    let parse = |s: &str| -> Result<Vec<Shape>, AbabaParseError> {
+        AbabaParser::new(s)
+            .with_file("scene.ababa")
+            .with_resolver(&files)
+            .parse()?
+            .try_into()
+    };
+
+    let shapes = parse(r#"import "shapes/library.ababa"; [include "shapes/sphere.ababa", $unit]"#);
+    assert_eq!(
+        shapes.unwrap(),
+        vec![
+            Shape::Sphere(Sphere { radius: 10. }),
+            Shape::Cube { size: 1. }
+        ]
+    );
+
+    // conversion errors point at the include
+    let s = r#"[Empty, include "shapes/broken.ababa"]"#;
+    let err = parse(s).unwrap_err();
+    assert_eq!(err.path(), "[1].size");
+    assert_eq!(
+        err.with_source(s).to_string().lines().nth(1),
+        Some(" --> 1:9")
+    );
+}
+
+#[test]
+fn serialize() {
+    assert_eq!(
+        Shape::Cube { size: 2. }.to_ababa().to_string(),
+        "Cube { size: 2 }"
+    );
+    assert_eq!(
+        Shape::Sphere(Sphere { radius: 1. }).to_ababa().to_string(),
+        "Sphere(Sphere { radius: 1 },)"
+    );
+    assert_eq!(Shape::Empty.to_ababa().to_string(), "Empty");
+
+    let shapes = vec![
+        Shape::Sphere(Sphere { radius: 1.5 }),
+        Shape::Cube { size: -2. },
+        Shape::Point(0.1, 0.2, 0.3),
+        Shape::Empty,
+    ];
+    assert_round_trip(&shapes);
+
+    let camera = Camera {
+        position: (0., 0., -30.),
+        lens: Lens {
+            focal_distance: 5.,
+            aperture: 0.25,
+        },
+        label: None,
+        frame_count: 12,
+    };
+    // renamed, flattened, skipped and missing optional fields
+    assert_eq!(
+        camera.to_ababa().to_string(),
+        "Camera { aperture: 0.25, focal_distance: 5, pos: (0, 0, -30) }"
+    );
+    assert_round_trip(&Camera {
+        label: Some("main \"camera\"".to_string()),
+        frame_count: 0,
+        ..camera
+    });
+
+    assert_round_trip(&Tagged {
+        name: "ababa".to_string(),
+        rest: [("a".to_string(), 1.), ("b".to_string(), 2.)].into(),
+    });
+    // `None` is only written out if leaving it out would be read as something else
+    let output = Output {
+        path: None,
+        format: None,
+    };
+    assert_eq!(output.to_ababa().to_string(), "Output { path: None }");
+    assert_round_trip(&output);
+    assert_round_trip(&Output {
+        path: Some("a.ppm".to_string()),
+        format: Some("ppm".to_string()),
+    });
+
+    assert_round_trip(&StrictSphere {
+        radius: 1.,
+        color: Some((255, 0, 128)),
+        lens: Lens {
+            focal_distance: 10.,
+            aperture: 0.,
+        },
+    });
+}
+
+// all errors of validating `s` against the schema of `T`
+fn validate<T: AbabaDescribe>(s: &str) -> Vec<String> {
+    let value = AbabaParser::new(s).parse().unwrap();
+    match T::schema().validate(&value) {
+        Ok(()) => vec![],
+        Err(errors) => errors.iter().map(|e| e.to_string()).collect(),
+    }
+}
+
+#[test]
+fn schema() {
+    let AbabaSchema::Struct(camera) = Camera::schema() else {
+        panic!("structs are described as structs");
+    };
+    assert_eq!(camera.name, "Camera");
+    assert_eq!(
+        camera.field_names(),
+        [
+            "pos",
+            "position",
+            "origin",
+            "focal_distance",
+            "aperture",
+            "label"
+        ]
+    );
+    assert_eq!(
+        camera.fields[1],
+        AbabaFieldSchema {
+            name: "focal_distance",
+            aliases: vec![],
+            ty: "f64",
+            schema: f64::schema(),
+            required: false,
+            default: Some("default_focal_distance"),
+            constraints: vec![],
+        }
+    );
+    assert_eq!(camera.fields[3].schema, AbabaSchema::String);
+    assert!(!camera.fields[3].required);
+    assert!(camera.fields[0].required);
+
+    let AbabaSchema::Enum { name, variants } = Shape::schema() else {
+        panic!("enums are described as enums");
+    };
+    assert_eq!(name, "Shape");
+    assert_eq!(
+        variants
+            .iter()
+            .map(AbabaVariantSchema::name)
+            .collect::<Vec<_>>(),
+        ["Sphere", "Cube", "Point", "Empty"]
+    );
+    assert_eq!(
+        variants[0],
+        AbabaVariantSchema::Newtype {
+            name: "Sphere",
+            inner: Box::new(Sphere::schema()),
+        }
+    );
+
+    let AbabaSchema::Struct(tagged) = Tagged::schema() else {
+        panic!("structs are described as structs");
+    };
+    assert_eq!(tagged.other_fields, Some(Box::new(f64::schema())));
+
+    // schemas can be printed, e.g. for documentation
+    assert_eq!(
+        Sphere::schema().to_ababa().to_string(),
+        "Struct(AbabaStructSchema { deny_unknown_fields: false, fields: [AbabaFieldSchema { \
+         aliases: [], constraints: [], name: \"radius\", required: true, schema: Number { ty: \
+         \"f64\" }, ty: \"f64\" }], name: \"Sphere\" },)"
+    );
+}
+
+#[test]
+fn validation() {
+    assert!(validate::<Camera>("{ pos: (0, 0, 0), label: \"main\", anything: 1 }").is_empty());
+    assert_eq!(
+        validate::<Camera>("Camera { origin: (0, 0), focal_distance: [], label: 5 }"),
+        [
+            "expected 3 elements, got 2 (at `origin`)",
+            "expected Number, got List (at `focal_distance`)",
+            "expected String, got Number (at `label`)",
+        ]
+    );
+    assert_eq!(
+        validate::<StrictSphere>("{ colour: (0, 0, 0), color: (0, 0, 300), radus: 1 }"),
+        [
+            "missing field `radius`",
+            "field `colour` is defined more than once",
+            "number 300 does not fit into u8 (at `color[2]`)",
+            "unknown field `radus`, did you mean `radius`?",
+        ]
+    );
+    assert_eq!(
+        validate::<Vec<Shape>>("[Cube { size: true }, Point(1, 2), Sphere { r: 1 }, Circle]"),
+        [
+            "expected Number, got Bool (at `[0].size`)",
+            "expected 3 elements, got 2 (at `[1]`)",
+            "missing field `radius` (at `[2]`)",
+            "unknown type `Circle`, expected one of `Sphere`, `Cube`, `Point`, `Empty` (at `[3]`)",
+        ]
+    );
+    assert_eq!(
+        validate::<StrictShape>("Cube { size: 1, sizes: 6 }"),
+        ["unknown field `sizes`, did you mean `size`?"]
+    );
+
+    // the first error is the one the conversion stops at
+    for s in [
+        "{ origin: (0, 0), label: 5 }",
+        "{ pos: (1, 2, 3), aperture: [] }",
+        "{ positon: (1, 2, 3) }",
+        "{ pos: (1, 2, 3), origin: (1, 2, 3) }",
+        "Lens { pos: (1, 2, 3) }",
+        "(1, 2, 3)",
+    ] {
+        let first = validate::<Camera>(s).into_iter().next();
+        assert_eq!(
+            first,
+            parse::<Camera>(s).err().map(|e| e.to_string()),
+            "{s}"
+        );
+    }
+}
+
+fn even(n: &u32) -> Result<(), String> {
+    match n % 2 {
+        0 => Ok(()),
+        _ => Err(format!("{n} is odd")),
+    }
+}
+
+#[derive(Debug, PartialEq, AbabaDeserialize)]
+struct Grid {
+    #[ababa(range = 0.0..=1.0)]
+    opacity: f64,
+    #[ababa(nonzero, validate = "even")]
+    cells: u32,
+    #[ababa(nonzero)]
+    direction: Option<(f64, f64)>,
+    #[ababa(range = -10..10, default)]
+    offset: i32,
+    #[ababa(validate = "even")]
+    layers: Option<u32>,
+}
+
+#[test]
+fn constraints() {
+    assert_eq!(
+        parse::<Grid>("{ opacity: 1, cells: 4, direction: (0, -1) }").unwrap(),
+        Grid {
+            opacity: 1.,
+            cells: 4,
+            direction: Some((0., -1.)),
+            offset: 0,
+            layers: None,
+        }
+    );
+    // an explicit `None` is neither converted nor validated
+    let grid = parse::<Grid>("{ opacity: 1, cells: 2, direction: None, layers: None }").unwrap();
+    assert_eq!((grid.direction, grid.layers), (None, None));
+    assert_eq!(
+        parse::<Grid>("{ opacity: 1, cells: 2, layers: 4 }")
+            .unwrap()
+            .layers,
+        Some(4)
+    );
+
+    let error = |s| parse::<Grid>(s).unwrap_err().to_string();
+    assert_eq!(
+        error("{ opacity: 1.5, cells: 4 }"),
+        "1.5 is not in the range 0..=1 (at `opacity`)"
+    );
+    assert_eq!(
+        error("{ opacity: 1, cells: 0 }"),
+        "must not be zero (at `cells`)"
+    );
+    assert_eq!(error("{ opacity: 1, cells: 3 }"), "3 is odd (at `cells`)");
+    assert_eq!(
+        error("{ opacity: 1, cells: 2, layers: 3 }"),
+        "3 is odd (at `layers`)"
+    );
+    assert_eq!(
+        error("{ opacity: 1, cells: 2, direction: (0, 0) }"),
+        "must not be zero (at `direction`)"
+    );
+    assert_eq!(
+        error("{ opacity: 1, cells: 2, offset: 10 }"),
+        "10 is not in the range -10..10 (at `offset`)"
+    );
+
+    let s = "{ opacity: 0, cells: 3 }";
+    let err = parse::<Grid>(s).unwrap_err();
+    assert!(matches!(
+        err.root(),
+        AbabaParseError::ConstraintViolated { span, .. } if &s[span.start..span.end] == "3"
+    ));
+
+    // the schema knows all constraints, but can only check the ones that don't need the rust value
+    let AbabaSchema::Struct(grid) = Grid::schema() else {
+        panic!("structs are described as structs");
+    };
+    assert_eq!(
+        grid.fields[1].constraints,
+        [
+            AbabaConstraint::NonZero,
+            AbabaConstraint::Validate { name: "even" }
+        ]
+    );
+    assert_eq!(
+        validate::<Grid>("{ opacity: -1, cells: 3, direction: (0, 0), offset: -11 }"),
+        [
+            "-1 is not in the range 0..=1 (at `opacity`)",
+            "must not be zero (at `direction`)",
+            "-11 is not in the range -10..10 (at `offset`)",
+        ]
+    );
+}
+
+#[derive(Debug, PartialEq, AbabaDeserialize)]
+struct Turn {
+    #[ababa(unit = "angle")]
+    angle: f64,
+    #[ababa(unit = "length", range = 0.0..)]
+    axis: (f64, f64, f64),
+    speed: f64,
+}
+
+#[test]
+fn units() {
+    let turn = parse::<Turn>("{ angle: 90deg - 0.5rad, axis: (0, 10cm, 2m), speed: 1cm / 2 }");
+    assert_eq!(
+        turn.unwrap(),
+        Turn {
+            angle: std::f64::consts::FRAC_PI_2 - 0.5,
+            axis: (0., 0.1, 2.),
+            speed: 0.005,
+        }
+    );
+
+    let error = |s| parse::<Turn>(s).unwrap_err().to_string();
+    assert_eq!(
+        error("{ angle: 10cm, axis: (0, 0, 1) }"),
+        "expected an angle, got a length (at `angle`)"
+    );
+    assert_eq!(
+        error("{ angle: 1, axis: (0, 1mm, 45deg), speed: 0 }"),
+        "expected a length, got an angle (at `axis`)"
+    );
+    assert_eq!(
+        error("{ angle: 1m * 2m, axis: (0, 0, 1) }"),
+        "expected a number without a unit, got a length"
+    );
+
+    let AbabaSchema::Struct(turn) = Turn::schema() else {
+        panic!("structs are described as structs");
+    };
+    assert_eq!(
+        turn.fields[0].constraints,
+        [AbabaConstraint::Unit(AbabaUnit::Angle)]
+    );
+    assert_eq!(
+        validate::<Turn>("{ angle: 1m, axis: (1deg, 0, 0), speed: 1rad }"),
+        [
+            "expected an angle, got a length (at `angle`)",
+            "expected a length, got an angle (at `axis`)",
+        ]
+    );
+}
@@ -1,15 +1,116 @@
+//! renders a scene of spheres and planes to `artifacts/sphere.ppm`.
+//!
+//! usage: `03_spheres_and_planes [--schema] [--set key.path=value]... [--param name=value]...
+//! [FILE]...`
+//!
+//! the scene files are merged on top of the built-in scene in order, followed by the overrides,
+//! e.g. `--set camera.screen_width=128`. all errors in the merged scene are reported at once.
+//! `--param quality=2` is the value of `param("quality", ..)` in the files and overrides.
+//! `--schema` prints the fields and scene objects a scene accepts.
+
+use ababa_config::{AbabaDescribe, AbabaLoader, AbabaParser, AbabaSerialize, AbabaValue};
 use raytracer::math::Vector3;
 use raytracer::ppm::{Pixel, PpmImage};
-use raytracer::raytracing::{Camera, InfinitePlane, SceneObject, Sphere};
+use raytracer::raytracing::{Camera, InfinitePlane, Scene, Sphere};
+use std::collections::HashMap;
 use std::error::Error;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: 03_spheres_and_planes [--schema] [--set key.path=value]... \
+                     [--param name=value]... [FILE]...";
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let mut loader = AbabaLoader::new().value(default_scene().to_ababa());
+    let mut overrides = Vec::new();
+    let mut params = HashMap::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--set" => match args.next() {
+                Some(assignment) => overrides.push(assignment),
+                None => {
+                    eprintln!("missing value for `--set`\n{USAGE}");
+                    return Ok(ExitCode::FAILURE);
+                }
+            },
+            "--param" => match args.next().as_deref().map(parse_param) {
+                Some(Ok((name, value))) => {
+                    params.insert(name, value);
+                }
+                Some(Err(message)) => {
+                    eprint!("{message}");
+                    return Ok(ExitCode::FAILURE);
+                }
+                None => {
+                    eprintln!("missing value for `--param`\n{USAGE}");
+                    return Ok(ExitCode::FAILURE);
+                }
+            },
+            "--schema" => {
+                println!("{:#}", Scene::schema().to_ababa());
+                return Ok(ExitCode::SUCCESS);
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(ExitCode::SUCCESS);
+            }
+            flag if flag.starts_with('-') => {
+                eprintln!("unknown flag `{flag}`\n{USAGE}");
+                return Ok(ExitCode::FAILURE);
+            }
+            file => loader = loader.file(file),
+        }
+    }
+
+    let loader = overrides
+        .into_iter()
+        .fold(loader.with_params(&params), AbabaLoader::set);
+    if let Err(errors) = loader.validate(&Scene::schema()) {
+        for e in errors {
+            eprint!("{}", e.diagnostic());
+        }
+        return Ok(ExitCode::FAILURE);
+    }
+    let scene: Scene = match loader.load() {
+        Ok(scene) => scene,
+        Err(e) => {
+            eprint!("{}", e.diagnostic());
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    let mut image = PpmImage::new(scene.camera.screen_width, scene.camera.screen_height);
 
-struct Scene {
-    pub camera: Camera,
-    pub scene_objects: Vec<Box<dyn SceneObject>>,
+    for x in 0..scene.camera.screen_width {
+        for y in 0..scene.camera.screen_height {
+            *image.get_mut_pixel(x, y).unwrap() = compute_pixel(&scene, x, y);
+        }
+    }
+
+    std::fs::write("artifacts/sphere.ppm", image.to_string())?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+// `name=value`, or the message to print
+fn parse_param(param: &str) -> Result<(String, AbabaValue), String> {
+    let Some((name, value)) = param.split_once('=') else {
+        return Err(format!(
+            "invalid parameter `{param}`, expected `name=value`\n{USAGE}\n"
+        ));
+    };
+    match AbabaParser::new(value).parse() {
+        Ok(value) => Ok((name.to_string(), value)),
+        Err(e) => Err(e
+            .with_source(value)
+            .with_file_name(&format!("<param {name}>"))
+            .to_string()),
+    }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let scene = Scene {
+fn default_scene() -> Scene {
+    Scene {
         camera: Camera {
             position: Vector3::new(0., 0., -30.),
             up: Vector3::new(0., 1., 0.),
@@ -37,19 +138,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                 material: Pixel::new(128, 128, 128),
             }),
         ],
-    };
-
-    let mut image = PpmImage::new(scene.camera.screen_width, scene.camera.screen_height);
-
-    for x in 0..scene.camera.screen_width {
-        for y in 0..scene.camera.screen_height {
-            *image.get_mut_pixel(x, y).unwrap() = compute_pixel(&scene, x, y);
-        }
     }
-
-    std::fs::write("artifacts/sphere.ppm", image.to_string())?;
-
-    Ok(())
 }
 
 fn compute_pixel(scene: &Scene, x: usize, y: usize) -> Pixel {
@@ -0,0 +1,20 @@
+//! a language server for scene files, see the `lsp` module of `ababa-config` for what it offers.
+//!
+//! usage: `ababa-lsp`
+//!
+//! the editor starts it and talks to it over stdin and stdout.
+
+use ababa_config::{AbabaDescribe, AbabaLanguageServer};
+use raytracer::raytracing::Scene;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut server = AbabaLanguageServer::new(Scene::schema());
+    match server.run(std::io::stdin().lock(), std::io::stdout().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("ababa-lsp: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use crate::math::Vector3;
 use crate::ppm::Pixel;
-use ababa_config::{AbabaParseError, AbabaValue};
+use ababa_config::{
+    AbabaDescribe, AbabaParseError, AbabaSchema, AbabaSerialize, AbabaValue, AbabaValueKind, Span,
+};
 
 impl TryFrom<AbabaValue> for Vector3 {
     type Error = AbabaParseError;
@@ -11,11 +13,255 @@ impl TryFrom<AbabaValue> for Vector3 {
     }
 }
 
+impl AbabaSerialize for Vector3 {
+    fn to_ababa(&self) -> AbabaValue {
+        (self.x, self.y, self.z).to_ababa()
+    }
+}
+
+impl AbabaDescribe for Vector3 {
+    fn schema() -> AbabaSchema {
+        <(f64, f64, f64)>::schema()
+    }
+}
+
+/// the CSS colors that can be used by name
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("black", 0x000000),
+    ("white", 0xffffff),
+    ("gray", 0x808080),
+    ("grey", 0x808080),
+    ("silver", 0xc0c0c0),
+    ("red", 0xff0000),
+    ("maroon", 0x800000),
+    ("crimson", 0xdc143c),
+    ("salmon", 0xfa8072),
+    ("coral", 0xff7f50),
+    ("orange", 0xffa500),
+    ("gold", 0xffd700),
+    ("yellow", 0xffff00),
+    ("olive", 0x808000),
+    ("lime", 0x00ff00),
+    ("green", 0x008000),
+    ("teal", 0x008080),
+    ("cyan", 0x00ffff),
+    ("aqua", 0x00ffff),
+    ("skyblue", 0x87ceeb),
+    ("blue", 0x0000ff),
+    ("navy", 0x000080),
+    ("indigo", 0x4b0082),
+    ("purple", 0x800080),
+    ("violet", 0xee82ee),
+    ("magenta", 0xff00ff),
+    ("fuchsia", 0xff00ff),
+    ("pink", 0xffc0cb),
+    ("brown", 0xa52a2a),
+];
+
+/// a pixel is read from
+/// - a tuple of bytes `(255, 204, 0)` or `Rgb(255, 204, 0)`, or a color literal `#ffcc00`
+/// - `#rrggbbaa` (or a tuple of four bytes) if it's opaque, pixels can't be transparent
+/// - a tuple of floats from 0 to 1 like `(1, 0.8, 0)`, which are scaled to bytes. it's floats as
+///   soon as any item has a fraction. only 0 and 1 like `(1, 0, 0)` could be either and is an
+///   error, that has to be `Rgb(1, 0, 0)` or `#ff0000`.
+/// - the name of a CSS color like `orange`, see `NAMED_COLORS`
 impl TryFrom<AbabaValue> for Pixel {
     type Error = AbabaParseError;
 
     fn try_from(value: AbabaValue) -> Result<Self, Self::Error> {
-        let (r, g, b) = value.try_into()?;
+        let span = value.span;
+        let items = match value.kind {
+            AbabaValueKind::Ident(name) => {
+                return match NAMED_COLORS.iter().find(|(n, _)| *n == name) {
+                    Some((_, rgb)) => {
+                        Ok(Pixel::new((rgb >> 16) as u8, (rgb >> 8) as u8, *rgb as u8))
+                    }
+                    None => Err(AbabaParseError::ConstraintViolated {
+                        message: format!("unknown color `{name}`"),
+                        span,
+                    }),
+                };
+            }
+            AbabaValueKind::Tuple {
+                struct_type: None,
+                items,
+            } => match floats(&items, span)? {
+                Some(pixel) => return Ok(pixel),
+                None => items,
+            },
+            AbabaValueKind::Tuple {
+                struct_type: Some(name),
+                items,
+            } => match name.as_str() {
+                "Rgb" => items,
+                _ => {
+                    return Err(AbabaParseError::StructTypeDidNotMatch {
+                        expected: "Rgb",
+                        got: Some(name),
+                        span,
+                    })
+                }
+            },
+            kind => {
+                let (r, g, b) = AbabaValue::new(kind, span).try_into()?;
+                return Ok(Pixel::new(r, g, b));
+            }
+        };
+
+        // u8 would round them down, which can't be what was meant
+        for (i, item) in items.iter().enumerate() {
+            if matches!(item.kind, AbabaValueKind::Number(x) if x.fract() != 0.) {
+                return Err(AbabaParseError::ConstraintViolated {
+                    message: "bytes have to be whole numbers".to_string(),
+                    span: item.span,
+                }
+                .at_index(i));
+            }
+        }
+
+        if items.len() == 4 {
+            let alpha = &items[3];
+            if alpha.kind != AbabaValueKind::Number(255.) {
+                return Err(AbabaParseError::ConstraintViolated {
+                    message: "pixels can't be transparent, the alpha has to be 255".to_string(),
+                    span: alpha.span,
+                }
+                .at_index(3));
+            }
+        }
+
+        let items = AbabaValueKind::Tuple {
+            struct_type: None,
+            items: items.into_iter().take(3).collect(),
+        };
+        let (r, g, b) = AbabaValue::new(items, span).try_into()?;
         Ok(Pixel::new(r, g, b))
     }
 }
+
+// `(r, g, b)` with floats from 0 to 1 if any of them has a fraction, `None` if it's bytes (or
+// anything else, which the bytes report)
+fn floats(items: &[AbabaValue], span: Span) -> Result<Option<Pixel>, AbabaParseError> {
+    let numbers: Option<Vec<f64>> = items
+        .iter()
+        .map(|item| match item.kind {
+            AbabaValueKind::Number(x) => Some(x),
+            _ => None,
+        })
+        .collect();
+    let Some(numbers) = numbers.filter(|numbers| numbers.len() == 3) else {
+        return Ok(None);
+    };
+
+    if numbers.iter().all(|x| x.fract() == 0.) {
+        // black is the same either way
+        if numbers.iter().all(|&x| x == 0. || x == 1.) && numbers.contains(&1.) {
+            let [r, g, b] = [0, 1, 2].map(|i| numbers[i] as u8 * 255);
+            return Err(AbabaParseError::ConstraintViolated {
+                message: format!(
+                    "`({}, {}, {})` could be bytes or floats from 0 to 1, write `Rgb(..)` for \
+                     bytes or `#{r:02x}{g:02x}{b:02x}` for floats",
+                    numbers[0], numbers[1], numbers[2]
+                ),
+                span,
+            });
+        }
+        return Ok(None);
+    }
+
+    let mut bytes = [0; 3];
+    for (i, (item, x)) in items.iter().zip(numbers).enumerate() {
+        if !(0. ..=1.).contains(&x) {
+            return Err(AbabaParseError::ConstraintViolated {
+                message: format!(
+                    "{x} can't be mixed with floats, a color with fractions has floats from 0 to 1"
+                ),
+                span: item.span,
+            }
+            .at_index(i));
+        }
+        bytes[i] = (x * 255.).round() as u8;
+    }
+    let [r, g, b] = bytes;
+    Ok(Some(Pixel::new(r, g, b)))
+}
+
+impl AbabaSerialize for Pixel {
+    fn to_ababa(&self) -> AbabaValue {
+        (self.r, self.g, self.b).to_ababa()
+    }
+}
+
+impl AbabaDescribe for Pixel {
+    // names, bytes and floats can't be told apart by a schema
+    fn schema() -> AbabaSchema {
+        AbabaSchema::Any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ababa_config::AbabaParser;
+
+    fn pixel(s: &str) -> Result<Pixel, String> {
+        let value = AbabaParser::new(s).parse().unwrap();
+        Pixel::try_from(value).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn colors() {
+        let orange = Pixel::new(255, 165, 0);
+        assert_eq!(pixel("(255, 165, 0)"), Ok(orange));
+        assert_eq!(pixel("#ffa500"), Ok(orange));
+        assert_eq!(pixel("#FFA500ff"), Ok(orange));
+        assert_eq!(pixel("(255, 165, 0, 255)"), Ok(orange));
+        assert_eq!(pixel("orange"), Ok(orange));
+        assert_eq!(pixel("Rgb(255, 165, 0)"), Ok(orange));
+        assert_eq!(pixel("(1, 0.647, 0)"), Ok(orange));
+        assert_eq!(pixel("(0.2, 0.5, 1.0)"), Ok(Pixel::new(51, 128, 255)));
+        assert_eq!(pixel("(0.5, 0.5, 0.5)"), Ok(Pixel::new(128, 128, 128)));
+        assert_eq!(pixel("(0, 0, 0)"), Ok(Pixel::BLACK));
+        assert_eq!(pixel("(2, 1, 1)"), Ok(Pixel::new(2, 1, 1)));
+        assert_eq!(pixel("Rgb(1, 1, 1)"), Ok(Pixel::new(1, 1, 1)));
+        assert_eq!(pixel("grey"), pixel("gray"));
+
+        assert_eq!(pixel("orang"), Err("unknown color `orang`".to_string()));
+        assert_eq!(
+            pixel("(1, 0, 1.0)"),
+            Err(
+                "`(1, 0, 1)` could be bytes or floats from 0 to 1, write `Rgb(..)` for bytes or \
+                 `#ff00ff` for floats"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            pixel("(0.5, 255, 0)"),
+            Err(
+                "255 can't be mixed with floats, a color with fractions has floats from 0 to 1 \
+                 (at `[1]`)"
+                    .to_string()
+            )
+        );
+        assert!(pixel("(0.5, -1, 0)").is_err());
+        assert_eq!(
+            pixel("Rgb(0.5, 1, 0)"),
+            Err("bytes have to be whole numbers (at `[0]`)".to_string())
+        );
+        assert!(pixel("(0.5, 1, 0, 1)").is_err());
+        assert_eq!(
+            pixel("Rgba(255, 0, 0)"),
+            Err("expected object of type Rgb, got Rgba".to_string())
+        );
+        assert_eq!(
+            pixel("#ffa50080"),
+            Err("pixels can't be transparent, the alpha has to be 255 (at `[3]`)".to_string())
+        );
+        assert_eq!(
+            pixel("(256, 0, 0)"),
+            Err("number 256 does not fit into u8 (at `[0]`)".to_string())
+        );
+        assert!(pixel("\"orange\"").is_err());
+        assert!(pixel("(255, 0)").is_err());
+    }
+}
@@ -1,15 +1,62 @@
 use crate::math::Vector3;
 use crate::ppm::Pixel;
-use ababa_config_proc::AbabaDeserialize;
+use ababa_config::{
+    AbabaDescribe, AbabaParseError, AbabaRegistry, AbabaSchema, AbabaSerialize, AbabaValue,
+};
+use ababa_config_proc::{AbabaDeserialize, AbabaSerialize};
+use std::sync::OnceLock;
 
-pub trait SceneObject {
+/// scene objects can be written back to a scene file, their [AbabaSerialize] impl has to produce
+/// a value that their constructor in [scene_object_registry] accepts
+pub trait SceneObject: AbabaSerialize {
     fn intersects_ray(&self, camera: &Camera, ray: &Vector3) -> Option<f64>;
     fn material(&self) -> &Pixel;
 }
 
-#[derive(Debug, AbabaDeserialize)]
+/// constructors for every [SceneObject] that can be used in a scene file, by type name
+pub fn scene_object_registry() -> &'static AbabaRegistry<dyn SceneObject> {
+    static REGISTRY: OnceLock<AbabaRegistry<dyn SceneObject>> = OnceLock::new();
+
+    REGISTRY.get_or_init(|| {
+        let mut registry = AbabaRegistry::<dyn SceneObject>::new();
+        registry
+            .register("Sphere", |v| Ok(Box::new(Sphere::try_from(v)?)))
+            .register("InfinitePlane", |v| {
+                Ok(Box::new(InfinitePlane::try_from(v)?))
+            })
+            .describe("Sphere", Sphere::schema)
+            .describe("InfinitePlane", InfinitePlane::schema);
+        registry
+    })
+}
+
+impl TryFrom<AbabaValue> for Box<dyn SceneObject> {
+    type Error = AbabaParseError;
+
+    fn try_from(value: AbabaValue) -> Result<Self, Self::Error> {
+        scene_object_registry().construct(value)
+    }
+}
+
+impl AbabaDescribe for Box<dyn SceneObject> {
+    fn schema() -> AbabaSchema {
+        scene_object_registry().schema("SceneObject")
+    }
+}
+
+#[derive(AbabaDeserialize, AbabaSerialize)]
+#[ababa(deny_unknown_fields)]
+pub struct Scene {
+    pub camera: Camera,
+    pub scene_objects: Vec<Box<dyn SceneObject>>,
+}
+
+#[derive(Debug, AbabaDeserialize, AbabaSerialize)]
+#[ababa(deny_unknown_fields)]
 pub struct Sphere {
+    #[ababa(unit = "length")]
     pub position: Vector3,
+    #[ababa(range = 0.0.., unit = "length")]
     pub radius: f64,
     pub material: Pixel,
 }
@@ -73,13 +120,25 @@ impl SceneObject for Sphere {
     }
 }
 
-#[derive(Debug, AbabaDeserialize)]
+#[derive(Debug, AbabaDeserialize, AbabaSerialize)]
+#[ababa(deny_unknown_fields)]
 pub struct InfinitePlane {
+    #[ababa(nonzero, validate = "unit_length")]
     pub normal: Vector3,
+    #[ababa(unit = "length")]
     pub offset: f64,
     pub material: Pixel,
 }
 
+// the distance in `intersects_ray` is only correct for normals of length 1
+fn unit_length(normal: &Vector3) -> Result<(), String> {
+    let length = normal.euclidean_norm();
+    match (length - 1.).abs() < 1e-6 {
+        true => Ok(()),
+        false => Err(format!("has length {length}, expected a normalized vector")),
+    }
+}
+
 impl SceneObject for InfinitePlane {
     // a plane is defined by the equation <v, normal> = d
     // once again we want to insert the ray equation and solve for t
@@ -115,13 +174,184 @@ impl SceneObject for InfinitePlane {
     }
 }
 
-#[derive(Debug, AbabaDeserialize)]
+#[derive(Debug, AbabaDeserialize, AbabaSerialize)]
+#[ababa(deny_unknown_fields)]
 pub struct Camera {
+    #[ababa(unit = "length")]
     pub position: Vector3,
+    #[ababa(nonzero)]
     pub up: Vector3,
+    #[ababa(nonzero)]
     pub right: Vector3,
+    #[ababa(nonzero)]
     pub forward: Vector3,
+    #[ababa(range = 0.0.., nonzero, unit = "length")]
     pub focal_distance: f64,
+    #[ababa(nonzero)]
     pub screen_width: usize,
+    #[ababa(nonzero)]
     pub screen_height: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ababa_config::{AbabaLoader, AbabaParser};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn parse_scene(s: &str) -> Result<Scene, AbabaParseError> {
+        AbabaParser::new(s).parse()?.try_into()
+    }
+
+    #[test]
+    fn scene_file() {
+        let scene = parse_scene(include_str!("scene.ababa")).unwrap();
+
+        assert_eq!(scene.camera.position, Vector3::new(0., 0., -30.));
+        assert_eq!(scene.camera.screen_width, 64);
+        assert_eq!(scene.scene_objects.len(), 3);
+        assert_eq!(scene.scene_objects[1].material(), &Pixel::new(0, 255, 0));
+        assert_eq!(
+            scene.scene_objects[2].material(),
+            &Pixel::new(128, 128, 128)
+        );
+    }
+
+    #[test]
+    fn colors() {
+        let s = include_str!("scene.ababa")
+            .replace("(255, 255, 255)", "white")
+            .replace("(0, 255, 0)", "#00ff00")
+            .replace("(128, 128, 128)", "(0.5, 0.5, 0.5)");
+        let scene = parse_scene(&s).unwrap();
+
+        assert_eq!(scene.scene_objects[0].material(), &Pixel::WHITE);
+        assert_eq!(scene.scene_objects[1].material(), &Pixel::new(0, 255, 0));
+        assert_eq!(
+            scene.scene_objects[2].material(),
+            &Pixel::new(128, 128, 128)
+        );
+
+        let value = AbabaParser::new(&s).parse().unwrap();
+        assert!(Scene::schema().validate(&value).is_ok());
+    }
+
+    #[test]
+    fn print_scene() {
+        let scene = parse_scene(include_str!("scene.ababa")).unwrap();
+        let printed = format!("{:#}", scene.to_ababa());
+        let reparsed = parse_scene(&printed).unwrap();
+
+        assert_eq!(reparsed.to_ababa(), scene.to_ababa());
+        assert_eq!(reparsed.camera.position, scene.camera.position);
+        assert_eq!(reparsed.scene_objects.len(), 3);
+        assert_eq!(
+            reparsed.scene_objects[2].material(),
+            &Pixel::new(128, 128, 128)
+        );
+    }
+
+    #[test]
+    fn layered_scene() {
+        let files = HashMap::from([
+            (PathBuf::from("scene.ababa"), include_str!("scene.ababa").into()),
+            (
+                PathBuf::from("shot.ababa"),
+                "{
+                    camera: { position: (0, 5, -40) },
+                    scene_objects: [.., Sphere { position: (5, 0, 0), radius: 1, material: (0, 0, 255) }],
+                }"
+                .into(),
+            ),
+        ]);
+        let scene: Scene = AbabaLoader::new()
+            .with_resolver(&files)
+            .file("scene.ababa")
+            .file("shot.ababa")
+            .set("camera.screen_width=128")
+            .load()
+            .unwrap();
+
+        assert_eq!(scene.camera.position, Vector3::new(0., 5., -40.));
+        assert_eq!(scene.camera.screen_width, 128);
+        assert_eq!(scene.camera.screen_height, 48);
+        assert_eq!(scene.scene_objects.len(), 4);
+        assert_eq!(scene.scene_objects[3].material(), &Pixel::new(0, 0, 255));
+    }
+
+    #[test]
+    fn unknown_scene_object() {
+        let s = include_str!("scene.ababa").replace("InfinitePlane", "Plane");
+        let err = parse_scene(&s).err().unwrap();
+
+        assert_eq!(err.path(), "scene_objects[2]");
+        assert!(matches!(
+            err.root(),
+            AbabaParseError::UnknownVariant { expected, .. } if expected == &["InfinitePlane", "Sphere"]
+        ));
+    }
+
+    #[test]
+    fn typo_in_field() {
+        let s = include_str!("scene.ababa").replace("radius: 5.0", "radus: 5.0");
+        let err = parse_scene(&s).err().unwrap();
+
+        assert_eq!(err.path(), "scene_objects[1]");
+        assert_eq!(
+            err.root().to_string(),
+            "unknown field `radus`, did you mean `radius`?"
+        );
+    }
+
+    #[test]
+    fn validate_scene() {
+        let s = include_str!("scene.ababa")
+            .replace("radius: 5.0", "radus: 5.0")
+            .replace("screen_width: 64", "screen_width: -64")
+            .replace("InfinitePlane", "Plane");
+        let value = AbabaParser::new(&s).parse().unwrap();
+        let errors = Scene::schema().validate(&value).unwrap_err();
+        let errors: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+
+        assert_eq!(
+            errors,
+            [
+                "number -64 does not fit into usize (at `camera.screen_width`)",
+                "missing field `radius` (at `scene_objects[1]`)",
+                "unknown field `radus`, did you mean `radius`? (at `scene_objects[1]`)",
+                "unknown type `Plane`, expected one of `InfinitePlane`, `Sphere` (at `scene_objects[2]`)",
+            ]
+        );
+
+        let value = AbabaParser::new(include_str!("scene.ababa"))
+            .parse()
+            .unwrap();
+        assert!(Scene::schema().validate(&value).is_ok());
+    }
+
+    #[test]
+    fn constraints() {
+        let error = |from, to| {
+            let s = include_str!("scene.ababa").replace(from, to);
+            parse_scene(&s).err().unwrap().to_string()
+        };
+
+        assert_eq!(
+            error("radius: 5.0", "radius: -5.0"),
+            "-5 is not in the range 0.. (at `scene_objects[1].radius`)"
+        );
+        assert_eq!(
+            error("focal_distance: 10.0", "focal_distance: 0"),
+            "must not be zero (at `camera.focal_distance`)"
+        );
+        assert_eq!(
+            error("normal: (0.0, 1.0, 0.0)", "normal: (0, 0, 0)"),
+            "must not be zero (at `scene_objects[2].normal`)"
+        );
+        assert_eq!(
+            error("normal: (0.0, 1.0, 0.0)", "normal: (0, 2, 0)"),
+            "has length 2, expected a normalized vector (at `scene_objects[2].normal`)"
+        );
+    }
+}